@@ -6,9 +6,11 @@ __all__ = [
     "FFSLevel",
     "FFSResult",
     "FFSRunConfig",
+    "ProofreadTileSet",
 ]
 
 import copy
+import json
 import numpy as np
 from . import rgrow as rgr
 from .rgrow import (
@@ -17,6 +19,7 @@ from .rgrow import (
     # FFSLevel,
     FFSResult,
     FFSRunConfig,
+    ProofreadTileSet as _ProofreadTileSet,
     System,
     State,
 )
@@ -246,6 +249,9 @@ class TileSet:
     size: Optional[int | tuple[int, int]] = None
     tau: Optional[float] = None
     smax: Optional[int] = None
+    smin: Optional[int] = None
+    tmax: Optional[float] = None
+    emax: Optional[int] = None
     update_rate: Optional[int] = None
     kf: Optional[float] = None
     fission: Optional[str] = None
@@ -253,6 +259,7 @@ class TileSet:
     chunk_handling: Optional[str] = None
     chunk_size: Optional[str] = None
     canvas_type: Optional[str] = None
+    rate_store: Optional[str] = None
     hdoubletiles: Optional[List[Tuple[str | int, str | int]]] = None
     vdoubletiles: Optional[List[Tuple[str | int, str | int]]] = None
     model: Optional[str] = None
@@ -269,6 +276,9 @@ class TileSet:
                 "size",
                 "tau",
                 "smax",
+                "smin",
+                "tmax",
+                "emax",
                 "update_rate",
                 "kf",
                 "fission",
@@ -276,6 +286,7 @@ class TileSet:
                 "chunk_handling",
                 "chunk_size",
                 "canvas_type",
+                "rate_store",
                 "cover_strands",
                 "hdoubletiles",
                 "vdoubletiles",
@@ -320,8 +331,61 @@ class TileSet:
     def run_window(self) -> tuple[System, State]:
         return self._to_rg_tileset().run_window()
 
+    def to_xgrow_string(self) -> str:
+        """Returns the tile set in Xgrow's .tiles format."""
+        return self._to_rg_tileset().to_xgrow_string()
+
+    def write_xgrow(self, path: str) -> None:
+        """Writes the tile set to an Xgrow .tiles file."""
+        self._to_rg_tileset().write_xgrow(path)
+
+    def write_tas(self, path: str) -> None:
+        """Writes the tile set as ISU TAS (and PyTAS) .tds and .tdp files, with the given path stem."""
+        self._to_rg_tileset().write_tas(path)
+
+    @classmethod
+    def _from_rg_tileset(cls, ts: _TileSet) -> "TileSet":
+        d = {k: v for k, v in json.loads(ts.to_json()).items() if v is not None}
+        if "seed" in d:
+            if isinstance(d["seed"][0], list):
+                d["seed"] = [tuple(x) for x in d["seed"]]
+            else:
+                d["seed"] = tuple(d["seed"])
+        if "size" in d and isinstance(d["size"], list):
+            d["size"] = tuple(d["size"])
+        d["glues"] = [tuple(x) for x in d.get("glues", [])]
+        return cls.from_dict(d)
+
+    @classmethod
+    def from_file(cls, path: str) -> "TileSet":
+        """Reads a tile set from a YAML or Xgrow file, resolving any included files."""
+        return cls._from_rg_tileset(_TileSet.from_file(path))
+
+    @classmethod
+    def from_file_with_ignored(cls, path: str) -> tuple["TileSet", list[str]]:
+        """Reads a tile set like `from_file`, also returning descriptions of the options
+        in Xgrow files that were ignored."""
+        ts, ignored = _TileSet.from_file_with_ignored(path)
+        return cls._from_rg_tileset(ts), ignored
+
+    @classmethod
+    def from_ca_rules(cls, spec: dict[str, Any]) -> "TileSet":
+        """Compiles a cellular automaton rule table into a tile set.  See
+        `rgrow::ca_compiler` for the format of the rule table."""
+        return cls._from_rg_tileset(_TileSet.from_ca_rules(spec))
+
+    def proofread(self, k: int, kind: str = "uniform") -> "ProofreadTileSet":
+        """Returns the k×k proofreading ("uniform" or "snaked") version of the tile set,
+        along with a mapping of its tiles back to the original tiles.  The new tile set is
+        available as the `tileset` attribute of the result."""
+        return ProofreadTileSet(self._to_rg_tileset().proofread(k, kind))
+
     @classmethod
     def from_dict(cls, d: dict[str, Any]) -> "TileSet":
+        if "include" in d:
+            raise ValueError(
+                "Tile set has unresolved includes; read it with TileSet.from_file."
+            )
         if "tiles" in d:
             d["tiles"] = [Tile(**x) for x in d["tiles"]]
         if "bonds" in d:
@@ -343,6 +407,37 @@ class TileSet:
         return self._to_rg_tileset().run_ffs(**kwargs)
 
 
+class ProofreadTileSet:
+    """A proofreading version of a tile set, from `TileSet.proofread`, along with the
+    mapping of its tiles back to the original tile set."""
+
+    tileset: TileSet
+    """The transformed tile set."""
+
+    def __init__(self, proofread: _ProofreadTileSet):
+        self._proofread = proofread
+        self.tileset = TileSet._from_rg_tileset(proofread.tileset)
+
+    @property
+    def original_tiles(self) -> list[int]:
+        """For each new tile number, the original tile number (0 for empty)."""
+        return self._proofread.original_tiles
+
+    @property
+    def block_size(self) -> int:
+        return self._proofread.block_size
+
+    def original_canvas(self, state: State) -> np.ndarray:
+        """The state's canvas in original tiles, with each complete block becoming one site."""
+        return self._proofread.original_canvas(state)
+
+    def original_mismatch_locations(self, state: State) -> np.ndarray:
+        return self._proofread.original_mismatch_locations(state)
+
+    def original_mismatches(self, state: State) -> int:
+        return self._proofread.original_mismatches(state)
+
+
 class Simulation:
     tileset: TileSet
     system: System
@@ -1,5 +1,5 @@
 from enum import Enum
-from typing import TYPE_CHECKING, Any, Sequence, overload
+from typing import TYPE_CHECKING, Any, Callable, Sequence, overload
 import numpy as np
 
 if TYPE_CHECKING:  # pragma: no cover
@@ -24,6 +24,31 @@ class State(object):
     def time(self) -> float: ...
     @property
     def total_events(self) -> int: ...
+    @property
+    def mismatch_count(self) -> int | None: ...
+    @property
+    def tile_counts(self) -> np.ndarray | None: ...
+    @property
+    def event_stats(self) -> EventStats | None: ...
+
+class EventStats(object):
+    @property
+    def attachments(self) -> int: ...
+    @property
+    def detachments(self) -> int: ...
+    @property
+    def polymer_attachments(self) -> int: ...
+    @property
+    def fission_events(self) -> int: ...
+    @property
+    def changes(self) -> int: ...
+    @property
+    def dead_events(self) -> int: ...
+    @property
+    def tile_attachments(self) -> np.ndarray: ...
+    @property
+    def tile_detachments(self) -> np.ndarray: ...
+    def total(self) -> int: ...
 
 class System(object):
     @overload
@@ -38,6 +63,9 @@ class System(object):
         size_min=float | None,
         size_max=float | None,
         for_wall_time: float | None = None,
+        leap_tolerance: float | None = None,
+        parallel_domain_size: int | None = None,
+        parallel_phase_events: float | None = None,
         require_strong_bound: bool = True,
     ) -> EvolveOutcome: ...
     @overload
@@ -52,10 +80,16 @@ class System(object):
         size_min=float | None,
         size_max=float | None,
         for_wall_time: float | None = None,
+        leap_tolerance: float | None = None,
+        parallel_domain_size: int | None = None,
+        parallel_phase_events: float | None = None,
         require_strong_bound: bool = True,
     ) -> list[EvolveOutcome]: ...
     def calc_mismatches(self, state: State) -> int: ...
     def calc_mismatch_locations(self, state: State) -> np.ndarray: ...
+    def validate_rates(self, state: State) -> None: ...
+    def track_counts(self, state: State) -> None: ...
+    def track_event_stats(self, state: State) -> None: ...
     def name_canvas(self, state: State) -> np.ndarray: ...
     def color_canvas(self, state: State) -> np.ndarray: ...
     @property
@@ -73,6 +107,90 @@ class System(object):
     ) -> "plt.Axes": ...
     def get_param(self, name: str) -> Any: ...
     def set_param(self, name: str, value: Any): ...
+    def run_ffs(
+        self,
+        config: FFSRunConfig = ...,
+        canvas_type: CanvasType | None = None,
+        start_states: Sequence[State] | None = None,
+        **kwargs,
+    ) -> FFSResult:
+        """Run FFS.  By default, this starts from dimers, measuring the nucleation rate (in M/s).
+        With `start_states`, or `from_seed=True`, it starts from the given states, or the
+        seeded state, measuring the rate (in /s) at which they reach `target_size`."""
+        ...
+    def resume_ffs(
+        self,
+        previous: FFSResult,
+        config: FFSRunConfig | None = None,
+        canvas_type: CanvasType | None = None,
+        **kwargs,
+    ) -> FFSResult:
+        """Resume an FFS run made with this system, or extend it to a larger target size.
+        The configuration defaults to that of `previous`, changed by any keyword arguments."""
+        ...
+    def ffs_committor(
+        self,
+        result: FFSResult,
+        trials: int = 100,
+        max_configs: int = 20,
+        target: int | None = None,
+        window: float = 0.2,
+        seed: int | None = None,
+        canvas_type: CanvasType | None = None,
+    ) -> CommittorAnalysis:
+        """Estimate committors for stored configurations of an FFS run made with this system,
+        by shooting `trials` trials from up to `max_configs` configurations on each surface,
+        until they reach `target` (by default, the last surface) or fall back."""
+        ...
+    def ffs_structures(
+        self,
+        result: FFSResult,
+        structures: list[
+            tuple[str, tuple[str, list[list[int]] | np.ndarray] | tuple[str, list[int], float]]
+        ],
+        grow_to: int | None = None,
+        confidence: float = 0.95,
+        seed: int | None = None,
+        canvas_type: CanvasType | None = None,
+    ) -> StructureAnalysis:
+        """Classify the nucleated assemblies of an FFS run made with this system into
+        `structures`, a list of `(name, pattern)` in order of priority, where a pattern is
+        `("template", array)` (0 matching anything) or `("tile_subset", tiles, min_fraction)`.
+        With `grow_to`, assemblies are first grown to that order parameter value."""
+        ...
+    def calc_nmers(self, max_size: int = 4) -> list[NmerInfo]:
+        """Enumerate every assembly of 2 to `max_size` tiles held together by bonds, with its
+        bond energy, formation rate (M/s) and equilibrium concentration (M).  aTAM systems have
+        no concentrations, and return an empty list."""
+        ...
+    def free_energy_profile(
+        self,
+        config: UmbrellaConfig = ...,
+        canvas_type: CanvasType | None = None,
+        start_state: State | None = None,
+        **kwargs,
+    ) -> FreeEnergyProfile:
+        """Sample the free energy landscape of an assembly by umbrella sampling.  By default,
+        this starts from the most stable dimer, or with `from_seed=True`, the seeded state."""
+        ...
+
+class NmerInfo(object):
+    @property
+    def tiles(self) -> list[tuple[int, int, int]]:
+        """(row, column, tile) for each tile, relative to the top left of the bounding box.
+        Duples appear as both of their parts."""
+        ...
+    @property
+    def size(self) -> int:
+        """The number of tiles, counting each duple once."""
+        ...
+    @property
+    def bond_energy(self) -> float: ...
+    @property
+    def formation_rate(self) -> float: ...
+    @property
+    def equilibrium_conc(self) -> float: ...
+    def config(self) -> np.ndarray: ...
 
 class FissionHandling(object): ...
 class CanvasType(object): ...
@@ -90,6 +208,105 @@ class FFSLevel(object):
         """For each configuration, the index of the configuration in the previous
         level that resulted in it."""
         ...
+    @property
+    def num_states(self) -> int:
+        """The number of configurations that reached this level, whether or not they were kept."""
+        ...
+    @property
+    def num_trials(self) -> int: ...
+    @property
+    def target_size(self) -> int: ...
+    @property
+    def parent_trials(self) -> list[int]:
+        """For each configuration in the previous level, the number of trials started
+        from it (empty if not recorded)."""
+        ...
+
+class ConfidenceInterval(object):
+    @property
+    def estimate(self) -> float: ...
+    @property
+    def lower(self) -> float: ...
+    @property
+    def upper(self) -> float: ...
+
+class SurfaceCommittor(object):
+    @property
+    def surface(self) -> int: ...
+    @property
+    def size(self) -> int: ...
+    @property
+    def configs(self) -> list[int]: ...
+    @property
+    def committors(self) -> list[float]: ...
+    @property
+    def stalled(self) -> int: ...
+    @property
+    def mean(self) -> float: ...
+
+class NucleusStats(object):
+    @property
+    def count(self) -> int: ...
+    @property
+    def mean_tiles(self) -> float: ...
+    @property
+    def mean_height(self) -> float: ...
+    @property
+    def mean_width(self) -> float: ...
+    @property
+    def tile_counts(self) -> list[float]:
+        """For each tile, its mean number in a configuration."""
+        ...
+    @property
+    def tile_frequency(self) -> list[float]:
+        """For each tile, the fraction of configurations that contain it."""
+        ...
+
+class CommittorAnalysis(object):
+    @property
+    def target(self) -> int: ...
+    @property
+    def surfaces(self) -> list[SurfaceCommittor]: ...
+    @property
+    def critical_size(self) -> float | None:
+        """Where the mean committor crosses 1/2, interpolating between surfaces."""
+        ...
+    @property
+    def critical_configs(self) -> list[tuple[int, int]]:
+        """(surface, configuration) indices of configurations with committors near 1/2."""
+        ...
+    @property
+    def critical_nuclei(self) -> NucleusStats: ...
+
+class SurfaceStructures(object):
+    @property
+    def surface(self) -> int: ...
+    @property
+    def size(self) -> int: ...
+    @property
+    def counts(self) -> list[int]: ...
+    @property
+    def unmatched(self) -> int: ...
+
+class StructureAnalysis(object):
+    @property
+    def names(self) -> list[str]: ...
+    @property
+    def surfaces(self) -> list[SurfaceStructures]: ...
+    @property
+    def outcomes(self) -> list[int | None]:
+        """For each configuration at the last surface, the index of its structure."""
+        ...
+    @property
+    def dissolved(self) -> int: ...
+    @property
+    def nucleation_rates(self) -> list[float]:
+        """The nucleation rate of each structure, in M/s."""
+        ...
+    @property
+    def unmatched_rate(self) -> float: ...
+    @property
+    def nucleation_rate_intervals(self) -> list[ConfidenceInterval]: ...
 
 class FFSResult(object):
     @property
@@ -106,6 +323,37 @@ class FFSResult(object):
     def surfaces(self) -> list[FFSLevel]: ...
     @property
     def previous_indices(self) -> list[list[int]]: ...
+    @property
+    def nucleation_rate_relative_variance(self) -> float:
+        """
+        The estimated variance of the nucleation rate divided by its square, treating
+        the surfaces as independent binomial samples.
+        """
+        ...
+    def forward_prob_intervals(
+        self, confidence: float = 0.95
+    ) -> list[ConfidenceInterval]:
+        """Wilson score confidence intervals for each forward probability."""
+        ...
+    def nucleation_rate_interval(self, confidence: float = 0.95) -> ConfidenceInterval:
+        """A log-normal confidence interval for the nucleation rate, from
+        `nucleation_rate_relative_variance`."""
+        ...
+    def nucleation_rate_bootstrap(
+        self, confidence: float = 0.95, samples: int = 1000, seed: int | None = None
+    ) -> ConfidenceInterval:
+        """A percentile bootstrap confidence interval for the nucleation rate,
+        resampling configurations along their lineages (`previous_indices`)."""
+        ...
+    @property
+    def config(self) -> FFSRunConfig: ...
+    def save(self, path: str) -> None:
+        """Save the result to a file, to be loaded with `FFSResult.load`."""
+        ...
+    @staticmethod
+    def load(path: str) -> FFSResult:
+        """Load a result saved with `save`, or by a run with `checkpoint` set."""
+        ...
 
 class Tile(object):
     def __init__(
@@ -124,6 +372,36 @@ class TileSet(object):
     def create_state_empty(self, **kwargs) -> State: ...
     def run_window(self, **kwargs) -> tuple[System, State]: ...
     def run_ffs(self, **kwargs) -> FFSResult: ...
+    def resume_ffs(
+        self, previous: FFSResult, config: FFSRunConfig | None = None, **kwargs
+    ) -> FFSResult:
+        """Resume an FFS run of this tile set, or extend it to a larger target size.
+        The configuration defaults to that of `previous`, changed by any keyword arguments."""
+        ...
+    def to_xgrow_string(self) -> str: ...
+    def write_xgrow(self, path: str) -> None: ...
+    def write_tas(self, path: str) -> None: ...
+    @classmethod
+    def from_file(cls, path: str) -> TileSet: ...
+    @classmethod
+    def from_file_with_ignored(cls, path: str) -> tuple[TileSet, list[str]]:
+        """Read a tile set from a file, also returning the options in Xgrow files that were ignored."""
+        ...
+    @classmethod
+    def from_ca_rules(cls, spec: dict) -> TileSet: ...
+    def to_json(self) -> str: ...
+    def proofread(self, k: int, kind: str = "uniform") -> ProofreadTileSet: ...
+
+class ProofreadTileSet(object):
+    @property
+    def tileset(self) -> TileSet: ...
+    @property
+    def original_tiles(self) -> list[int]: ...
+    @property
+    def block_size(self) -> int: ...
+    def original_canvas(self, state: State) -> np.ndarray: ...
+    def original_mismatch_locations(self, state: State) -> np.ndarray: ...
+    def original_mismatches(self, state: State) -> int: ...
 
 class EvolveBounds(object):
     def __init__(
@@ -135,6 +413,9 @@ class EvolveBounds(object):
         size_min: float | None = None,
         size_max: float | None = None,
         for_wall_time: float | None = None,
+        leap_tolerance: float | None = None,
+        parallel_domain_size: int | None = None,
+        parallel_phase_events: float | None = None,
         require_strong_bound: bool = True,
     ) -> None: ...
 
@@ -177,3 +458,88 @@ class FFSRunConfig(object):
     def canvas_size(self) -> tuple[int, int]: ...
     @property
     def target_size(self) -> int: ...
+    # Seed for reproducible results, independent of the number of threads.
+    @property
+    def seed(self) -> int | None: ...
+    # Order parameter defining surfaces: "tile_count", ("tile_subset", [tiles...]) or
+    # ("strong_bonds", threshold).  Sizes are values of the order parameter.
+    @property
+    def order_parameter(self) -> str | tuple[str, Any]: ...
+    # File to save the run to after each surface, to resume it if interrupted.
+    @property
+    def checkpoint(self) -> str | None: ...
+    # Start from the system's seed rather than from dimers.
+    @property
+    def from_seed(self) -> bool: ...
+    # Called with an FFSProgress after each surface; the run stops if it returns False
+    # (or raises an exception).
+    @property
+    def progress(self) -> Callable[[FFSProgress], bool | None] | None: ...
+
+class FFSProgress(object):
+    @property
+    def surface(self) -> int: ...
+    @property
+    def target_size(self) -> int: ...
+    @property
+    def num_trials(self) -> int: ...
+    @property
+    def num_successes(self) -> int: ...
+    @property
+    def forward_prob(self) -> float: ...
+    @property
+    def elapsed(self) -> float:
+        """Wall time since the run was started or resumed, in seconds."""
+        ...
+    @property
+    def nucleation_rate(self) -> float:
+        """The nucleation rate estimated from the surfaces so far."""
+        ...
+
+class UmbrellaConfig(object):
+    def __init__(self, **kwargs) -> None: ...
+    # Order parameter to sample, as for FFSRunConfig.
+    order_parameter: str | tuple[str, Any]
+    # Smallest and largest values of the order parameter sampled.
+    min_size: int
+    max_size: int
+    # Number of values in each window, and shared by consecutive windows.
+    window_width: int
+    window_overlap: int
+    # Bias each window by the previous one's estimate of ln P(n), to flatten its histogram.
+    adaptive: bool
+    # Largest number of events in each move.
+    move_events: int
+    # Moves sampled, and made before sampling, in each window.
+    samples: int
+    equilibration: int
+    canvas_size: tuple[int, int]
+    # Start from the system's seed rather than from a dimer.
+    from_seed: bool
+    seed: int | None
+
+class FreeEnergyProfile(object):
+    @property
+    def sizes(self) -> list[int]: ...
+    @property
+    def free_energy(self) -> list[float]:
+        """ΔG at each size, in kT, relative to the smallest size (NaN where unsampled)."""
+        ...
+    @property
+    def counts(self) -> list[int]: ...
+    @property
+    def windows(self) -> list[tuple[int, int]]: ...
+    @property
+    def acceptance(self) -> list[float]: ...
+    @property
+    def flux(self) -> list[float]:
+        """For each size, the equilibrium flux between it and larger sizes and smaller ones."""
+        ...
+    @property
+    def critical_size(self) -> int | None: ...
+    @property
+    def barrier(self) -> float | None: ...
+    def nucleation_rate(self, dimerization_rate: float) -> float | None:
+        """The Becker–Döring nucleation rate, calibrated by the dimerization rate of an
+        FFS run, for comparison to its nucleation rate."""
+        ...
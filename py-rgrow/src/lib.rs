@@ -3,18 +3,32 @@ use pyo3::prelude::*;
 #[pymodule]
 #[pyo3(name = "rgrow")]
 fn pyrgrow(_py: Python, m: &PyModule) -> PyResult<()> {
+    rgrow::python::init_python_logging();
+
     m.add_class::<rgrow::tileset::TileSet>()?;
     m.add_class::<rgrow::tileset::TileShape>()?;
+    m.add_class::<rgrow::proofreading::ProofreadTileSet>()?;
 
     m.add_class::<rgrow::python::PySystem>()?;
     m.add_class::<rgrow::python::PyState>()?;
+    m.add_class::<rgrow::state::EventStats>()?;
 
     m.add_class::<rgrow::ffs::BoxedFFSResult>()?;
     m.add_class::<rgrow::ffs::FFSLevelRef>()?;
+    m.add_class::<rgrow::ffs::ConfidenceInterval>()?;
+    m.add_class::<rgrow::ffs::CommittorAnalysis>()?;
+    m.add_class::<rgrow::ffs::SurfaceCommittor>()?;
+    m.add_class::<rgrow::ffs::StructureAnalysis>()?;
+    m.add_class::<rgrow::ffs::SurfaceStructures>()?;
+    m.add_class::<rgrow::ffs::NucleusStats>()?;
 
     m.add_class::<rgrow::ffs::FFSRunConfig>()?;
+    m.add_class::<rgrow::ffs::FFSProgress>()?;
+    m.add_class::<rgrow::umbrella::UmbrellaConfig>()?;
+    m.add_class::<rgrow::umbrella::FreeEnergyProfile>()?;
     m.add_class::<rgrow::system::EvolveBounds>()?;
     m.add_class::<rgrow::system::EvolveOutcome>()?;
+    m.add_class::<rgrow::system::NmerInfo>()?;
 
     Ok(())
 }
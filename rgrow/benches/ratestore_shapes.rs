@@ -0,0 +1,122 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::Rng;
+use rgrow::{
+    base::new_sim_rng,
+    canvas::PointSafeHere,
+    ratestore::{
+        CreateSizedRateStore, QuadTreeRectArray, QuadTreeSquareArray, RateStore, SparseRateStore,
+    },
+};
+
+/// Compares square and rectangular quadtree rate stores on elongated canvases: a tube (as in
+/// examples/tube.yaml), a ribbon, and a non-power-of-two square.
+const SHAPES: &[(&str, (usize, usize))] = &[
+    ("tube-6x64", (6, 64)),
+    ("ribbon-32x4096", (32, 4096)),
+    ("square-1000", (1000, 1000)),
+];
+
+fn filled<R: CreateSizedRateStore + RateStore>(shape: (usize, usize)) -> R {
+    let mut rs = R::new_with_size(shape.0, shape.1);
+    let mut rng = rand::thread_rng();
+    let changes = (0..shape.0)
+        .flat_map(|y| (0..shape.1).map(move |x| (y, x)))
+        .map(|p| (PointSafeHere(p), rng.gen::<f64>()))
+        .collect::<Vec<_>>();
+    rs.update_multiple(&changes);
+    rs
+}
+
+fn random_points(shape: (usize, usize), n: usize) -> Vec<(PointSafeHere, f64)> {
+    let mut rng = rand::thread_rng();
+    (0..n)
+        .map(|_| {
+            (
+                PointSafeHere((rng.gen_range(0..shape.0), rng.gen_range(0..shape.1))),
+                rng.gen::<f64>(),
+            )
+        })
+        .collect()
+}
+
+fn ratestore_shapes(c: &mut Criterion) {
+    let mut rng = new_sim_rng();
+    let mut group = c.benchmark_group("ratestore-shapes-choose");
+    for (name, shape) in SHAPES {
+        let square: QuadTreeSquareArray<f64> = filled(*shape);
+        let rect: QuadTreeRectArray<f64> = filled(*shape);
+
+        group.bench_with_input(BenchmarkId::new("square", name), &square, |b, rs| {
+            b.iter(|| rs.choose_point(&mut rng))
+        });
+        group.bench_with_input(BenchmarkId::new("rect", name), &rect, |b, rs| {
+            b.iter(|| rs.choose_point(&mut rng))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("ratestore-shapes-update");
+    for (name, shape) in SHAPES {
+        let mut square: QuadTreeSquareArray<f64> = filled(*shape);
+        let mut rect: QuadTreeRectArray<f64> = filled(*shape);
+        let points = random_points(*shape, 1000);
+
+        group.bench_with_input(BenchmarkId::new("square", name), &points, |b, a| {
+            b.iter(|| {
+                for (p, r) in a.iter() {
+                    square.update_point(*p, *r);
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("rect", name), &points, |b, a| {
+            b.iter(|| {
+                for (p, r) in a.iter() {
+                    rect.update_point(*p, *r);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Compares the quadtree and sparse rate stores for a small flake (a 32×32 block of active
+/// points) on a large canvas.
+fn ratestore_sparse(c: &mut Criterion) {
+    let shape = (4096, 4096);
+    let mut rng = new_sim_rng();
+    let flake = (2000..2032)
+        .flat_map(|y| (2000..2032).map(move |x| (y, x)))
+        .map(|p| (PointSafeHere(p), rng.gen::<f64>()))
+        .collect::<Vec<_>>();
+
+    let mut quadtree = QuadTreeSquareArray::<f64>::new_with_size(shape.0, shape.1);
+    let mut sparse = SparseRateStore::new_with_size(shape.0, shape.1);
+    quadtree.update_multiple(&flake);
+    sparse.update_multiple(&flake);
+
+    let mut group = c.benchmark_group("ratestore-sparse");
+    group.bench_function("choose/quadtree", |b| {
+        b.iter(|| quadtree.choose_point(&mut rng))
+    });
+    group.bench_function("choose/sparse", |b| {
+        b.iter(|| sparse.choose_point(&mut rng))
+    });
+    group.bench_function("update/quadtree", |b| {
+        b.iter(|| {
+            for (p, r) in flake.iter() {
+                quadtree.update_point(*p, *r);
+            }
+        })
+    });
+    group.bench_function("update/sparse", |b| {
+        b.iter(|| {
+            for (p, r) in flake.iter() {
+                sparse.update_point(*p, *r);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, ratestore_shapes, ratestore_sparse);
+criterion_main!(benches);
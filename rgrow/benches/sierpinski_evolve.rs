@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rgrow::{
+    canvas::CanvasPeriodic,
+    models::{atam::ATAM, ktam::KTAM},
+    state::{NullStateTracker, QuadTreeState},
+    system::{EvolveBounds, System},
+    tileset::{FromTileSet, Model, Seed, TileSet},
+};
+
+/// Measures events per second in the step loop on the sierpinski example, starting from a
+/// partially-grown assembly so that each run has a realistic number of active sites.
+const EVENTS: u64 = 10000;
+
+const BOUNDS: EvolveBounds = EvolveBounds {
+    for_events: Some(EVENTS),
+    total_events: None,
+    for_time: None,
+    total_time: None,
+    size_min: None,
+    size_max: None,
+    for_wall_time: None,
+    leap_tolerance: None,
+    parallel_domain_size: None,
+    parallel_phase_events: None,
+};
+
+type St = QuadTreeState<CanvasPeriodic, NullStateTracker>;
+
+fn grown<S: System>(sys: &S) -> St {
+    let mut st = sys.new_state::<St>((512, 512)).unwrap();
+    sys.evolve(
+        &mut st,
+        EvolveBounds {
+            size_max: Some(2000),
+            for_events: Some(1_000_000),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    st
+}
+
+fn evolve_sierpinski(c: &mut Criterion) {
+    let mut ts = TileSet::from_file("examples/sierpinski.yaml").unwrap();
+    ts.seed = Some(Seed::Single(500, 500, 1.into()));
+
+    let mut group = c.benchmark_group("sierpinski-evolve");
+    group.throughput(Throughput::Elements(EVENTS));
+
+    ts.model = Some(Model::KTAM);
+    let ktam = KTAM::from_tileset(&ts).unwrap();
+    let st = grown(&ktam);
+    group.bench_function("ktam", |b| {
+        b.iter_batched_ref(
+            || st.clone(),
+            |s| ktam.evolve(s, BOUNDS).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("ktam-leap", |b| {
+        b.iter_batched_ref(
+            || st.clone(),
+            |s| ktam.evolve(s, BOUNDS.leap_tolerance(0.05)).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    ts.model = Some(Model::ATAM);
+    let atam = ATAM::from_tileset(&ts).unwrap();
+    let st = grown(&atam);
+    group.bench_function("atam", |b| {
+        b.iter_batched_ref(
+            || st.clone(),
+            |s| atam.evolve(s, BOUNDS).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, evolve_sierpinski);
+criterion_main!(benches);
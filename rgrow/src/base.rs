@@ -8,6 +8,9 @@ use numpy::{PyArray1, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
 use serde::{Deserialize, Serialize};
 use thiserror;
 
+use crate::ca_compiler::CACompileError;
+use crate::export::ExportError;
+use crate::ffs::FFSDataError;
 use crate::tileset::ParserError;
 use thiserror::Error;
 
@@ -19,6 +22,16 @@ pub type Energy = f64;
 pub type Glue = usize;
 pub type CanvasLength = usize;
 
+/// The random number generator used in the simulation loop: a fast, non-cryptographic
+/// generator (Xoshiro256++ on 64-bit platforms), owned by each evolve call.
+pub type SimRng = rand::rngs::SmallRng;
+
+/// Creates a new [SimRng], seeded from the thread-local generator.
+pub fn new_sim_rng() -> SimRng {
+    use rand::SeedableRng;
+    SimRng::from_rng(rand::thread_rng()).expect("thread_rng should not fail")
+}
+
 #[derive(Error, Debug)]
 #[error("{0}")]
 pub struct StringConvError(pub(crate) String);
@@ -39,6 +52,26 @@ pub enum GrowError {
     WrongCanvasSize(usize, usize),
     #[error("FFS is meaningless for the aTAM.")]
     FFSCannotRunATAM,
+    #[error("Starting from the seed needs a system with a seed.")]
+    FFSNoSeed,
+    #[error("FFS needs at least one starting state.")]
+    FFSNoStartStates,
+    #[error(
+        "FFS starting states have order parameter up to {0}, which must be below start_size ({1})."
+    )]
+    FFSStartAboveSurface(NumTiles, NumTiles),
+    #[error(
+        "No assemblies are left to classify: the FFS result has no surfaces, its last surface has no stored configurations, or all of them dissolved."
+    )]
+    FFSNoAssembliesToClassify,
+    #[error("Starting order parameter {0} is outside the first umbrella window ({1} to {2}).")]
+    UmbrellaStartOutsideWindow(NumTiles, NumTiles, NumTiles),
+    #[error(
+        "Umbrella windows starting at {0} and {1} have no samples in common: use more samples or a larger overlap."
+    )]
+    UmbrellaNoOverlap(NumTiles, NumTiles),
+    #[error("Invalid umbrella sampling configuration: {0}.")]
+    UmbrellaInvalidConfig(String),
     #[error("Point ({0}, {1}) is out of bounds.")]
     OutOfBounds(usize, usize),
     #[error("{0}")]
@@ -51,12 +84,23 @@ pub enum GrowError {
     NoParameter(String),
     #[error("Parameter type is wrong for {0}")]
     WrongParameterType(String),
+    #[error("Rate store is inconsistent: {0}")]
+    RateStoreInconsistent(String),
+    #[error(transparent)]
+    FFSData(#[from] FFSDataError),
+    /// An exception raised by Python code called from a simulation, such as a callback.
+    #[cfg(feature = "python")]
+    #[error(transparent)]
+    Python(#[from] PyErr),
 }
 
 #[cfg(feature = "python")]
 impl From<GrowError> for pyo3::PyErr {
     fn from(err: GrowError) -> Self {
-        pyo3::exceptions::PyException::new_err(err.to_string())
+        match err {
+            GrowError::Python(err) => err,
+            err => pyo3::exceptions::PyException::new_err(err.to_string()),
+        }
     }
 }
 
@@ -68,6 +112,10 @@ pub enum RgrowError {
     Grow(#[from] GrowError),
     #[error(transparent)]
     ModelError(#[from] ModelError),
+    #[error(transparent)]
+    Export(#[from] ExportError),
+    #[error(transparent)]
+    CACompile(#[from] CACompileError),
     #[cfg(feature = "ui")]
     #[error(transparent)]
     Pixel(#[from] pixels::Error),
@@ -78,7 +126,10 @@ pub enum RgrowError {
 #[cfg(feature = "python")]
 impl From<RgrowError> for pyo3::PyErr {
     fn from(err: RgrowError) -> Self {
-        pyo3::exceptions::PyException::new_err(err.to_string())
+        match err {
+            RgrowError::Grow(err) => err.into(),
+            err => pyo3::exceptions::PyException::new_err(err.to_string()),
+        }
     }
 }
 
@@ -0,0 +1,552 @@
+//! Compilation of one-dimensional block cellular automata, written as Wang tile rule tables,
+//! into tile sets.
+//!
+//! Each rule takes a *side* input (from the previous cell in the row) and a *lower* input (from
+//! the cell below), and produces a side output (to the next cell in the row) and an upper output
+//! (to the cell above).  Two layouts are supported:
+//!
+//! - [CALayout::Diagonal]: the layout of the Sierpinski example.  Growth starts from a corner seed
+//!   at the bottom right, with boundary tiles along the right column feeding `side_input` to the
+//!   west, and along the bottom row feeding `lower_input` to the north.  Rule tiles take inputs
+//!   from the east and south, and output to the west and north.
+//! - [CALayout::ZigZag]: a ribbon of fixed width, grown row by row, alternately from left to
+//!   right and right to left, starting from a seed row holding `initial`.  At the start of each
+//!   row, a boundary tile feeds `side_input` (or `reverse_side_input`, for right-to-left rows) to
+//!   the first cell.  Boundary tiles at the end of a row only attach once the row is complete.
+//!   Right-to-left rows use `reverse_rules`, if given.
+//!
+//! Glues between rule tiles have strength 1, and boundary glues strength 2, so the result grows
+//! correctly in the aTAM at threshold 2, and is suitable for the kTAM.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::base::{GlueIdent, RgrowError, TileIdent};
+use crate::proofreading::ProofreadingKind;
+use crate::tileset::{Bond, CanvasType, Seed, Size, TemplateValue, Tile, TileSet};
+
+#[derive(thiserror::Error, Debug)]
+pub enum CACompileError {
+    #[error("No rules were given.")]
+    NoRules,
+    #[error("Rules {0} and {1} have the same inputs.")]
+    AmbiguousRules(String, String),
+    #[error("The zig-zag layout requires a non-empty initial row.")]
+    NoInitialRow,
+    #[error("The diagonal layout requires a lower_input value.")]
+    NoLowerInput,
+    #[error("A canvas of size {size} is too small for a ribbon of width {width}.")]
+    CanvasTooSmall { size: usize, width: usize },
+    #[error("A canvas of size {0} is too small for the diagonal layout, which needs at least 6.")]
+    DiagonalCanvasTooSmall(usize),
+    #[error("Snaked proofreading is only supported for the diagonal layout.")]
+    SnakedZigZag,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum CALayout {
+    #[serde(alias = "diagonal")]
+    Diagonal,
+    #[serde(alias = "zigzag", alias = "zig-zag")]
+    ZigZag,
+}
+
+/// A single rule: (side_in, lower) → (side_out, upper).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CARule {
+    pub side_in: TemplateValue,
+    pub lower: TemplateValue,
+    pub side_out: TemplateValue,
+    pub upper: TemplateValue,
+    /// Name of the rule tile.  Defaults to `rule_{side_in}{lower}`.
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+impl CARule {
+    fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("rule_{}{}", self.side_in, self.lower))
+    }
+}
+
+/// A cellular automaton rule table, with its layout and boundaries.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CASpec {
+    pub layout: CALayout,
+    pub rules: Vec<CARule>,
+    /// Rules for right-to-left rows of the zig-zag layout.  Defaults to `rules`.
+    pub reverse_rules: Option<Vec<CARule>>,
+    /// Side value fed in at the start of each row.
+    pub side_input: TemplateValue,
+    /// Side value fed in at the start of right-to-left rows of the zig-zag layout.  Defaults to
+    /// `side_input`.
+    pub reverse_side_input: Option<TemplateValue>,
+    /// Lower value fed in along the bottom boundary of the diagonal layout.
+    pub lower_input: Option<TemplateValue>,
+    /// Initial row of the zig-zag layout, from left to right.
+    #[serde(default)]
+    pub initial: Vec<TemplateValue>,
+    /// Canvas size.  Defaults to 64, or larger if needed for the ribbon width.
+    pub size: Option<usize>,
+    /// If set, apply k×k proofreading to the compiled tile set.
+    pub proofreading: Option<usize>,
+    /// Kind of proofreading to apply.  Snaked proofreading is only supported for the diagonal
+    /// layout.
+    #[serde(default)]
+    pub proofreading_kind: ProofreadingKind,
+    pub gse: Option<f64>,
+    pub gmc: Option<f64>,
+}
+
+const BOUNDARY_COLOR: &str = "grey40";
+
+fn name(s: String) -> GlueIdent {
+    GlueIdent::Name(s)
+}
+
+fn check_rules(rules: &[CARule]) -> Result<(), CACompileError> {
+    if rules.is_empty() {
+        return Err(CACompileError::NoRules);
+    }
+    for (i, r1) in rules.iter().enumerate() {
+        for r2 in &rules[i + 1..] {
+            if r1.side_in == r2.side_in && r1.lower == r2.lower {
+                return Err(CACompileError::AmbiguousRules(r1.name(), r2.name()));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn side_outputs(rules: &[CARule]) -> Vec<String> {
+    rules
+        .iter()
+        .map(|r| r.side_out.to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+impl CASpec {
+    /// Compiles the rule table into a tile set.
+    pub fn to_tileset(&self) -> Result<TileSet, RgrowError> {
+        check_rules(&self.rules)?;
+        if let Some(rr) = &self.reverse_rules {
+            check_rules(rr)?;
+        }
+        let proofreading = self.proofreading.filter(|&k| k > 1);
+        if proofreading.is_some()
+            && self.layout == CALayout::ZigZag
+            && matches!(self.proofreading_kind, ProofreadingKind::Snaked)
+        {
+            return Err(CACompileError::SnakedZigZag.into());
+        }
+
+        let ts = match self.layout {
+            CALayout::Diagonal => self.diagonal()?,
+            CALayout::ZigZag => self.zigzag()?,
+        };
+
+        match proofreading {
+            Some(k) => Ok(ts.proofread(self.proofreading_kind, k)?.tileset),
+            None => Ok(ts),
+        }
+    }
+
+    fn diagonal(&self) -> Result<TileSet, CACompileError> {
+        let lower_input = self
+            .lower_input
+            .as_ref()
+            .ok_or(CACompileError::NoLowerInput)?;
+        let size = self.size.unwrap_or(64);
+        // The corner seed is three sites in from the edge, with room to grow diagonally from it.
+        if size < 6 {
+            return Err(CACompileError::DiagonalCanvasTooSmall(size));
+        }
+
+        let mut tiles = vec![
+            Tile {
+                name: Some("corner".to_string()),
+                edges: vec![
+                    name("B".into()),
+                    GlueIdent::Num(0),
+                    GlueIdent::Num(0),
+                    name("B".into()),
+                ],
+                stoic: Some(0.),
+                color: Some(BOUNDARY_COLOR.to_string()),
+                shape: None,
+            },
+            Tile {
+                name: Some("side_boundary".to_string()),
+                edges: vec![
+                    name("B".into()),
+                    GlueIdent::Num(0),
+                    name("B".into()),
+                    name(format!("h{}", self.side_input)),
+                ],
+                stoic: None,
+                color: Some(BOUNDARY_COLOR.to_string()),
+                shape: None,
+            },
+            Tile {
+                name: Some("lower_boundary".to_string()),
+                edges: vec![
+                    name(format!("v{lower_input}")),
+                    name("B".into()),
+                    GlueIdent::Num(0),
+                    name("B".into()),
+                ],
+                stoic: None,
+                color: Some(BOUNDARY_COLOR.to_string()),
+                shape: None,
+            },
+        ];
+
+        tiles.extend(self.rules.iter().map(|r| Tile {
+            name: Some(r.name()),
+            edges: vec![
+                name(format!("v{}", r.upper)),
+                name(format!("h{}", r.side_in)),
+                name(format!("v{}", r.lower)),
+                name(format!("h{}", r.side_out)),
+            ],
+            stoic: None,
+            color: r.color.clone(),
+            shape: None,
+        }));
+
+        Ok(TileSet {
+            tiles,
+            bonds: vec![Bond {
+                name: name("B".into()),
+                strength: 2.,
+            }],
+            seed: Some(Seed::Single(
+                size - 3,
+                size - 3,
+                TileIdent::Name("corner".into()),
+            )),
+            size: Some(Size::Single(size)),
+            gse: self.gse,
+            gmc: self.gmc,
+            ..Default::default()
+        })
+    }
+
+    fn zigzag(&self) -> Result<TileSet, CACompileError> {
+        let width = self.initial.len();
+        if width == 0 {
+            return Err(CACompileError::NoInitialRow);
+        }
+        let size = self
+            .size
+            .unwrap_or_else(|| 64.max((width + 6).next_power_of_two()));
+        if width + 6 > size {
+            return Err(CACompileError::CanvasTooSmall { size, width });
+        }
+
+        let reverse_rules = self.reverse_rules.as_ref().unwrap_or(&self.rules);
+        let reverse_side_input = self.reverse_side_input.as_ref().unwrap_or(&self.side_input);
+
+        let boundary = |n: &str, edges: [GlueIdent; 4]| Tile {
+            name: Some(n.to_string()),
+            edges: edges.to_vec(),
+            stoic: None,
+            color: Some(BOUNDARY_COLOR.to_string()),
+            shape: None,
+        };
+        let z = || GlueIdent::Num(0);
+
+        // Glues are suffixed with the direction of the row that outputs them: R for left-to-right
+        // rows, L for right-to-left rows.  The seed row counts as an L row.
+        let mut tiles = vec![
+            // Start of an R row, on the left.
+            boundary(
+                "left_start",
+                [
+                    name("lb_R".into()),
+                    name(format!("h{}_R", self.side_input)),
+                    name("lbt_L".into()),
+                    z(),
+                ],
+            ),
+            // Start of an L row, on the right.
+            boundary(
+                "right_start",
+                [
+                    name("rb_L".into()),
+                    z(),
+                    name("rbt_R".into()),
+                    name(format!("h{reverse_side_input}_L")),
+                ],
+            ),
+        ];
+
+        // Ends of rows, which attach cooperatively with the last cell.
+        for v in side_outputs(&self.rules) {
+            tiles.push(boundary(
+                &format!("right_end_{v}"),
+                [
+                    name("rbt_R".into()),
+                    z(),
+                    name("rb_L".into()),
+                    name(format!("h{v}_R")),
+                ],
+            ));
+        }
+        for v in side_outputs(reverse_rules) {
+            tiles.push(boundary(
+                &format!("left_end_{v}"),
+                [
+                    name("lbt_L".into()),
+                    name(format!("h{v}_L")),
+                    name("lb_R".into()),
+                    z(),
+                ],
+            ));
+        }
+
+        tiles.extend(self.rules.iter().map(|r| Tile {
+            name: Some(format!("{}_R", r.name())),
+            edges: vec![
+                name(format!("v{}_R", r.upper)),
+                name(format!("h{}_R", r.side_out)),
+                name(format!("v{}_L", r.lower)),
+                name(format!("h{}_R", r.side_in)),
+            ],
+            stoic: None,
+            color: r.color.clone(),
+            shape: None,
+        }));
+        tiles.extend(reverse_rules.iter().map(|r| Tile {
+            name: Some(format!("{}_L", r.name())),
+            edges: vec![
+                name(format!("v{}_L", r.upper)),
+                name(format!("h{}_L", r.side_in)),
+                name(format!("v{}_R", r.lower)),
+                name(format!("h{}_L", r.side_out)),
+            ],
+            stoic: None,
+            color: r.color.clone(),
+            shape: None,
+        }));
+
+        // The seed row: a left corner, the initial values, and a right corner.
+        let y = size - 3;
+        let x0 = 2;
+        let mut seed = vec![(y, x0, TileIdent::Name("seed_left".into()))];
+        tiles.push(boundary("seed_left", [name("lbt_L".into()), z(), z(), z()]));
+        tiles.push(boundary("seed_right", [name("rb_L".into()), z(), z(), z()]));
+        for v in self
+            .initial
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<BTreeSet<_>>()
+        {
+            tiles.push(boundary(
+                &format!("seed_{v}"),
+                [name(format!("v{v}_L")), z(), z(), z()],
+            ));
+        }
+        for (i, v) in self.initial.iter().enumerate() {
+            seed.push((y, x0 + 1 + i, TileIdent::Name(format!("seed_{v}"))));
+        }
+        seed.push((y, x0 + 1 + width, TileIdent::Name("seed_right".into())));
+
+        Ok(TileSet {
+            tiles,
+            bonds: vec![
+                Bond {
+                    name: name("lbt_L".into()),
+                    strength: 2.,
+                },
+                Bond {
+                    name: name("rbt_R".into()),
+                    strength: 2.,
+                },
+            ],
+            seed: Some(Seed::Multi(seed)),
+            size: Some(Size::Single(size)),
+            canvas_type: Some(CanvasType::Square),
+            gse: self.gse,
+            gmc: self.gmc,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::{Canvas, PointSafe2};
+    use crate::state::StateStatus;
+    use crate::system::{DynSystem, EvolveBounds, TileBondInfo};
+    use crate::tileset::Model;
+
+    fn xor_rules() -> Vec<CARule> {
+        let mut rules = Vec::new();
+        for a in 0..2 {
+            for b in 0..2 {
+                rules.push(CARule {
+                    side_in: TemplateValue::Int(a),
+                    lower: TemplateValue::Int(b),
+                    side_out: TemplateValue::Int(a ^ b),
+                    upper: TemplateValue::Int(a ^ b),
+                    name: None,
+                    color: None,
+                });
+            }
+        }
+        rules
+    }
+
+    #[test]
+    fn test_diagonal_sierpinski() -> Result<(), RgrowError> {
+        let spec = CASpec {
+            layout: CALayout::Diagonal,
+            rules: xor_rules(),
+            reverse_rules: None,
+            side_input: TemplateValue::Int(1),
+            reverse_side_input: None,
+            lower_input: Some(TemplateValue::Int(1)),
+            initial: Vec::new(),
+            size: Some(32),
+            proofreading: None,
+            proofreading_kind: ProofreadingKind::Uniform,
+            gse: None,
+            gmc: None,
+        };
+        let mut ts = spec.to_tileset()?;
+        ts.model = Some(Model::ATAM);
+
+        let (sys, mut state) = ts.create_system_and_state()?;
+        sys.evolve(
+            &mut state,
+            EvolveBounds {
+                for_events: Some(2000),
+                ..Default::default()
+            },
+        )?;
+
+        // Along the diagonal, inputs are C(2i-1, i) mod 2.
+        let name_at = |y, x| {
+            sys.tile_name(state.tile_at_point(PointSafe2((y, x))))
+                .to_string()
+        };
+        assert_eq!(name_at(28, 28), "rule_11");
+        assert_eq!(name_at(26, 26), "rule_00");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagonal_too_small() {
+        let spec = CASpec {
+            layout: CALayout::Diagonal,
+            rules: xor_rules(),
+            reverse_rules: None,
+            side_input: TemplateValue::Int(1),
+            reverse_side_input: None,
+            lower_input: Some(TemplateValue::Int(1)),
+            initial: Vec::new(),
+            size: Some(2),
+            proofreading: None,
+            proofreading_kind: ProofreadingKind::Uniform,
+            gse: None,
+            gmc: None,
+        };
+        assert!(matches!(
+            spec.to_tileset(),
+            Err(RgrowError::CACompile(
+                CACompileError::DiagonalCanvasTooSmall(2)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_zigzag_copy() -> Result<(), RgrowError> {
+        // A ribbon that copies its initial row, row after row.
+        let rules = ["a", "b"]
+            .iter()
+            .map(|v| CARule {
+                side_in: TemplateValue::Str("c".into()),
+                lower: TemplateValue::Str(v.to_string()),
+                side_out: TemplateValue::Str("c".into()),
+                upper: TemplateValue::Str(v.to_string()),
+                name: Some(format!("copy_{v}")),
+                color: None,
+            })
+            .collect();
+        let spec = CASpec {
+            layout: CALayout::ZigZag,
+            rules,
+            reverse_rules: None,
+            side_input: TemplateValue::Str("c".into()),
+            reverse_side_input: None,
+            lower_input: None,
+            initial: ["a", "b", "b", "a"]
+                .iter()
+                .map(|v| TemplateValue::Str(v.to_string()))
+                .collect(),
+            size: Some(32),
+            proofreading: None,
+            proofreading_kind: ProofreadingKind::Uniform,
+            gse: None,
+            gmc: None,
+        };
+        let mut ts = spec.to_tileset()?;
+        ts.model = Some(Model::ATAM);
+
+        let (sys, mut state) = ts.create_system_and_state()?;
+        sys.evolve(
+            &mut state,
+            EvolveBounds {
+                for_events: Some(30),
+                ..Default::default()
+            },
+        )?;
+
+        // Seed row (6 tiles), then five rows of 6 tiles.
+        assert_eq!(state.n_tiles(), 36);
+        let name_at = |y, x| {
+            sys.tile_name(state.tile_at_point(PointSafe2((y, x))))
+                .to_string()
+        };
+        assert_eq!(name_at(28, 4), "copy_b_R");
+        assert_eq!(name_at(27, 3), "copy_a_L");
+        assert_eq!(name_at(27, 7), "right_start");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zigzag_snaked_rejected() {
+        let spec = CASpec {
+            layout: CALayout::ZigZag,
+            rules: xor_rules(),
+            reverse_rules: None,
+            side_input: TemplateValue::Int(0),
+            reverse_side_input: None,
+            lower_input: None,
+            initial: vec![TemplateValue::Int(1), TemplateValue::Int(0)],
+            size: Some(32),
+            proofreading: Some(2),
+            proofreading_kind: ProofreadingKind::Snaked,
+            gse: None,
+            gmc: None,
+        };
+        assert!(matches!(
+            spec.to_tileset(),
+            Err(RgrowError::CACompile(CACompileError::SnakedZigZag))
+        ));
+        let uniform = CASpec {
+            proofreading_kind: ProofreadingKind::Uniform,
+            ..spec
+        };
+        assert!(uniform.to_tileset().is_ok());
+    }
+}
@@ -58,6 +58,12 @@ pub struct EvolveBounds {
     pub size_max: COption<NumTiles>,
     /// Stop after this amount of (real) time has passed.
     pub for_wall_time: COption<f64>,
+    /// If set, evolve with approximate leaps, with this tolerance.
+    pub leap_tolerance: COption<f64>,
+    /// If set, evolve in parallel domains of about this size.
+    pub parallel_domain_size: COption<usize>,
+    /// For parallel evolution, the average number of events per domain in each phase.
+    pub parallel_phase_events: COption<f64>,
 }
 
 impl From<system::EvolveBounds> for EvolveBounds {
@@ -70,6 +76,9 @@ impl From<system::EvolveBounds> for EvolveBounds {
             size_min: value.size_min.into(),
             size_max: value.size_max.into(),
             for_wall_time: value.for_wall_time.map(|d| d.as_secs_f64()).into(),
+            leap_tolerance: value.leap_tolerance.into(),
+            parallel_domain_size: value.parallel_domain_size.into(),
+            parallel_phase_events: value.parallel_phase_events.into(),
         }
     }
 }
@@ -84,6 +93,9 @@ impl From<EvolveBounds> for system::EvolveBounds {
             size_min: value.size_min.into(),
             size_max: value.size_max.into(),
             for_wall_time: Option::from(value.for_wall_time).map(Duration::from_secs_f64),
+            leap_tolerance: value.leap_tolerance.into(),
+            parallel_domain_size: value.parallel_domain_size.into(),
+            parallel_phase_events: value.parallel_phase_events.into(),
         }
     }
 }
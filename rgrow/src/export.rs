@@ -0,0 +1,384 @@
+//! Writers for tile set formats used by other simulators.
+//!
+//! These are the inverse of [crate::parser_xgrow]: they take a [TileSet] and write it out so that
+//! designs can be checked in Xgrow, or in the ISU TAS (and PyTAS, which reads the same files).
+//! Tiles are written in the order that rgrow numbers them, so tile numbers (in seeds, double tiles,
+//! etc) are preserved.  Duples are written as two tiles joined by `doubletile`/`vdoubletile`, as
+//! Xgrow does.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::base::{Glue, RgrowError};
+use crate::system::{ChunkHandling, ChunkSize, FissionHandling};
+use crate::tileset::{CanvasType, Model, ProcessedTileSet, Size, TileSet, TileShape};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("{format} does not support {feature}.")]
+    Unsupported {
+        format: &'static str,
+        feature: String,
+    },
+    #[error("Name \"{0}\" cannot be written: it contains whitespace or a reserved character.")]
+    InvalidName(String),
+    #[error("Glue {name} has strength {strength}, but {format} requires integer strengths.")]
+    NonIntegerStrength {
+        format: &'static str,
+        name: String,
+        strength: f64,
+    },
+}
+
+fn check_name(name: &str) -> Result<(), ExportError> {
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || "{}()[]%,".contains(c)) {
+        Err(ExportError::InvalidName(name.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Names of glues 1..=n, using the number itself for unnamed glues.
+fn glue_names(proc: &ProcessedTileSet) -> Vec<String> {
+    (1..proc.glue_strengths.len())
+        .map(|g| match proc.glue_map.get_by_right(&g) {
+            Some(n) => n.clone(),
+            None => g.to_string(),
+        })
+        .collect()
+}
+
+/// Colors and names of tiles, in processed order (including second halves of duples), as written
+/// in the tile set.  Unlike [ProcessedTileSet], this does not assign random colors.
+fn tile_labels(tileset: &TileSet) -> Vec<(Option<String>, Option<String>)> {
+    let mut labels = vec![(None, None)];
+    let mut second_halves = Vec::new();
+    for tile in &tileset.tiles {
+        labels.push((tile.name.clone(), tile.color.clone()));
+        match tile.shape {
+            Some(TileShape::Horizontal) | Some(TileShape::Vertical) => {
+                second_halves.push((tile.name.clone(), tile.color.clone()))
+            }
+            _ => {}
+        }
+    }
+    labels.append(&mut second_halves);
+    labels
+}
+
+fn single_size(tileset: &TileSet, format: &'static str) -> Result<Option<usize>, ExportError> {
+    match tileset.size {
+        None => Ok(None),
+        Some(Size::Single(n)) => Ok(Some(n)),
+        Some(Size::Pair((a, b))) if a == b => Ok(Some(a)),
+        Some(Size::Pair((a, b))) => Err(ExportError::Unsupported {
+            format,
+            feature: format!("non-square canvases ({a}, {b})"),
+        }),
+    }
+}
+
+impl TileSet {
+    /// Writes the tile set in Xgrow's `.tiles` format.
+    ///
+    /// Options that Xgrow has no equivalent for (eg, alpha, k_f) are not written.  Fission handling
+    /// other than no fission or keeping the seeded fragment (Xgrow's `fission`, and `chunk_fission`
+    /// with dimer chunk detachment) results in an error.
+    /// Tube canvases, multi-tile seeds and cover strands cannot be represented, and result in an error.
+    pub fn to_xgrow_string(&self) -> Result<String, RgrowError> {
+        const FORMAT: &str = "Xgrow";
+
+        if self.cover_strands.as_ref().is_some_and(|c| !c.is_empty()) {
+            return Err(ExportError::Unsupported {
+                format: FORMAT,
+                feature: "cover strands".to_string(),
+            }
+            .into());
+        }
+
+        let proc = ProcessedTileSet::from_tileset(self)?;
+        let gnames = glue_names(&proc);
+        for n in &gnames {
+            check_name(n)?;
+        }
+        let labels = tile_labels(self);
+        let ntiles = proc.tile_edges.nrows() - 1;
+
+        let mut s = String::new();
+        writeln!(s, "tile edges matches {{{{N E S W}}*}}").unwrap();
+        writeln!(s, "num tile types={ntiles}").unwrap();
+        writeln!(s, "num binding types={}", gnames.len()).unwrap();
+        if !gnames.is_empty() {
+            writeln!(s, "binding type names={{ {} }}", gnames.join(" ")).unwrap();
+        }
+
+        writeln!(s, "tile edges={{").unwrap();
+        for (t, edges) in proc.tile_edges.outer_iter().enumerate().skip(1) {
+            write!(
+                s,
+                "{{{} {} {} {}}}[{}]",
+                edges[0], edges[1], edges[2], edges[3], proc.tile_stoics[t]
+            )
+            .unwrap();
+            let (name, color) = &labels[t];
+            if let Some(color) = color {
+                write!(s, "({color})").unwrap();
+            }
+            if let Some(name) = name {
+                write!(s, " % {name}").unwrap();
+            }
+            writeln!(s).unwrap();
+        }
+        writeln!(s, "}}").unwrap();
+
+        if !gnames.is_empty() {
+            let strengths: Vec<String> = proc
+                .glue_strengths
+                .iter()
+                .skip(1)
+                .map(|x| x.to_string())
+                .collect();
+            writeln!(s, "binding strengths={{{}}}", strengths.join(" ")).unwrap();
+        }
+
+        for (g1, g2, v) in &proc.gluelinks {
+            writeln!(s, "g({g1},{g2})={v}").unwrap();
+        }
+        for (t1, t2) in &proc.hdoubletiles {
+            writeln!(s, "doubletile={t1},{t2}").unwrap();
+        }
+        for (t1, t2) in &proc.vdoubletiles {
+            writeln!(s, "vdoubletile={t1},{t2}").unwrap();
+        }
+
+        match proc.seed.as_slice() {
+            [] => {}
+            [(y, x, t)] => writeln!(s, "seed={y},{x},{t}").unwrap(),
+            _ => {
+                return Err(ExportError::Unsupported {
+                    format: FORMAT,
+                    feature: "seeds of more than one tile".to_string(),
+                }
+                .into())
+            }
+        }
+
+        if let Some(size) = single_size(self, FORMAT)? {
+            writeln!(s, "size={size}").unwrap();
+        }
+        match self.canvas_type {
+            None | Some(CanvasType::Square) => {}
+            Some(CanvasType::Periodic) => writeln!(s, "periodic").unwrap(),
+            Some(CanvasType::Tube) => {
+                return Err(ExportError::Unsupported {
+                    format: FORMAT,
+                    feature: "tube canvases".to_string(),
+                }
+                .into())
+            }
+        }
+
+        match self.model {
+            Some(Model::ATAM) => writeln!(s, "T={}", self.threshold.unwrap_or(2.)).unwrap(),
+            _ => {
+                if let Some(x) = self.gmc {
+                    writeln!(s, "Gmc={x}").unwrap();
+                }
+                if let Some(x) = self.gse {
+                    writeln!(s, "Gse={x}").unwrap();
+                }
+            }
+        }
+        if let Some(x) = self.block {
+            writeln!(s, "block={x}").unwrap();
+        }
+        if let Some(x) = self.update_rate {
+            writeln!(s, "update_rate={x}").unwrap();
+        }
+        if let Some(x) = self.smax {
+            writeln!(s, "smax={x}").unwrap();
+        }
+        if let Some(x) = self.smin {
+            writeln!(s, "smin={x}").unwrap();
+        }
+        if let Some(x) = self.tmax {
+            writeln!(s, "tmax={x}").unwrap();
+        }
+        if let Some(x) = self.emax {
+            writeln!(s, "emax={x}").unwrap();
+        }
+
+        let chunk_fission = matches!(
+            (self.chunk_handling, self.chunk_size),
+            (Some(ChunkHandling::Detach), Some(ChunkSize::Dimer))
+        );
+        match (self.fission, chunk_fission) {
+            (None, false) | (Some(FissionHandling::NoFission), false) => {}
+            (Some(FissionHandling::KeepSeeded), false) => writeln!(s, "fission").unwrap(),
+            (Some(FissionHandling::KeepSeeded), true) => writeln!(s, "chunk_fission").unwrap(),
+            (f, _) => {
+                return Err(ExportError::Unsupported {
+                    format: FORMAT,
+                    feature: format!(
+                        "fission handling {f:?} with chunk handling {:?}/{:?}",
+                        self.chunk_handling, self.chunk_size
+                    ),
+                }
+                .into())
+            }
+        }
+
+        Ok(s)
+    }
+
+    /// Writes the tile set to an Xgrow `.tiles` file.  See [TileSet::to_xgrow_string].
+    pub fn write_xgrow<P: AsRef<Path>>(&self, path: P) -> Result<(), RgrowError> {
+        std::fs::write(path, self.to_xgrow_string()?)?;
+        Ok(())
+    }
+
+    /// Writes the tile set in the ISU TAS format, returning the contents of the tile definition
+    /// (`.tds`) file and the assembly (`.tdp`) file.  The `.tdp` file refers to the `.tds` file
+    /// as `tds_name`.  PyTAS reads the same pair of files.
+    ///
+    /// The TAS is an aTAM simulator: glue strengths must be integers, and duples, glue links and
+    /// cover strands are not supported.  Seed positions are written with x as the column and y as
+    /// the negated row, so that north remains up.
+    pub fn to_tas_strings(&self, tds_name: &str) -> Result<(String, String), RgrowError> {
+        const FORMAT: &str = "ISU TAS";
+
+        let unsupported = |feature: &str| -> RgrowError {
+            ExportError::Unsupported {
+                format: FORMAT,
+                feature: feature.to_string(),
+            }
+            .into()
+        };
+
+        if self.cover_strands.as_ref().is_some_and(|c| !c.is_empty()) {
+            return Err(unsupported("cover strands"));
+        }
+
+        let proc = ProcessedTileSet::from_tileset(self)?;
+        if proc.has_duples {
+            return Err(unsupported("duples"));
+        }
+        if !proc.gluelinks.is_empty() {
+            return Err(unsupported("glue links"));
+        }
+
+        let gnames = glue_names(&proc);
+        for (g, name) in gnames.iter().enumerate() {
+            check_name(name)?;
+            let strength = proc.glue_strengths[g + 1];
+            if strength.fract() != 0. {
+                return Err(ExportError::NonIntegerStrength {
+                    format: FORMAT,
+                    name: name.clone(),
+                    strength,
+                }
+                .into());
+            }
+        }
+        let label = |g: Glue| if g == 0 { "" } else { gnames[g - 1].as_str() };
+        let strength = |g: Glue| proc.glue_strengths[g] as i64;
+
+        let mut tds = String::new();
+        for (t, edges) in proc.tile_edges.outer_iter().enumerate().skip(1) {
+            check_name(&proc.tile_names[t])?;
+            let [r, g, b, _] = proc.tile_colors[t];
+            writeln!(tds, "TILENAME {}", proc.tile_names[t]).unwrap();
+            writeln!(tds, "LABEL ").unwrap();
+            writeln!(tds, "TILECOLOR {r:02X}{g:02X}{b:02X}").unwrap();
+            for (dir, e) in ["NORTH", "EAST", "SOUTH", "WEST"].iter().zip(edges) {
+                writeln!(tds, "{dir}BIND {}", strength(*e)).unwrap();
+            }
+            for (dir, e) in ["NORTH", "EAST", "SOUTH", "WEST"].iter().zip(edges) {
+                writeln!(tds, "{dir}LABEL {}", label(*e)).unwrap();
+            }
+            writeln!(tds, "CREATE").unwrap();
+            writeln!(tds).unwrap();
+        }
+
+        let mut tdp = String::new();
+        writeln!(tdp, "{tds_name}").unwrap();
+        for (y, x, t) in &proc.seed {
+            writeln!(
+                tdp,
+                "{} {} {}",
+                proc.tile_names[*t as usize],
+                x,
+                -(*y as i64)
+            )
+            .unwrap();
+        }
+
+        Ok((tds, tdp))
+    }
+
+    /// Writes the tile set as a pair of ISU TAS files, `<stem>.tds` and `<stem>.tdp`.  See
+    /// [TileSet::to_tas_strings].
+    pub fn write_tas<P: AsRef<Path>>(&self, path: P) -> Result<(), RgrowError> {
+        let tds_path = path.as_ref().with_extension("tds");
+        let tdp_path = path.as_ref().with_extension("tdp");
+        let tds_name = tds_path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (tds, tdp) = self.to_tas_strings(&tds_name)?;
+        std::fs::write(tds_path, tds)?;
+        std::fs::write(tdp_path, tdp)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser_xgrow;
+
+    #[test]
+    fn test_xgrow_roundtrip() -> anyhow::Result<()> {
+        let ts = TileSet::from_file("examples/sierpinski.yaml")?;
+        let s = ts.to_xgrow_string()?;
+        let ts2 = parser_xgrow::parse_xgrow_string(&s)?;
+
+        let p1 = ProcessedTileSet::from_tileset(&ts)?;
+        let p2 = ProcessedTileSet::from_tileset(&ts2)?;
+        assert_eq!(p1.tile_edges, p2.tile_edges);
+        assert_eq!(p1.tile_stoics, p2.tile_stoics);
+        assert_eq!(p1.glue_strengths, p2.glue_strengths);
+        assert_eq!(p1.seed, p2.seed);
+        assert_eq!(ts.gse, ts2.gse);
+        assert_eq!(ts.gmc, ts2.gmc);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xgrow_roundtrip_duples() -> anyhow::Result<()> {
+        let ts = TileSet::from_file("examples/xgrow-format/zig-zag-3w.tiles")?;
+        let s = ts.to_xgrow_string()?;
+        let ts2 = parser_xgrow::parse_xgrow_string(&s)?;
+
+        let p1 = ProcessedTileSet::from_tileset(&ts)?;
+        let p2 = ProcessedTileSet::from_tileset(&ts2)?;
+        assert_eq!(p1.tile_edges, p2.tile_edges);
+        assert_eq!(p1.hdoubletiles, p2.hdoubletiles);
+        assert_eq!(p1.vdoubletiles, p2.vdoubletiles);
+        assert_eq!(p1.gluelinks, p2.gluelinks);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tas_export() -> anyhow::Result<()> {
+        let ts = TileSet::from_file("examples/sierpinski.yaml")?;
+        let (tds, tdp) = ts.to_tas_strings("sierpinski.tds")?;
+        let ntiles = ProcessedTileSet::from_tileset(&ts)?.tile_edges.nrows() - 1;
+        assert_eq!(tds.matches("CREATE").count(), ntiles);
+        assert!(tdp.starts_with("sierpinski.tds\n"));
+        assert_eq!(tdp.lines().count(), 2);
+        Ok(())
+    }
+}
@@ -1,13 +1,15 @@
 #![allow(clippy::too_many_arguments)]
 
+use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Instant;
 
-use crate::base::{GrowError, RgrowError, Tile};
+use crate::base::{new_sim_rng, GrowError, RgrowError, SimRng, Tile};
 use crate::canvas::{CanvasPeriodic, CanvasSquare, CanvasTube, PointSafe2};
 use crate::models::ktam::KTAM;
 use crate::models::oldktam::OldKTAM;
 use crate::state::{NullStateTracker, QuadTreeState, StateTracked};
-use crate::system::{EvolveBounds, SystemWithDimers};
+use crate::system::{DimerInfo, EvolveBounds, SystemWithDimers};
 use crate::tileset::{CanvasType, FromTileSet, Model, TileSet, SIZE_DEFAULT};
 
 use super::*;
@@ -15,12 +17,14 @@ use super::*;
 //use ndarray::Zip;
 use base::{NumTiles, Rate};
 
-use ndarray::ArrayView2;
+use ndarray::{Array2, ArrayView2};
 #[cfg(feature = "python")]
 use numpy::{PyArray2, ToPyArray};
 use rand::{distributions::Uniform, distributions::WeightedIndex, prelude::Distribution};
-use rand::{prelude::SmallRng, SeedableRng};
-use rand::{thread_rng, Rng};
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "use_rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "python")]
 use pyo3::exceptions::PyTypeError;
@@ -29,11 +33,12 @@ use pyo3::prelude::*;
 
 use state::{State, StateWithCreate};
 
-use system::{Orientation, System};
+use system::{Event, Orientation, System};
 //use std::convert::{TryFrom, TryInto};
 
 /// Configuration options for FFS.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 #[cfg_attr(feature = "python", pyclass(get_all, set_all))]
 pub struct FFSRunConfig {
     /// Use constant-variance, variable-configurations-per-surface method.
@@ -58,6 +63,23 @@ pub struct FFSRunConfig {
     pub min_nuc_rate: Option<Rate>,
     pub canvas_size: (usize, usize),
     pub target_size: NumTiles,
+    /// Seed for random number generation.  If set, each trial's generator depends only on the
+    /// seed, the surface and the trial's index, so results are reproducible, regardless of
+    /// the number of threads trials are run on.
+    pub seed: Option<u64>,
+    /// Order parameter defining the surfaces: `start_size`, `size_step` and `target_size` are
+    /// values of it.
+    pub order_parameter: FFSOrderParameter,
+    /// If set, the run is saved to this file after each surface, so that it can be resumed if
+    /// interrupted (see [FFSRun::resume]).
+    pub checkpoint: Option<String>,
+    /// Start from the system's seed, rather than from dimers, measuring the rate at which the
+    /// seeded state reaches `target_size` (see [FFSRun::create_from_states]).
+    pub from_seed: bool,
+    /// Called with the run's progress after each surface; if it returns false, the run stops
+    /// there.  Not saved with the run.
+    #[serde(skip)]
+    pub progress: Option<FFSProgressCallback>,
 }
 
 impl Default for FFSRunConfig {
@@ -79,6 +101,11 @@ impl Default for FFSRunConfig {
             min_nuc_rate: None,
             canvas_size: (64, 64),
             target_size: 100,
+            seed: None,
+            order_parameter: FFSOrderParameter::TileCount,
+            checkpoint: None,
+            from_seed: false,
+            progress: None,
         }
     }
 }
@@ -103,6 +130,11 @@ impl FFSRunConfig {
             "min_nuc_rate" => self.min_nuc_rate = v.extract()?,
             "canvas_size" => self.canvas_size = v.extract()?,
             "target_size" => self.target_size = v.extract()?,
+            "seed" => self.seed = v.extract()?,
+            "order_parameter" => self.order_parameter = v.extract()?,
+            "checkpoint" => self.checkpoint = v.extract()?,
+            "from_seed" => self.from_seed = v.extract()?,
+            "progress" => self.progress = v.extract()?,
             _ => {
                 return Err(PyTypeError::new_err(format!(
                     "Unknown FFSRunConfig setting: {k}"
@@ -134,6 +166,11 @@ impl FFSRunConfig {
         min_nuc_rate: Option<Rate>,
         canvas_size: Option<(usize, usize)>,
         target_size: Option<NumTiles>,
+        seed: Option<u64>,
+        order_parameter: Option<FFSOrderParameter>,
+        checkpoint: Option<String>,
+        from_seed: Option<bool>,
+        progress: Option<FFSProgressCallback>,
     ) -> Self {
         let mut rc = Self::default();
 
@@ -187,15 +224,592 @@ impl FFSRunConfig {
         if let Some(x) = target_size {
             rc.target_size = x;
         }
+        rc.seed = seed;
+        if let Some(x) = order_parameter {
+            rc.order_parameter = x;
+        }
+        rc.checkpoint = checkpoint;
+        if let Some(x) = from_seed {
+            rc.from_seed = x;
+        }
+        rc.progress = progress;
         rc
     }
 }
 
+/// The progress of an FFS run, reported after each surface.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct FFSProgress {
+    /// The index of the surface just reached.
+    pub surface: usize,
+    /// The surface's order parameter value.
+    pub target_size: NumTiles,
+    /// The number of trials run to reach the surface.
+    pub num_trials: usize,
+    /// The number of trials that reached it.
+    pub num_successes: usize,
+    /// The forward probability to the surface.
+    pub forward_prob: f64,
+    /// Wall time since the run was started or resumed, in seconds.
+    pub elapsed: f64,
+    /// The nucleation rate estimated from the surfaces so far.
+    pub nucleation_rate: Rate,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl FFSProgress {
+    fn __repr__(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+type ProgressFn = dyn Fn(&FFSProgress) -> Result<bool, GrowError> + Send + Sync;
+
+/// A function called with an FFS run's progress after each surface, returning false to stop
+/// the run.  From Python, any callable taking an `FFSProgress` can be used: the run stops if it
+/// returns False, and fails with the exception if it raises one.
+#[derive(Clone)]
+pub struct FFSProgressCallback {
+    f: Arc<ProgressFn>,
+    #[cfg(feature = "python")]
+    obj: Option<PyObject>,
+}
+
+impl FFSProgressCallback {
+    pub fn new(f: impl Fn(&FFSProgress) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            f: Arc::new(move |progress| Ok(f(progress))),
+            #[cfg(feature = "python")]
+            obj: None,
+        }
+    }
+
+    /// As [FFSProgressCallback::new], for a function that can fail, failing the run.
+    pub fn try_new(
+        f: impl Fn(&FFSProgress) -> Result<bool, GrowError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            f: Arc::new(f),
+            #[cfg(feature = "python")]
+            obj: None,
+        }
+    }
+
+    pub fn call(&self, progress: &FFSProgress) -> Result<bool, GrowError> {
+        (self.f)(progress)
+    }
+}
+
+impl Debug for FFSProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FFSProgressCallback")
+    }
+}
+
+#[cfg(feature = "python")]
+impl IntoPy<PyObject> for FFSProgressCallback {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        self.obj.unwrap_or_else(|| py.None())
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'source> FromPyObject<'source> for FFSProgressCallback {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if !ob.is_callable() {
+            return Err(PyTypeError::new_err(
+                "FFS progress callback must be callable",
+            ));
+        }
+        let obj: PyObject = ob.into();
+        let callback = obj.clone();
+        Ok(Self {
+            f: Arc::new(move |progress| {
+                Python::with_gil(|py| {
+                    let ret = callback.call1(py, (progress.clone(),))?;
+                    Ok(ret.extract::<bool>(py).unwrap_or(true))
+                })
+            }),
+            obj: Some(obj),
+        })
+    }
+}
+
+/// A measure of progress towards nucleation, defining the surfaces of FFS.  Order parameters
+/// are sums of contributions from each point, depending only on the tiles at the point and to
+/// its east and south, so they can be updated locally as events take place.  Order parameters
+/// other than those of [FFSOrderParameter] can be used with [FFSOrderParameter::Custom].
+pub trait OrderParameter: Debug + Send + Sync {
+    /// The contribution of point `p`.
+    fn at_point(&self, system: &dyn SystemWithDimers, state: &dyn State, p: PointSafe2)
+        -> NumTiles;
+
+    /// Calculates the order parameter of a state.
+    fn calc(&self, system: &dyn SystemWithDimers, state: &dyn State) -> NumTiles {
+        let mut total = 0;
+        for r in 0..state.nrows() {
+            for c in 0..state.ncols() {
+                if state.inbounds((r, c)) {
+                    total += self.at_point(system, state, PointSafe2((r, c)));
+                }
+            }
+        }
+        total
+    }
+
+    /// Sums the contributions of `points`.
+    fn sum_at(
+        &self,
+        system: &dyn SystemWithDimers,
+        state: &dyn State,
+        points: &[PointSafe2],
+    ) -> NumTiles {
+        points
+            .iter()
+            .map(|p| self.at_point(system, state, *p))
+            .sum()
+    }
+}
+
+/// FFS order parameters.  In Python, the built-in ones are given as `"tile_count"`,
+/// `("tile_subset", [tiles...])` and `("strong_bonds", threshold)`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum FFSOrderParameter {
+    /// The number of tiles (as counted by the state, so duples count as one tile).
+    #[default]
+    TileCount,
+    /// The number of tiles of the given types.
+    TileSubset(Vec<Tile>),
+    /// The number of bonds between neighboring tiles with energy (see
+    /// [SystemWithDimers::bond_energy_ns]) of at least the given threshold.
+    StrongBonds(f64),
+    /// Any other order parameter.  Runs using one can't be saved, and it can't be used from
+    /// Python.
+    #[serde(skip)]
+    Custom(Arc<dyn OrderParameter>),
+}
+
+impl OrderParameter for FFSOrderParameter {
+    fn at_point(
+        &self,
+        system: &dyn SystemWithDimers,
+        state: &dyn State,
+        p: PointSafe2,
+    ) -> NumTiles {
+        if let FFSOrderParameter::Custom(order) = self {
+            return order.at_point(system, state, p);
+        }
+        let t = state.tile_at_point(p);
+        if t == 0 {
+            return 0;
+        }
+        match self {
+            FFSOrderParameter::TileCount => 1,
+            FFSOrderParameter::TileSubset(tiles) => tiles.contains(&t) as NumTiles,
+            FFSOrderParameter::StrongBonds(threshold) => {
+                let (te, ts) = (state.tile_to_e(p), state.tile_to_s(p));
+                ((te != 0) && (system.bond_energy_we(t, te) >= *threshold)) as NumTiles
+                    + ((ts != 0) && (system.bond_energy_ns(t, ts) >= *threshold)) as NumTiles
+            }
+            FFSOrderParameter::Custom(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl IntoPy<PyObject> for FFSOrderParameter {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            FFSOrderParameter::TileCount => "tile_count".into_py(py),
+            FFSOrderParameter::TileSubset(tiles) => ("tile_subset", tiles).into_py(py),
+            FFSOrderParameter::StrongBonds(threshold) => ("strong_bonds", threshold).into_py(py),
+            FFSOrderParameter::Custom(order) => ("custom", format!("{order:?}")).into_py(py),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'source> FromPyObject<'source> for FFSOrderParameter {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let (name, arg) = match ob.extract::<&str>() {
+            Ok(name) => (name, None),
+            Err(_) => {
+                let (name, arg): (&str, &PyAny) = ob.extract()?;
+                (name, Some(arg))
+            }
+        };
+        match (name, arg) {
+            ("tile_count", None) => Ok(FFSOrderParameter::TileCount),
+            ("tile_subset", Some(tiles)) => Ok(FFSOrderParameter::TileSubset(tiles.extract()?)),
+            ("strong_bonds", Some(threshold)) => {
+                Ok(FFSOrderParameter::StrongBonds(threshold.extract()?))
+            }
+            _ => Err(PyTypeError::new_err(format!(
+                "Unknown FFS order parameter: {ob}"
+            ))),
+        }
+    }
+}
+
+/// Points whose contribution to an order parameter may change with `event`: those within two
+/// sites of a point it changes (which covers the other halves of duples).
+fn order_parameter_points<St: State + ?Sized>(
+    state: &St,
+    event: &Event,
+    points: &mut Vec<PointSafe2>,
+) {
+    points.clear();
+    let mut add_around = |p: &PointSafe2| {
+        let p = p.0;
+        let (n, e, s, w) = (
+            state.u_move_point_n(p),
+            state.u_move_point_e(p),
+            state.u_move_point_s(p),
+            state.u_move_point_w(p),
+        );
+        for q in [
+            p,
+            n,
+            e,
+            s,
+            w,
+            state.u_move_point_n(n),
+            state.u_move_point_e(n),
+            state.u_move_point_e(e),
+            state.u_move_point_e(s),
+            state.u_move_point_s(s),
+            state.u_move_point_w(s),
+            state.u_move_point_w(w),
+            state.u_move_point_w(n),
+        ] {
+            if state.inbounds(q) {
+                points.push(PointSafe2(q));
+            }
+        }
+    };
+    match event {
+        Event::None => (),
+        Event::MonomerAttachment(p, _)
+        | Event::MonomerDetachment(p)
+        | Event::MonomerChange(p, _) => add_around(p),
+        Event::PolymerAttachment(changelist) | Event::PolymerChange(changelist) => {
+            changelist.iter().for_each(|(p, _)| add_around(p))
+        }
+        Event::PolymerDetachment(changelist) => changelist.iter().for_each(add_around),
+    }
+    points.sort_unstable_by_key(|p| p.0);
+    points.dedup_by_key(|p| p.0);
+}
+
+/// The order parameter of `state`, as [evolve_to_surface] tracks it: for tile count, this is the
+/// state's count, which leaves out tiles (such as seed tiles) that the system doesn't count.
+pub(crate) fn order_value<Sy: SystemWithDimers + System, St: State>(
+    system: &Sy,
+    state: &St,
+    order: &FFSOrderParameter,
+) -> NumTiles {
+    match order {
+        FFSOrderParameter::TileCount => state.n_tiles(),
+        _ => order.calc(system, state),
+    }
+}
+
+/// Where trials end, other than at the next surface.
+#[derive(Debug, Clone, Copy)]
+enum Floor {
+    /// When the state has no tiles (for runs from dimers).
+    Empty,
+    /// When the order parameter falls to the starting basin (for runs from starting states).
+    Basin(NumTiles),
+    /// Nowhere (for trials from starting states to the first surface).
+    None,
+}
+
+/// Evolves `state` until its order parameter reaches `target`, it reaches `floor`, no events are
+/// possible, or `bounds` are reached, returning the order parameter.  For tile count,
+/// this is [System::evolve_with_rng] with size bounds; otherwise, exact single steps are taken,
+/// and the order parameter is updated locally after each event, so `bounds` can't use leaping
+/// or parallel evolution.
+fn evolve_to_surface<Sy: SystemWithDimers + System, St: State>(
+    system: &Sy,
+    state: &mut St,
+    order: &FFSOrderParameter,
+    target: NumTiles,
+    floor: Floor,
+    bounds: EvolveBounds,
+    rng: &mut SimRng,
+) -> Result<NumTiles, GrowError> {
+    if let FFSOrderParameter::TileCount = order {
+        let mut bounds = bounds;
+        bounds.size_max = Some(target);
+        bounds.size_min = match floor {
+            Floor::Empty => Some(0),
+            Floor::Basin(basin) => Some(basin),
+            Floor::None => None,
+        };
+        system.evolve_with_rng(state, bounds, rng)?;
+        return Ok(state.n_tiles());
+    }
+
+    if bounds.leap_tolerance.is_some() || bounds.parallel_domain_size.is_some() {
+        return Err(GrowError::NotImplemented(
+            "Leaping and parallel evolution are only implemented for the tile count order parameter"
+                .to_string(),
+        ));
+    }
+
+    let mut value = order.calc(system, state);
+    let mut rtime = bounds.for_time.unwrap_or(f64::INFINITY);
+    if let Some(t) = bounds.total_time {
+        rtime = rtime.min(t - state.time());
+    }
+    let start_time = bounds.for_wall_time.map(|_| std::time::Instant::now());
+    let mut events = 0;
+    let mut points = Vec::new();
+
+    while (value < target)
+        && match floor {
+            Floor::Empty => state.n_tiles() > 0,
+            Floor::Basin(basin) => value > basin,
+            Floor::None => true,
+        }
+        && (state.total_rate() > 0.)
+        && !bounds.for_events.is_some_and(|e| events >= e)
+        && !bounds
+            .for_wall_time
+            .is_some_and(|t| start_time.unwrap().elapsed() >= t)
+    {
+        let time_step = -f64::ln(rng.gen()) / state.total_rate();
+        if time_step > rtime {
+            state.add_time(rtime);
+            break;
+        }
+        rtime -= time_step;
+        state.add_time(time_step);
+        let (point, remainder) = match state.choose_point(rng) {
+            Ok(x) => x,
+            Err(_) => {
+                state.resum();
+                state.choose_point(rng)?
+            }
+        };
+        let event = system.choose_event_at_point(state, PointSafe2(point), remainder);
+        system.record_event_stats(state, &event);
+        if let Event::None = event {
+            continue;
+        }
+        order_parameter_points(state, &event, &mut points);
+        let before = order.sum_at(system, state, &points);
+        system.perform_event_with_counts(state, &event);
+        system.update_after_event(state, &event);
+        value = value + order.sum_at(system, state, &points) - before;
+        events += 1;
+    }
+    Ok(value)
+}
+
+/// An estimate, with lower and upper bounds of a confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct ConfidenceInterval {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl ConfidenceInterval {
+    pub fn contains(&self, x: f64) -> bool {
+        (self.lower <= x) & (x <= self.upper)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ConfidenceInterval {
+    fn __repr__(&self) -> String {
+        format!(
+            "ConfidenceInterval({:e}, lower={:e}, upper={:e})",
+            self.estimate, self.lower, self.upper
+        )
+    }
+}
+
 pub trait FFSResult: Send + Sync {
     fn nucleation_rate(&self) -> f64;
     fn forward_vec(&self) -> &Vec<f64>;
     fn dimerization_rate(&self) -> f64;
+    /// The surfaces, starting with the dimer surface, so that `forward_vec()[i]` is the
+    /// probability of reaching `surfaces()[i + 1]`.
     fn surfaces(&self) -> Vec<&dyn FFSSurface>;
+    /// The configuration the run was made (or last extended) with.
+    fn config(&self) -> &FFSRunConfig;
+    /// For runs from a starting ensemble, the largest order parameter of the starting states:
+    /// trials that fall back to it fail.  None for runs from dimers.
+    fn basin(&self) -> Option<NumTiles> {
+        None
+    }
+
+    /// A copy of the result's surfaces, configurations, lineage and statistics, that can be
+    /// saved and loaded.
+    fn to_data(&self) -> FFSRunData {
+        FFSRunData {
+            config: self.config().clone(),
+            dimerization_rate: self.dimerization_rate(),
+            forward_prob: self.forward_vec().clone(),
+            basin: self.basin(),
+            levels: self
+                .surfaces()
+                .iter()
+                .map(|surface| FFSLevelData {
+                    configs: surface.configs().iter().map(|c| c.to_owned()).collect(),
+                    previous_list: surface.previous_list(),
+                    num_states: surface.num_states(),
+                    num_trials: surface.num_trials(),
+                    target_size: surface.target_size(),
+                    parent_trials: surface.parent_trials(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Saves the result to a file (see [FFSRunData::save]).
+    fn save(&self, path: &str) -> Result<(), FFSDataError> {
+        self.to_data().save(path)
+    }
+
+    /// Wilson score intervals for each forward probability, at the given confidence level (eg,
+    /// 0.95).
+    fn forward_prob_intervals(&self, confidence: f64) -> Vec<ConfidenceInterval> {
+        let z = normal_quantile(0.5 + confidence / 2.);
+        let surfaces = self.surfaces();
+        self.forward_vec()
+            .iter()
+            .zip(&surfaces[1..])
+            .map(|(&p, surface)| wilson_interval(p, surface.num_trials(), z))
+            .collect()
+    }
+
+    /// The estimated variance of the nucleation rate divided by its square, treating each
+    /// surface as an independent binomial sample: $\sum_i (1 - p_i) / (p_i N_i)$ (Allen,
+    /// Frenkel and ten Wolde, J. Chem. Phys. 124, 024102 (2006)).  This neglects correlations
+    /// between configurations that share ancestors, which `nucleation_rate_bootstrap` includes.
+    fn nucleation_rate_relative_variance(&self) -> f64 {
+        let surfaces = self.surfaces();
+        self.forward_vec()
+            .iter()
+            .zip(&surfaces[1..])
+            .map(|(&p, surface)| (1. - p) / (p * surface.num_trials() as f64))
+            .sum()
+    }
+
+    /// A confidence interval for the nucleation rate from `nucleation_rate_relative_variance`,
+    /// taking the rate to be log-normally distributed.  If a forward probability is zero, the
+    /// upper bound is from the Wilson intervals of the forward probabilities instead.
+    fn nucleation_rate_interval(&self, confidence: f64) -> ConfidenceInterval {
+        let estimate = self.nucleation_rate();
+        if estimate == 0. {
+            let upper = self
+                .forward_prob_intervals(confidence)
+                .iter()
+                .fold(self.dimerization_rate(), |acc, i| acc * i.upper);
+            return ConfidenceInterval {
+                estimate,
+                lower: 0.,
+                upper,
+            };
+        }
+        let z = normal_quantile(0.5 + confidence / 2.);
+        let sigma = self.nucleation_rate_relative_variance().ln_1p().sqrt();
+        ConfidenceInterval {
+            estimate,
+            lower: estimate * (-z * sigma).exp(),
+            upper: estimate * (z * sigma).exp(),
+        }
+    }
+
+    /// A percentile bootstrap confidence interval for the nucleation rate.  Each sample redraws
+    /// the number of successes at the first surface, then resamples the configurations there with
+    /// replacement.  At each later surface, the resampled weights are carried along the lineages
+    /// in `previous_list`, so that configurations descended from the same ancestor are resampled
+    /// together, giving the trials started from the resampled configurations and the fraction
+    /// of them that succeeded.  The number of successes is then redrawn from those trials, so
+    /// that each surface's own sampling variation is included.
+    fn nucleation_rate_bootstrap(
+        &self,
+        confidence: f64,
+        samples: usize,
+        seed: Option<u64>,
+    ) -> ConfidenceInterval {
+        let mut rng = match seed {
+            Some(seed) => SimRng::seed_from_u64(seed),
+            None => new_sim_rng(),
+        };
+        let surfaces = self.surfaces();
+        let forward = self.forward_vec();
+
+        let mut rates: Vec<f64> = (0..samples)
+            .map(|_| {
+                let first = surfaces[1];
+                let mut rate = self.dimerization_rate()
+                    * sample_binomial(first.num_trials(), forward[0], &mut rng)
+                    / first.num_trials() as f64;
+
+                let mut weights = vec![0.; first.num_states()];
+                if !weights.is_empty() {
+                    let chooser = Uniform::new(0, weights.len());
+                    for _ in 0..weights.len() {
+                        weights[chooser.sample(&mut rng)] += 1.;
+                    }
+                }
+
+                for surface in &surfaces[2..] {
+                    let previous = surface.previous_list();
+                    let trials = surface.parent_trials();
+                    let attempts: f64 = if trials.len() == weights.len() {
+                        weights
+                            .iter()
+                            .zip(&trials)
+                            .map(|(w, &t)| w * t as f64)
+                            .sum()
+                    } else {
+                        // Without per-configuration trial counts, share the trials out evenly.
+                        weights.iter().sum::<f64>() * surface.num_trials() as f64
+                            / weights.len() as f64
+                    };
+                    weights = previous.iter().map(|&i| weights[i]).collect();
+                    let carried: f64 = weights.iter().sum();
+                    if carried == 0. {
+                        return 0.;
+                    }
+                    let successes = sample_binomial(
+                        attempts.round() as usize,
+                        (carried / attempts).min(1.),
+                        &mut rng,
+                    );
+                    if successes == 0. {
+                        return 0.;
+                    }
+                    rate *= successes / attempts;
+                }
+                rate
+            })
+            .collect();
+        rates.sort_by(f64::total_cmp);
+
+        let percentile = |q: f64| {
+            if rates.is_empty() {
+                return f64::NAN;
+            }
+            rates[((q * rates.len() as f64) as usize).min(rates.len() - 1)]
+        };
+        ConfidenceInterval {
+            estimate: self.nucleation_rate(),
+            lower: percentile(0.5 - confidence / 2.),
+            upper: percentile(0.5 + confidence / 2.),
+        }
+    }
 }
 
 pub trait FFSSurface: Send + Sync {
@@ -207,56 +821,282 @@ pub trait FFSSurface: Send + Sync {
     }
     fn previous_list(&self) -> Vec<usize>;
     fn num_configs(&self) -> usize;
+    /// The number of configurations that reached the surface, whether or not they were kept.
+    fn num_states(&self) -> usize;
+    /// The number of trials started from each configuration of the previous surface, or an
+    /// empty list if this wasn't recorded.
+    fn parent_trials(&self) -> Vec<usize>;
     fn num_trials(&self) -> usize;
     fn target_size(&self) -> NumTiles;
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum FFSDataError {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Saved FFS run has no configurations at its last surface, so it can't be resumed.")]
+    NoConfigs,
+    #[error("Saved FFS run is inconsistent: {0}.")]
+    Inconsistent(String),
+}
+
+/// The surfaces, configurations, lineage and statistics of an FFS run, without the system or
+/// states, as saved to and loaded from files.  Loaded data can be analyzed directly, as an
+/// [FFSResult], or used to resume the run with [FFSRun::resume].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FFSRunData {
+    pub config: FFSRunConfig,
+    pub dimerization_rate: f64,
+    pub forward_prob: Vec<f64>,
+    #[serde(default)]
+    pub basin: Option<NumTiles>,
+    pub levels: Vec<FFSLevelData>,
+}
+
+/// A saved FFS surface.  See [FFSLevel].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FFSLevelData {
+    #[serde(with = "sparse_configs")]
+    pub configs: Vec<Array2<Tile>>,
+    pub previous_list: Vec<usize>,
+    pub num_states: usize,
+    pub num_trials: usize,
+    pub target_size: NumTiles,
+    #[serde(default)]
+    pub parent_trials: Vec<usize>,
+}
+
+impl FFSRunData {
+    /// Saves the data to a JSON file.  The file is written in full and then moved into place,
+    /// so an interruption while saving leaves any previous file intact.
+    pub fn save(&self, path: &str) -> Result<(), FFSDataError> {
+        let tmp = format!("{path}.tmp");
+        let file = std::io::BufWriter::new(std::fs::File::create(&tmp)?);
+        serde_json::to_writer(file, self)?;
+        std::fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    /// Loads data saved with [FFSRunData::save], checking that it is consistent.
+    pub fn load(path: &str) -> Result<Self, FFSDataError> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let data: Self = serde_json::from_reader(file)?;
+        data.validate()?;
+        Ok(data)
+    }
+
+    /// Checks that there is a forward probability for each surface after the first, and that
+    /// each surface's lineage and configurations agree with its number of states.
+    pub fn validate(&self) -> Result<(), FFSDataError> {
+        let inconsistent = |s: String| Err(FFSDataError::Inconsistent(s));
+        let expected = self.levels.len().saturating_sub(1);
+        if self.forward_prob.len() != expected {
+            return inconsistent(format!(
+                "{} forward probabilities for {} surfaces",
+                self.forward_prob.len(),
+                self.levels.len()
+            ));
+        }
+        for (i, level) in self.levels.iter().enumerate() {
+            if !level.previous_list.is_empty() && level.previous_list.len() != level.num_states {
+                return inconsistent(format!(
+                    "surface {i} has {} states but {} previous states",
+                    level.num_states,
+                    level.previous_list.len()
+                ));
+            }
+            if level.configs.len() > level.num_states {
+                return inconsistent(format!(
+                    "surface {i} has {} states but {} configurations",
+                    level.num_states,
+                    level.configs.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FFSResult for FFSRunData {
+    fn nucleation_rate(&self) -> Rate {
+        self.dimerization_rate * self.forward_prob.iter().product::<f64>()
+    }
+
+    fn forward_vec(&self) -> &Vec<f64> {
+        &self.forward_prob
+    }
+
+    fn dimerization_rate(&self) -> f64 {
+        self.dimerization_rate
+    }
+
+    fn surfaces(&self) -> Vec<&dyn FFSSurface> {
+        self.levels
+            .iter()
+            .map(|level| level as &dyn FFSSurface)
+            .collect()
+    }
+
+    fn config(&self) -> &FFSRunConfig {
+        &self.config
+    }
+
+    fn basin(&self) -> Option<NumTiles> {
+        self.basin
+    }
+
+    fn to_data(&self) -> FFSRunData {
+        self.clone()
+    }
+}
+
+impl FFSSurface for FFSLevelData {
+    fn get_config(&self, i: usize) -> ArrayView2<'_, Tile> {
+        self.configs[i].view()
+    }
+
+    fn previous_list(&self) -> Vec<usize> {
+        self.previous_list.clone()
+    }
+
+    fn num_configs(&self) -> usize {
+        self.configs.len()
+    }
+
+    fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    fn parent_trials(&self) -> Vec<usize> {
+        self.parent_trials.clone()
+    }
+
+    fn num_trials(&self) -> usize {
+        self.num_trials
+    }
+
+    fn target_size(&self) -> NumTiles {
+        self.target_size
+    }
+}
+
+/// Configurations are mostly empty, so they are stored as their shape and a list of
+/// `(row, col, tile)` for nonzero tiles.
+mod sparse_configs {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct SparseConfig {
+        shape: (usize, usize),
+        tiles: Vec<(usize, usize, Tile)>,
+    }
+
+    pub fn serialize<S: Serializer>(configs: &[Array2<Tile>], ser: S) -> Result<S::Ok, S::Error> {
+        ser.collect_seq(configs.iter().map(|config| {
+            SparseConfig {
+                shape: config.dim(),
+                tiles: config
+                    .indexed_iter()
+                    .filter(|(_, &t)| t != 0)
+                    .map(|((r, c), &t)| (r, c, t))
+                    .collect(),
+            }
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<Array2<Tile>>, D::Error> {
+        let sparse = Vec::<SparseConfig>::deserialize(de)?;
+        sparse
+            .into_iter()
+            .map(|config| {
+                let mut array = Array2::zeros(config.shape);
+                for (r, c, t) in config.tiles {
+                    *array.get_mut((r, c)).ok_or_else(|| {
+                        serde::de::Error::custom(format!("tile at ({r}, {c}) is out of bounds"))
+                    })? = t;
+                }
+                Ok(array)
+            })
+            .collect()
+    }
+}
+
 impl TileSet {
     pub fn run_ffs(&self, config: &FFSRunConfig) -> Result<Box<dyn FFSResult>, RgrowError> {
+        self.create_or_resume_ffs(None, config)
+    }
+
+    /// Resumes (or extends) a saved FFS run of this tile set, continuing to `config.target_size`.
+    /// See [FFSRun::resume].
+    pub fn resume_ffs(
+        &self,
+        data: &FFSRunData,
+        config: &FFSRunConfig,
+    ) -> Result<Box<dyn FFSResult>, RgrowError> {
+        self.create_or_resume_ffs(Some(data), config)
+    }
+
+    fn create_or_resume_ffs(
+        &self,
+        data: Option<&FFSRunData>,
+        config: &FFSRunConfig,
+    ) -> Result<Box<dyn FFSResult>, RgrowError> {
         match self.model.unwrap_or(Model::KTAM) {
             Model::KTAM => match self.canvas_type.unwrap_or(CanvasType::Periodic) {
                 CanvasType::Square => Ok(Box::new(FFSRun::<
                     QuadTreeState<CanvasSquare, NullStateTracker>,
-                >::create_from_tileset::<KTAM>(
-                    self, config
+                >::create_or_resume_from_tileset::<KTAM>(
+                    self, data, config
                 )?)),
                 CanvasType::Periodic => Ok(Box::new(FFSRun::<
                     QuadTreeState<CanvasPeriodic, NullStateTracker>,
-                >::create_from_tileset::<KTAM>(
-                    self, config
+                >::create_or_resume_from_tileset::<KTAM>(
+                    self, data, config
                 )?)),
                 CanvasType::Tube => Ok(Box::new(FFSRun::<
                     QuadTreeState<CanvasTube, NullStateTracker>,
-                >::create_from_tileset::<KTAM>(
-                    self, config
+                >::create_or_resume_from_tileset::<KTAM>(
+                    self, data, config
                 )?)),
             },
             Model::ATAM => Err(GrowError::FFSCannotRunATAM.into()),
             Model::OldKTAM => match self.canvas_type.unwrap_or(CanvasType::Periodic) {
                 CanvasType::Square => Ok(Box::new(FFSRun::<
                     QuadTreeState<CanvasSquare, NullStateTracker>,
-                >::create_from_tileset::<OldKTAM>(
-                    self, config
+                >::create_or_resume_from_tileset::<OldKTAM>(
+                    self, data, config
                 )?)),
                 CanvasType::Periodic => Ok(Box::new(FFSRun::<
                     QuadTreeState<CanvasPeriodic, NullStateTracker>,
-                >::create_from_tileset::<OldKTAM>(
-                    self, config
+                >::create_or_resume_from_tileset::<OldKTAM>(
+                    self, data, config
                 )?)),
                 CanvasType::Tube => Ok(Box::new(FFSRun::<
                     QuadTreeState<CanvasTube, NullStateTracker>,
-                >::create_from_tileset::<OldKTAM>(
-                    self, config
+                >::create_or_resume_from_tileset::<OldKTAM>(
+                    self, data, config
                 )?)),
             },
         }
     }
 }
 
+/// An FFS run.  Runs from dimers measure the nucleation rate, in M/s: `level_list[0]` holds the
+/// dimers, and `dimerization_rate` is their total formation rate.  Runs from a starting ensemble
+/// (see [FFSRun::create_from_states]) measure the rate, in /s, at which a starting state reaches
+/// the target: `level_list[0]` holds the starting states, and `dimerization_rate` is instead
+/// the flux from them to the first surface.
 pub struct FFSRun<St: State + StateTracked<NullStateTracker>> {
     pub level_list: Vec<FFSLevel<St>>,
     pub dimerization_rate: f64,
     pub forward_prob: Vec<f64>,
+    pub config: FFSRunConfig,
+    pub basin: Option<NumTiles>,
+    /// When the run was started or resumed, for progress reports.
+    started: Instant,
 }
 
 impl<St: State + StateTracked<NullStateTracker>> FFSResult for FFSRun<St> {
@@ -278,6 +1118,14 @@ impl<St: State + StateTracked<NullStateTracker>> FFSResult for FFSRun<St> {
     fn dimerization_rate(&self) -> f64 {
         self.dimerization_rate
     }
+
+    fn config(&self) -> &FFSRunConfig {
+        &self.config
+    }
+
+    fn basin(&self) -> Option<NumTiles> {
+        self.basin
+    }
 }
 
 impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullStateTracker>>
@@ -287,6 +1135,15 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
         system: &mut Sy,
         config: &FFSRunConfig,
     ) -> Result<Self, GrowError> {
+        if config.from_seed {
+            if system.seed_locs().is_empty() {
+                return Err(GrowError::FFSNoSeed);
+            }
+            let mut state = St::empty(config.canvas_size)?;
+            system.configure_empty_state(&mut state)?;
+            return Self::create_from_states(system, vec![state], config);
+        }
+
         let level_list = Vec::new();
 
         let dimerization_rate = system
@@ -298,57 +1155,252 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
             level_list,
             dimerization_rate,
             forward_prob: Vec::new(),
+            config: config.clone(),
+            basin: None,
+            started: Instant::now(),
         };
 
         let (first_level, dimer_level) = FFSLevel::nmers_from_dimers(system, config)?;
 
         ret.forward_prob.push(first_level.p_r);
 
-        let mut current_size = first_level.target_size;
-
         ret.level_list.push(dimer_level);
         ret.level_list.push(first_level);
 
-        let mut above_cutoff: usize = 0;
+        ret.checkpoint()?;
+        if ret.report_progress()? {
+            ret.extend(system, config)?;
+        }
 
-        while current_size < config.target_size {
-            let last = ret.level_list.last_mut().unwrap();
+        Ok(ret)
+    }
 
-            let next = last.next_level(system, config)?;
-            if !config.keep_configs {
-                last.drop_states();
-            }
+    /// Creates a run from an ensemble of starting states, such as a seeded state, rather than
+    /// from dimers, measuring the rate at which a starting state reaches `config.target_size`.
+    /// The starting basin is the largest order parameter of the starting states, and
+    /// `config.start_size`, the first surface, must be above it.  Trials from later surfaces
+    /// fail if they fall back to the basin.
+    pub fn create_from_states<Sy: SystemWithDimers + System>(
+        system: &mut Sy,
+        states: Vec<St>,
+        config: &FFSRunConfig,
+    ) -> Result<Self, GrowError> {
+        let basin = states
+            .iter()
+            .map(|state| order_value(&*system, state, &config.order_parameter))
+            .max()
+            .ok_or(GrowError::FFSNoStartStates)?;
+        if basin >= config.start_size {
+            return Err(GrowError::FFSStartAboveSurface(basin, config.start_size));
+        }
+
+        let (first_level, flux) = FFSLevel::from_start_states(system, &states, basin, config)?;
+
+        let num_states = states.len();
+        let start_level = FFSLevel {
+            state_list: states,
+            previous_list: Vec::new(),
+            p_r: 1.0,
+            num_states,
+            num_trials: num_states,
+            target_size: basin,
+            parent_trials: Vec::new(),
+        };
+
+        let mut ret = Self {
+            level_list: vec![start_level],
+            dimerization_rate: flux,
+            forward_prob: vec![first_level.p_r],
+            config: config.clone(),
+            basin: Some(basin),
+            started: Instant::now(),
+        };
+        ret.level_list.push(first_level);
+
+        ret.checkpoint()?;
+        if ret.report_progress()? {
+            ret.extend(system, config)?;
+        }
+
+        Ok(ret)
+    }
+
+    /// Creates a run from starting configurations, as for [FFSRun::create_from_states].
+    pub fn create_from_configs<Sy: SystemWithDimers + System>(
+        system: &mut Sy,
+        configs: &[Array2<Tile>],
+        config: &FFSRunConfig,
+    ) -> Result<Self, GrowError> {
+        let states = configs
+            .iter()
+            .map(|c| state_from_config(&*system, c))
+            .collect::<Result<Vec<St>, _>>()?;
+        Self::create_from_states(system, states, config)
+    }
+
+    /// Continues the run with further surfaces, until `config.target_size` is reached, or the
+    /// run is cut off early as `config` specifies.
+    pub fn extend<Sy: SystemWithDimers + System>(
+        &mut self,
+        system: &mut Sy,
+        config: &FFSRunConfig,
+    ) -> Result<(), GrowError> {
+        self.config = config.clone();
+
+        let mut current_size = self.level_list.last().unwrap().target_size;
+
+        // Surfaces above the cutoff probability at the end of the run so far still count.
+        let mut above_cutoff = self
+            .forward_prob
+            .iter()
+            .skip(1)
+            .rev()
+            .take_while(|&&pf| pf > config.cutoff_probability)
+            .count();
+
+        // Stopping conditions are checked before each surface, so that resuming a run that has
+        // already stopped doesn't add another.
+        while current_size < config.target_size {
+            if config.early_cutoff
+                && (above_cutoff > config.cutoff_number)
+                && (current_size >= config.min_cutoff_size)
+            {
+                break;
+            }
+            if config
+                .min_nuc_rate
+                .is_some_and(|min_nuc_rate| self.nucleation_rate() < min_nuc_rate)
+            {
+                break;
+            }
+
+            let last = self.level_list.last_mut().unwrap();
+
+            let next = last.next_level(system, config, self.basin)?;
+            if !config.keep_configs {
+                last.drop_states();
+            }
             let pf = next.p_r;
-            ret.forward_prob.push(pf);
-            // println!(
-            //     "Done with target size {}: p_f {}, used {} trials for {} states.",
-            //     last.target_size, pf, next.num_trials, next.num_states
-            // );
+            self.forward_prob.push(pf);
             current_size = next.target_size;
-            ret.level_list.push(next);
-
-            if config.early_cutoff {
-                if pf > config.cutoff_probability {
-                    above_cutoff += 1;
-                    if (above_cutoff > config.cutoff_number)
-                        & (current_size >= config.min_cutoff_size)
-                    {
-                        break;
-                    }
-                } else {
-                    above_cutoff = 0;
-                }
+            self.level_list.push(next);
+            self.checkpoint()?;
+
+            if !self.report_progress()? {
+                break;
             }
 
-            if let Some(min_nuc_rate) = config.min_nuc_rate {
-                if ret.nucleation_rate() < min_nuc_rate {
-                    break;
-                }
+            if pf > config.cutoff_probability {
+                above_cutoff += 1;
+            } else {
+                above_cutoff = 0;
             }
         }
 
+        Ok(())
+    }
+
+    /// Logs the progress of the run after its last surface, and passes it to
+    /// `config.progress`, if set, returning whether the run should continue.
+    fn report_progress(&self) -> Result<bool, GrowError> {
+        let level = self.level_list.last().unwrap();
+        let progress = FFSProgress {
+            surface: self.level_list.len() - 1,
+            target_size: level.target_size,
+            num_trials: level.num_trials,
+            num_successes: level.num_states,
+            forward_prob: level.p_r,
+            elapsed: self.started.elapsed().as_secs_f64(),
+            nucleation_rate: self.nucleation_rate(),
+        };
+        log::info!(
+            "FFS surface {} (size {}): {} of {} trials succeeded, p_f {:.4}, rate {:e}, {:.1} s",
+            progress.surface,
+            progress.target_size,
+            progress.num_successes,
+            progress.num_trials,
+            progress.forward_prob,
+            progress.nucleation_rate,
+            progress.elapsed,
+        );
+        match &self.config.progress {
+            Some(callback) => callback.call(&progress),
+            None => Ok(true),
+        }
+    }
+
+    /// Saves the run to `config.checkpoint`, if set.
+    fn checkpoint(&self) -> Result<(), GrowError> {
+        if let Some(path) = &self.config.checkpoint {
+            self.save(path)?;
+        }
+        Ok(())
+    }
+
+    /// Recreates a run from saved data, rebuilding states from the saved configurations for
+    /// `system`, which should be the system the run was made with.
+    pub fn from_data<Sy: SystemWithDimers + System>(
+        system: &Sy,
+        data: &FFSRunData,
+    ) -> Result<Self, GrowError> {
+        data.validate()?;
+        let level_list = data
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                let state_list = level
+                    .configs
+                    .iter()
+                    .map(|config| state_from_config(system, config))
+                    .collect::<Result<Vec<_>, GrowError>>()?;
+                Ok(FFSLevel {
+                    state_list,
+                    previous_list: level.previous_list.clone(),
+                    p_r: if i == 0 {
+                        1.0
+                    } else {
+                        data.forward_prob[i - 1]
+                    },
+                    num_states: level.num_states,
+                    num_trials: level.num_trials,
+                    target_size: level.target_size,
+                    parent_trials: level.parent_trials.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, GrowError>>()?;
+
+        Ok(Self {
+            level_list,
+            dimerization_rate: data.dimerization_rate,
+            forward_prob: data.forward_prob.clone(),
+            config: data.config.clone(),
+            basin: data.basin,
+            started: Instant::now(),
+        })
+    }
+
+    /// Resumes a saved run (for example, from a checkpoint of an interrupted run), or extends a
+    /// finished one, continuing from its last surface to `config.target_size`.  `system` should
+    /// be the system the run was made with.  With `config.seed` set, resuming gives the same
+    /// result as an uninterrupted run.  If the saved run doesn't have a first surface, it is
+    /// started again.
+    pub fn resume<Sy: SystemWithDimers + System>(
+        system: &mut Sy,
+        data: &FFSRunData,
+        config: &FFSRunConfig,
+    ) -> Result<Self, GrowError> {
+        if data.levels.len() < 2 {
+            return Self::create(system, config);
+        }
+        if data.levels.last().unwrap().configs.is_empty() {
+            return Err(FFSDataError::NoConfigs.into());
+        }
+        let mut ret = Self::from_data(system, data)?;
+        ret.extend(system, config)?;
         Ok(ret)
     }
+
     pub fn dimer_conc(&self) -> f64 {
         self.level_list[0].p_r
     }
@@ -360,6 +1412,22 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
     pub fn create_from_tileset<Sy: SystemWithDimers + System + FromTileSet>(
         tileset: &TileSet,
         config: &FFSRunConfig,
+    ) -> Result<Self, RgrowError> {
+        Self::create_or_resume_from_tileset::<Sy>(tileset, None, config)
+    }
+
+    pub fn resume_from_tileset<Sy: SystemWithDimers + System + FromTileSet>(
+        tileset: &TileSet,
+        data: &FFSRunData,
+        config: &FFSRunConfig,
+    ) -> Result<Self, RgrowError> {
+        Self::create_or_resume_from_tileset::<Sy>(tileset, Some(data), config)
+    }
+
+    fn create_or_resume_from_tileset<Sy: SystemWithDimers + System + FromTileSet>(
+        tileset: &TileSet,
+        data: Option<&FFSRunData>,
+        config: &FFSRunConfig,
     ) -> Result<Self, RgrowError> {
         let mut sys = Sy::from_tileset(tileset)?;
         let c = {
@@ -371,7 +1439,10 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
             c
         };
 
-        Ok(Self::create(&mut sys, &c)?)
+        Ok(match data {
+            Some(data) => Self::resume(&mut sys, data, &c)?,
+            None => Self::create(&mut sys, &c)?,
+        })
     }
 }
 
@@ -382,6 +1453,8 @@ pub struct FFSLevel<St: State + StateTracked<NullStateTracker>> {
     pub num_states: usize,
     pub num_trials: usize,
     pub target_size: NumTiles,
+    /// The number of trials started from each configuration of the previous level, if known.
+    pub parent_trials: Vec<usize>,
 }
 
 impl<St: State + StateTracked<NullStateTracker>> FFSSurface for FFSLevel<St> {
@@ -404,6 +1477,14 @@ impl<St: State + StateTracked<NullStateTracker>> FFSSurface for FFSLevel<St> {
     fn previous_list(&self) -> Vec<usize> {
         self.previous_list.clone()
     }
+
+    fn num_states(&self) -> usize {
+        self.num_states
+    }
+
+    fn parent_trials(&self) -> Vec<usize> {
+        self.parent_trials.clone()
+    }
 }
 
 impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullStateTracker>>
@@ -414,26 +1495,25 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
         self
     }
 
+    /// Runs trials from this level's states to the next surface.  Trials fail if they lose all
+    /// their tiles, or if their order parameter falls to `basin`, if given.
     pub fn next_level<Sy: SystemWithDimers + System>(
         &self,
         system: &mut Sy,
         config: &FFSRunConfig,
+        basin: Option<NumTiles>,
     ) -> Result<Self, GrowError> {
-        let mut rng = thread_rng();
+        let system = &*system;
 
         let mut state_list = Vec::new();
         let mut previous_list = Vec::new();
         let mut i = 0usize;
         let target_size = self.target_size + config.size_step;
 
-        let bounds = {
-            let mut b = config.subseq_bound;
-            b.size_max = Some(target_size);
-            b.size_min = Some(0);
-            b
-        };
+        let bounds = config.subseq_bound;
 
         let chooser = Uniform::new(0, self.state_list.len());
+        let mut parent_trials = vec![0; self.state_list.len()];
 
         let canvas_size = self.state_list[0].get_params();
 
@@ -443,42 +1523,68 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
             0.
         };
 
-        while state_list.len() < config.max_configs {
-            let mut state = St::empty(canvas_size)?;
-
-            let mut i_old_state: usize = 0;
-
-            while state.n_tiles() == 0 {
-                if state.total_rate() != 0. {
-                    panic!("Total rate is not zero! {state:?}");
-                };
-                i_old_state = chooser.sample(&mut rng);
-
-                state.zeroed_copy_from_state_nonzero_rate(&self.state_list[i_old_state]);
-                debug_assert_eq!(system.calc_n_tiles(&state), state.n_tiles());
+        'trials: while state_list.len() < config.max_configs {
+            let batch = run_trials(
+                i..i + trial_batch_size(),
+                |scratch: &mut Option<St>, trial| {
+                    let mut rng = trial_rng(config.seed, target_size, trial);
+                    let mut state = match scratch.take() {
+                        Some(state) => state,
+                        None => St::empty(canvas_size)?,
+                    };
+                    if state.total_rate() != 0. {
+                        panic!("Total rate is not zero! {state:?}");
+                    };
+                    let i_old_state = chooser.sample(&mut rng);
+
+                    state.zeroed_copy_from_state_nonzero_rate(&self.state_list[i_old_state]);
+                    debug_assert_eq!(system.calc_n_tiles(&state), state.n_tiles());
+
+                    let order = evolve_to_surface(
+                        system,
+                        &mut state,
+                        &config.order_parameter,
+                        target_size,
+                        basin.map_or(Floor::Empty, Floor::Basin),
+                        bounds,
+                        &mut rng,
+                    )?;
+
+                    let outcome = match basin {
+                        // The state may not be empty (eg, it has a seed), so isn't reused.
+                        Some(basin) if order <= basin => Trial::Failure,
+                        None if state.n_tiles() == 0 => {
+                            *scratch = Some(state);
+                            Trial::Failure
+                        }
+                        // >= hack for duples
+                        _ if order >= target_size => Trial::Success(state, i_old_state),
+                        _ => Trial::Stalled(state),
+                    };
+                    Ok((i_old_state, outcome))
+                },
+            )?;
 
-                system.evolve(&mut state, bounds).unwrap();
+            // Results are accounted for in order, stopping exactly where running the trials one
+            // at a time would, and discarding the rest of the batch.
+            for (i_old_state, outcome) in batch {
                 i += 1;
-            }
-
-            if state.n_tiles() >= target_size {
-                // >= hack for duples
-                state_list.push(state);
-                previous_list.push(i_old_state);
-            } else {
-                println!(
-                    "Ran out of events: {} tiles, {} events, {} time, {} total rate.",
-                    state.n_tiles(),
-                    state.total_events(),
-                    state.time(),
-                    state.total_rate(),
-                );
-            }
+                parent_trials[i_old_state] += 1;
+                match outcome {
+                    Trial::Failure => continue,
+                    Trial::Success(state, i_old_state) => {
+                        state_list.push(state);
+                        previous_list.push(i_old_state);
+                    }
+                    Trial::Stalled(state) => report_stalled(&state),
+                }
 
-            if (variance_over_mean2(state_list.len(), i) < cvar)
-                & (state_list.len() >= config.min_configs)
-            {
-                break;
+                if (state_list.len() >= config.max_configs)
+                    | ((variance_over_mean2(state_list.len(), i) < cvar)
+                        & (state_list.len() >= config.min_configs))
+                {
+                    break 'trials;
+                }
             }
         }
         let p_r = (state_list.len() as f64) / (i as f64);
@@ -491,6 +1597,7 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
             target_size,
             num_states,
             num_trials: i,
+            parent_trials,
         })
     }
 
@@ -498,7 +1605,7 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
         system: &mut Sy,
         config: &FFSRunConfig,
     ) -> Result<(Self, Self), GrowError> {
-        let mut rng = SmallRng::from_entropy();
+        let system = &*system;
 
         let dimers = system.calc_dimers();
 
@@ -520,76 +1627,92 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
 
         let mut tile_list = Vec::with_capacity(config.min_configs);
 
-        let mut other: (usize, usize);
-
         let cvar = if config.constant_variance {
             config.var_per_mean2
         } else {
             0.
         };
 
-        let bounds = {
-            let mut b = config.subseq_bound;
-            b.size_max = Some(config.start_size);
-            b.size_min = Some(0);
-            b
-        };
-
-        while state_list.len() < config.max_configs {
-            let mut state = St::empty(config.canvas_size)?;
-
-            while state.n_tiles() == 0 {
-                let i_old_state = chooser.sample(&mut rng);
-                let dimer = &dimers[i_old_state];
-
-                other = match dimer.orientation {
-                    Orientation::NS => state.move_sa_s(mid).0,
-                    Orientation::WE => state.move_sa_e(mid).0,
-                };
-                system.set_points(&mut state, &[(mid.0, dimer.t1), (other, dimer.t2)]);
-
-                debug_assert_eq!(system.calc_n_tiles(&state), state.n_tiles());
+        let bounds = config.subseq_bound;
 
-                system.evolve(&mut state, bounds).unwrap();
-                i += 1;
+        let place_dimer = |state: &mut St, dimer: &DimerInfo| {
+            let other = match dimer.orientation {
+                Orientation::NS => state.move_sa_s(mid).0,
+                Orientation::WE => state.move_sa_e(mid).0,
+            };
+            system.set_points(state, &[(mid.0, dimer.t1), (other, dimer.t2)]);
+        };
 
-                if state.n_tiles() >= config.start_size {
-                    // FIXME: >= is a hack
-                    // Create (retrospectively) a dimer state
-                    let mut dimer_state = St::empty(config.canvas_size)?;
-                    other = match dimer.orientation {
-                        Orientation::NS => dimer_state.move_sa_s(mid).0,
-                        Orientation::WE => dimer_state.move_sa_e(mid).0,
+        'trials: while state_list.len() < config.max_configs {
+            let batch = run_trials(
+                i..i + trial_batch_size(),
+                |scratch: &mut Option<St>, trial| {
+                    let mut rng = trial_rng(config.seed, config.start_size, trial);
+                    let mut state = match scratch.take() {
+                        Some(state) => state,
+                        None => St::empty(config.canvas_size)?,
                     };
-                    system.set_points(&mut dimer_state, &[(mid.0, dimer.t1), (other, dimer.t2)]);
-
-                    state_list.push(state);
-
-                    dimer_state_list.push(dimer_state);
 
-                    if rng.gen::<bool>() {
-                        tile_list.push(dimer.t1);
+                    let dimer = &dimers[chooser.sample(&mut rng)];
+                    place_dimer(&mut state, dimer);
+
+                    debug_assert_eq!(system.calc_n_tiles(&state), state.n_tiles());
+
+                    let order = evolve_to_surface(
+                        system,
+                        &mut state,
+                        &config.order_parameter,
+                        config.start_size,
+                        Floor::Empty,
+                        bounds,
+                        &mut rng,
+                    )?;
+
+                    if order >= config.start_size {
+                        // FIXME: >= is a hack
+                        // Create (retrospectively) a dimer state
+                        let mut dimer_state = St::empty(config.canvas_size)?;
+                        place_dimer(&mut dimer_state, dimer);
+
+                        let tile = if rng.gen::<bool>() {
+                            dimer.t1
+                        } else {
+                            dimer.t2
+                        };
+
+                        Ok(Trial::Success(state, (dimer_state, tile)))
+                    } else if state.n_tiles() != 0 {
+                        Ok(Trial::Stalled(state))
                     } else {
-                        tile_list.push(dimer.t2);
+                        if state.total_rate() != 0. {
+                            panic!("{}", state.panicinfo())
+                        };
+                        *scratch = Some(state);
+                        Ok(Trial::Failure)
                     }
+                },
+            )?;
 
-                    previous_list.push(num_states);
-
-                    num_states += 1;
-
-                    break;
-                } else {
-                    if state.n_tiles() != 0 {
-                        panic!("{}", state.panicinfo())
+            for outcome in batch {
+                i += 1;
+                match outcome {
+                    Trial::Failure => continue,
+                    Trial::Success(state, (dimer_state, tile)) => {
+                        state_list.push(state);
+                        dimer_state_list.push(dimer_state);
+                        tile_list.push(tile);
+                        previous_list.push(num_states);
+                        num_states += 1;
                     }
-                    if state.total_rate() != 0. {
-                        panic!("{}", state.panicinfo())
-                    };
+                    Trial::Stalled(state) => report_stalled(&state),
                 }
-            }
 
-            if (variance_over_mean2(num_states, i) < cvar) & (num_states >= config.min_configs) {
-                break;
+                if (state_list.len() >= config.max_configs)
+                    | ((variance_over_mean2(num_states, i) < cvar)
+                        & (num_states >= config.min_configs))
+                {
+                    break 'trials;
+                }
             }
         }
 
@@ -603,6 +1726,7 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
                 target_size: config.start_size,
                 num_states,
                 num_trials: i,
+                parent_trials: Vec::new(),
             },
             Self {
                 state_list: dimer_state_list,
@@ -611,9 +1735,893 @@ impl<St: State + StateWithCreate<Params = (usize, usize)> + StateTracked<NullSta
                 target_size: 2,
                 num_states,
                 num_trials: num_states,
+                parent_trials: Vec::new(),
             },
         ))
     }
+
+    /// Runs trials from states chosen uniformly from `start` until they reach the first surface,
+    /// at `config.start_size`, returning the first level and the flux out of the starting basin.
+    /// After reaching the surface, a copy of each trial continues until it returns to the basin
+    /// (or reaches the target), so that the flux is the number of crossings per unit of time
+    /// spent since last leaving the basin, as in the usual FFS definition.  The level's `p_r` is
+    /// 1, since the probability of reaching it is accounted for in the flux.
+    pub fn from_start_states<Sy: SystemWithDimers + System>(
+        system: &mut Sy,
+        start: &[St],
+        basin: NumTiles,
+        config: &FFSRunConfig,
+    ) -> Result<(Self, Rate), GrowError> {
+        let system = &*system;
+
+        let mut state_list = Vec::new();
+        let mut previous_list = Vec::new();
+        let mut i = 0usize;
+        let mut total_time = 0.;
+
+        let bounds = config.init_bound;
+
+        let chooser = Uniform::new(0, start.len());
+        let mut parent_trials = vec![0; start.len()];
+
+        let canvas_size = start[0].get_params();
+
+        'trials: while state_list.len() < config.max_configs {
+            let batch = run_trials(i..i + trial_batch_size(), |_: &mut (), trial| {
+                let mut rng = trial_rng(config.seed, config.start_size, trial);
+                let mut state = St::empty(canvas_size)?;
+                let i_start = chooser.sample(&mut rng);
+
+                state.zeroed_copy_from_state_nonzero_rate(&start[i_start]);
+
+                let order = evolve_to_surface(
+                    system,
+                    &mut state,
+                    &config.order_parameter,
+                    config.start_size,
+                    Floor::None,
+                    bounds,
+                    &mut rng,
+                )?;
+                let mut time = state.time();
+
+                let outcome = if order >= config.start_size {
+                    let mut excursion = St::empty(canvas_size)?;
+                    excursion.zeroed_copy_from_state_nonzero_rate(&state);
+                    evolve_to_surface(
+                        system,
+                        &mut excursion,
+                        &config.order_parameter,
+                        config.target_size,
+                        Floor::Basin(basin),
+                        bounds,
+                        &mut rng,
+                    )?;
+                    time += excursion.time();
+                    Trial::Success(state, i_start)
+                } else if state.total_rate() == 0. {
+                    // Dissolved (or otherwise stuck), without reaching the surface.
+                    Trial::Failure
+                } else {
+                    Trial::Stalled(state)
+                };
+                Ok((i_start, time, outcome))
+            })?;
+
+            for (i_start, time, outcome) in batch {
+                i += 1;
+                parent_trials[i_start] += 1;
+                total_time += time;
+                match outcome {
+                    Trial::Failure => continue,
+                    Trial::Success(state, i_start) => {
+                        state_list.push(state);
+                        previous_list.push(i_start);
+                    }
+                    Trial::Stalled(state) => report_stalled(&state),
+                }
+
+                // The relative variance of the flux is about 1 / successes.
+                if (state_list.len() >= config.max_configs)
+                    | ((1. / (state_list.len() as f64) < config.var_per_mean2)
+                        & config.constant_variance
+                        & (state_list.len() >= config.min_configs))
+                {
+                    break 'trials;
+                }
+            }
+        }
+
+        let num_states = state_list.len();
+        let flux = num_states as f64 / total_time;
+
+        Ok((
+            Self {
+                state_list,
+                previous_list,
+                p_r: 1.0,
+                target_size: config.start_size,
+                num_states,
+                num_trials: i,
+                parent_trials,
+            },
+            flux,
+        ))
+    }
+}
+
+/// Settings for [CommittorAnalysis::calculate].
+#[derive(Debug, Clone)]
+pub struct CommittorConfig {
+    /// The number of trials shot from each configuration.
+    pub trials: usize,
+    /// The most configurations analyzed on each surface.  Surfaces with more are sampled
+    /// uniformly.
+    pub max_configs: usize,
+    /// The order parameter value at which a trial is committed (by default, that of the run's
+    /// last surface).
+    pub target: Option<NumTiles>,
+    /// Configurations with committors within this of 1/2 are taken as critical nuclei.
+    pub window: f64,
+    /// Bounds for each trial (by default, the run's `subseq_bound`).
+    pub bounds: Option<EvolveBounds>,
+    /// Seed for reproducible results.
+    pub seed: Option<u64>,
+}
+
+impl Default for CommittorConfig {
+    fn default() -> Self {
+        Self {
+            trials: 100,
+            max_configs: 20,
+            target: None,
+            window: 0.2,
+            bounds: None,
+            seed: None,
+        }
+    }
+}
+
+/// Committor estimates for the configurations of one FFS surface.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct SurfaceCommittor {
+    /// The index of the surface in the run.
+    pub surface: usize,
+    /// The surface's order parameter value.
+    pub size: NumTiles,
+    /// The indices of the analyzed configurations on the surface.
+    pub configs: Vec<usize>,
+    /// The fraction of each configuration's trials that reached the target before falling back
+    /// (NaN if all of them stalled).
+    pub committors: Vec<f64>,
+    /// The number of trials that stalled, and so aren't counted.
+    pub stalled: usize,
+}
+
+impl SurfaceCommittor {
+    /// The mean committor of the analyzed configurations.
+    pub fn mean(&self) -> f64 {
+        let (sum, n) = self
+            .committors
+            .iter()
+            .filter(|p| !p.is_nan())
+            .fold((0., 0), |(sum, n), p| (sum + p, n + 1));
+        sum / n as f64
+    }
+}
+
+/// Shape and tile composition statistics of a set of configurations.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct NucleusStats {
+    /// The number of configurations.
+    pub count: usize,
+    /// The mean number of tiles (including tiles, like seed tiles, that the system doesn't count).
+    pub mean_tiles: f64,
+    /// The mean number of rows spanned, allowing for wrapping around the canvas.
+    pub mean_height: f64,
+    /// The mean number of columns spanned, allowing for wrapping around the canvas.
+    pub mean_width: f64,
+    /// For each tile, its mean number in a configuration.
+    pub tile_counts: Vec<f64>,
+    /// For each tile, the fraction of configurations that contain it.
+    pub tile_frequency: Vec<f64>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl SurfaceCommittor {
+    #[getter("mean")]
+    fn py_mean(&self) -> f64 {
+        self.mean()
+    }
+}
+
+impl NucleusStats {
+    pub fn from_configs(configs: &[ArrayView2<Tile>], num_tiles: usize) -> Self {
+        let mut stats = Self {
+            count: configs.len(),
+            tile_counts: vec![0.; num_tiles],
+            tile_frequency: vec![0.; num_tiles],
+            ..Default::default()
+        };
+        if configs.is_empty() {
+            return stats;
+        }
+
+        for config in configs {
+            let mut counts = vec![0usize; num_tiles];
+            for &t in config.iter().filter(|&&t| t != 0) {
+                counts[t as usize] += 1;
+            }
+            let rows: Vec<bool> = config
+                .rows()
+                .into_iter()
+                .map(|r| r.iter().any(|&t| t != 0))
+                .collect();
+            let cols: Vec<bool> = config
+                .columns()
+                .into_iter()
+                .map(|c| c.iter().any(|&t| t != 0))
+                .collect();
+
+            stats.mean_tiles += counts.iter().sum::<usize>() as f64;
+            stats.mean_height += wrapped_extent(&rows) as f64;
+            stats.mean_width += wrapped_extent(&cols) as f64;
+            for (t, &c) in counts.iter().enumerate() {
+                stats.tile_counts[t] += c as f64;
+                if c > 0 {
+                    stats.tile_frequency[t] += 1.;
+                }
+            }
+        }
+
+        let n = configs.len() as f64;
+        stats.mean_tiles /= n;
+        stats.mean_height /= n;
+        stats.mean_width /= n;
+        stats.tile_counts.iter_mut().for_each(|c| *c /= n);
+        stats.tile_frequency.iter_mut().for_each(|f| *f /= n);
+        stats
+    }
+}
+
+/// The length of the shortest run (wrapping around the end) that covers every true value in
+/// `occupied`.
+fn wrapped_extent(occupied: &[bool]) -> usize {
+    let n = occupied.len();
+    let Some(first) = occupied.iter().position(|&o| o) else {
+        return 0;
+    };
+    // The longest gap between occupied positions, starting from an occupied one.
+    let mut longest_gap = 0;
+    let mut gap = 0;
+    for i in 1..=n {
+        if occupied[(first + i) % n] {
+            longest_gap = longest_gap.max(gap);
+            gap = 0;
+        } else {
+            gap += 1;
+        }
+    }
+    n - longest_gap
+}
+
+/// Committor analysis of the configurations stored in an FFS run.  For each analyzed
+/// configuration, trials are shot until they reach the target or fall back to where FFS trials
+/// fail (no tiles for runs from dimers, the basin for runs from a starting ensemble).  The
+/// critical size is where the surfaces' mean committor crosses 1/2, and the critical nuclei are
+/// the configurations with committors near 1/2.
+///
+/// Only surfaces with stored configurations can be analyzed, so runs should generally have
+/// `keep_configs` set.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct CommittorAnalysis {
+    /// The order parameter value at which trials were committed.
+    pub target: NumTiles,
+    /// The analyzed surfaces, in order.
+    pub surfaces: Vec<SurfaceCommittor>,
+    /// The order parameter value at which the mean committor crosses 1/2, interpolating linearly
+    /// between surfaces.  None if it doesn't cross between analyzed surfaces.
+    pub critical_size: Option<f64>,
+    /// The (surface, configuration) indices of configurations with committors near 1/2.
+    pub critical_configs: Vec<(usize, usize)>,
+    /// Statistics of the critical nuclei.
+    pub critical_nuclei: NucleusStats,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl CommittorAnalysis {
+    fn __repr__(&self) -> String {
+        format!(
+            "CommittorAnalysis(target={}, surfaces={}, critical_size={:?}, critical_nuclei={})",
+            self.target,
+            self.surfaces.len(),
+            self.critical_size,
+            self.critical_nuclei.count
+        )
+    }
+}
+
+impl CommittorAnalysis {
+    pub fn calculate<
+        St: State + StateWithCreate<Params = (usize, usize)>,
+        Sy: SystemWithDimers + System,
+    >(
+        system: &Sy,
+        result: &dyn FFSResult,
+        config: &CommittorConfig,
+    ) -> Result<Self, GrowError> {
+        let run_surfaces = result.surfaces();
+        let order = &result.config().order_parameter;
+        let target = config.target.unwrap_or_else(|| {
+            run_surfaces
+                .last()
+                .map_or(result.config().target_size, |s| s.target_size())
+        });
+        let bounds = config.bounds.unwrap_or(result.config().subseq_bound);
+        let floor = result.basin().map_or(Floor::Empty, Floor::Basin);
+        let mut rng = match config.seed {
+            Some(seed) => SimRng::seed_from_u64(seed),
+            None => new_sim_rng(),
+        };
+
+        let mut surfaces = Vec::new();
+        let mut trial = 0;
+        for (i_surface, surface) in run_surfaces.iter().enumerate() {
+            let n = surface.num_configs();
+            if n == 0 || surface.target_size() >= target {
+                continue;
+            }
+            let mut configs = if n > config.max_configs {
+                rand::seq::index::sample(&mut rng, n, config.max_configs).into_vec()
+            } else {
+                (0..n).collect()
+            };
+            configs.sort_unstable();
+
+            let mut committors = Vec::with_capacity(configs.len());
+            let mut stalled = 0;
+            for &i_config in &configs {
+                let start: St =
+                    state_from_config(system, &surface.get_config(i_config).to_owned())?;
+                let outcomes = run_trials(
+                    trial..trial + config.trials,
+                    |scratch: &mut Option<St>, trial| {
+                        let mut rng = trial_rng(config.seed, surface.target_size(), trial);
+                        let mut state = match scratch.take() {
+                            Some(state) => state,
+                            None => St::empty(start.get_params())?,
+                        };
+                        state.zeroed_copy_from_state_nonzero_rate(&start);
+
+                        let value = evolve_to_surface(
+                            system, &mut state, order, target, floor, bounds, &mut rng,
+                        )?;
+
+                        Ok(if value >= target {
+                            Some(true)
+                        } else if match floor {
+                            Floor::Basin(basin) => value <= basin,
+                            _ => state.n_tiles() == 0,
+                        } {
+                            if state.n_tiles() == 0 {
+                                *scratch = Some(state);
+                            }
+                            Some(false)
+                        } else {
+                            report_stalled(&state);
+                            None
+                        })
+                    },
+                )?;
+                trial += config.trials;
+
+                let successes = outcomes.iter().filter(|o| **o == Some(true)).count();
+                let failures = outcomes.iter().filter(|o| **o == Some(false)).count();
+                stalled += config.trials - successes - failures;
+                committors.push(successes as f64 / (successes + failures) as f64);
+            }
+
+            surfaces.push(SurfaceCommittor {
+                surface: i_surface,
+                size: surface.target_size(),
+                configs,
+                committors,
+                stalled,
+            });
+        }
+
+        let critical_size = surfaces.windows(2).find_map(|w| {
+            let (m0, m1) = (w[0].mean(), w[1].mean());
+            (m0 < 0.5 && m1 >= 0.5)
+                .then(|| w[0].size as f64 + (0.5 - m0) / (m1 - m0) * (w[1].size - w[0].size) as f64)
+        });
+
+        let critical_configs: Vec<(usize, usize)> = surfaces
+            .iter()
+            .flat_map(|s| {
+                s.configs
+                    .iter()
+                    .zip(&s.committors)
+                    .filter(|(_, p)| (*p - 0.5).abs() <= config.window)
+                    .map(|(&i, _)| (s.surface, i))
+            })
+            .collect();
+        let critical_nuclei = NucleusStats::from_configs(
+            &critical_configs
+                .iter()
+                .map(|&(s, i)| run_surfaces[s].get_config(i))
+                .collect::<Vec<_>>(),
+            system.tile_names().len(),
+        );
+
+        Ok(Self {
+            target,
+            surfaces,
+            critical_size,
+            critical_configs,
+            critical_nuclei,
+        })
+    }
+}
+
+/// How a [TargetStructure] is recognized in a configuration.  In Python, these are given as
+/// `("template", [[tiles...], ...])` and `("tile_subset", [tiles...], min_fraction)`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructurePattern {
+    /// Configurations containing this arrangement of tiles somewhere, wrapping around the
+    /// canvas.  Zeros in the template match any tile, or no tile.
+    Template(Array2<Tile>),
+    /// Configurations in which at least the given fraction of tiles are of the given types.
+    TileSubset(Vec<Tile>, f64),
+}
+
+impl StructurePattern {
+    pub fn matches(&self, config: ArrayView2<Tile>) -> bool {
+        match self {
+            StructurePattern::Template(template) => {
+                let tiles: Vec<_> = template
+                    .indexed_iter()
+                    .filter(|(_, &t)| t != 0)
+                    .map(|(p, &t)| (p, t))
+                    .collect();
+                let Some(&((r0, c0), t0)) = tiles.first() else {
+                    return true;
+                };
+                let (nr, nc) = config.dim();
+                // Only offsets that put the template's first tile on a matching tile can match.
+                config
+                    .indexed_iter()
+                    .filter(|(_, &t)| t == t0)
+                    .any(|((r, c), _)| {
+                        let (dr, dc) = (r + nr * (r0 / nr + 1) - r0, c + nc * (c0 / nc + 1) - c0);
+                        tiles
+                            .iter()
+                            .all(|&((i, j), t)| config[((i + dr) % nr, (j + dc) % nc)] == t)
+                    })
+            }
+            StructurePattern::TileSubset(subset, min_fraction) => {
+                let (total, matching) = config
+                    .iter()
+                    .filter(|&&t| t != 0)
+                    .fold((0, 0), |(total, matching), t| {
+                        (total + 1, matching + subset.contains(t) as usize)
+                    });
+                total > 0 && matching as f64 >= min_fraction * total as f64
+            }
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl<'source> FromPyObject<'source> for StructurePattern {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let name: &str = ob.get_item(0)?.extract()?;
+        match (name, ob.len()?) {
+            ("template", 2) => {
+                let rows: Vec<Vec<Tile>> = ob.get_item(1)?.extract()?;
+                let ncols = rows.first().map_or(0, |r| r.len());
+                let template = Array2::from_shape_vec(
+                    (rows.len(), ncols),
+                    rows.into_iter().flatten().collect(),
+                )
+                .map_err(|_| PyTypeError::new_err("Template rows differ in length"))?;
+                Ok(StructurePattern::Template(template))
+            }
+            ("tile_subset", 3) => Ok(StructurePattern::TileSubset(
+                ob.get_item(1)?.extract()?,
+                ob.get_item(2)?.extract()?,
+            )),
+            _ => Err(PyTypeError::new_err(format!(
+                "Unknown structure pattern: {ob}"
+            ))),
+        }
+    }
+}
+
+/// A named structure that nucleated assemblies can be classified as, such as a correct ribbon or
+/// a particular misnucleated lattice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetStructure {
+    pub name: String,
+    pub pattern: StructurePattern,
+}
+
+/// Settings for [StructureAnalysis::calculate].
+#[derive(Debug, Clone)]
+pub struct StructureConfig {
+    /// The structures, in order of priority: each configuration is classified as the first
+    /// structure it matches.
+    pub structures: Vec<TargetStructure>,
+    /// If set, configurations at the last surface are grown until their order parameter reaches
+    /// this before they are classified, for structures that aren't yet distinguishable at the
+    /// run's target size.
+    pub grow_to: Option<NumTiles>,
+    /// Bounds for growing each configuration (by default, the run's `subseq_bound`).
+    pub bounds: Option<EvolveBounds>,
+    /// Confidence level for the per-structure nucleation rate intervals.
+    pub confidence: f64,
+    /// Seed for reproducible results.
+    pub seed: Option<u64>,
+}
+
+impl Default for StructureConfig {
+    fn default() -> Self {
+        Self {
+            structures: Vec::new(),
+            grow_to: None,
+            bounds: None,
+            confidence: 0.95,
+            seed: None,
+        }
+    }
+}
+
+/// The number of configurations of one FFS surface classified as each structure.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct SurfaceStructures {
+    /// The index of the surface in the run.
+    pub surface: usize,
+    /// The surface's order parameter value.
+    pub size: NumTiles,
+    /// For each structure, the number of configurations classified as it.
+    pub counts: Vec<usize>,
+    /// The number of configurations that matched no structure.
+    pub unmatched: usize,
+}
+
+/// Classification of the assemblies in an FFS run into target structures, and the nucleation
+/// rate of each.  Configurations that reached the last surface are equally weighted samples of
+/// nucleated assemblies, so each structure's nucleation rate is the run's rate times the
+/// fraction of them classified as the structure.  Stored configurations on earlier surfaces
+/// (with `keep_configs`) are also classified, to show where pathways diverge.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct StructureAnalysis {
+    /// The names of the structures.
+    pub names: Vec<String>,
+    /// Classification of the configurations, as they are, on each surface with stored
+    /// configurations.
+    pub surfaces: Vec<SurfaceStructures>,
+    /// For each configuration at the last surface, the structure it was classified as (after
+    /// growing, if `grow_to` was set), or None if it matched no structure or dissolved.
+    pub outcomes: Vec<Option<usize>>,
+    /// The number of configurations at the last surface that dissolved while being grown.  These
+    /// are left out of the fractions of each structure.
+    pub dissolved: usize,
+    /// The nucleation rate of each structure, in M/s.
+    pub nucleation_rates: Vec<Rate>,
+    /// The nucleation rate of assemblies that matched no structure, in M/s.
+    pub unmatched_rate: Rate,
+    /// Confidence intervals on each structure's nucleation rate, combining the run's analytical
+    /// variance estimate with binomial sampling of the structure's fraction.
+    pub nucleation_rate_intervals: Vec<ConfidenceInterval>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl StructureAnalysis {
+    fn __repr__(&self) -> String {
+        format!(
+            "StructureAnalysis({}, unmatched={:e}, dissolved={})",
+            self.names
+                .iter()
+                .zip(&self.nucleation_rates)
+                .map(|(name, rate)| format!("{name}={rate:e}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.unmatched_rate,
+            self.dissolved
+        )
+    }
+}
+
+impl StructureAnalysis {
+    /// The index of the first of `structures` that `config` matches.
+    pub fn classify(structures: &[TargetStructure], config: ArrayView2<Tile>) -> Option<usize> {
+        structures.iter().position(|s| s.pattern.matches(config))
+    }
+
+    pub fn calculate<
+        St: State + StateWithCreate<Params = (usize, usize)>,
+        Sy: SystemWithDimers + System,
+    >(
+        system: &Sy,
+        result: &dyn FFSResult,
+        config: &StructureConfig,
+    ) -> Result<Self, GrowError> {
+        let run_surfaces = result.surfaces();
+        let Some(&last) = run_surfaces.last() else {
+            return Err(GrowError::FFSNoAssembliesToClassify);
+        };
+        let structures = &config.structures;
+        let n_structures = structures.len();
+
+        let surfaces = run_surfaces
+            .iter()
+            .enumerate()
+            .filter(|(_, surface)| surface.num_configs() > 0)
+            .map(|(i_surface, surface)| {
+                let mut counts = vec![0; n_structures];
+                let mut unmatched = 0;
+                for c in surface.configs() {
+                    match Self::classify(structures, c) {
+                        Some(i) => counts[i] += 1,
+                        None => unmatched += 1,
+                    }
+                }
+                SurfaceStructures {
+                    surface: i_surface,
+                    size: surface.target_size(),
+                    counts,
+                    unmatched,
+                }
+            })
+            .collect();
+
+        let outcomes: Vec<Option<Option<usize>>> = match config.grow_to {
+            None => last
+                .configs()
+                .into_iter()
+                .map(|c| Some(Self::classify(structures, c)))
+                .collect(),
+            Some(grow_to) => {
+                let order = &result.config().order_parameter;
+                let bounds = config.bounds.unwrap_or(result.config().subseq_bound);
+                let floor = result.basin().map_or(Floor::Empty, Floor::Basin);
+                run_trials(0..last.num_configs(), |_: &mut (), i| {
+                    let mut rng = trial_rng(config.seed, grow_to, i);
+                    let mut state: St = state_from_config(system, &last.get_config(i).to_owned())?;
+                    let value = evolve_to_surface(
+                        system, &mut state, order, grow_to, floor, bounds, &mut rng,
+                    )?;
+                    let dissolved = match floor {
+                        Floor::Basin(basin) => value <= basin,
+                        _ => state.n_tiles() == 0,
+                    };
+                    if !dissolved && value < grow_to {
+                        report_stalled(&state);
+                    }
+                    Ok((!dissolved).then(|| Self::classify(structures, state.raw_array())))
+                })?
+            }
+        };
+
+        let dissolved = outcomes.iter().filter(|o| o.is_none()).count();
+        let outcomes: Vec<Option<usize>> = outcomes.into_iter().map(Option::flatten).collect();
+        let classified = outcomes.len() - dissolved;
+        if classified == 0 {
+            return Err(GrowError::FFSNoAssembliesToClassify);
+        }
+        let mut counts = vec![0; n_structures];
+        for &i in outcomes.iter().flatten() {
+            counts[i] += 1;
+        }
+        let unmatched = classified - counts.iter().sum::<usize>();
+
+        let rate = result.nucleation_rate();
+        let fraction = |count: usize| count as f64 / classified as f64;
+        let z = normal_quantile(0.5 + config.confidence / 2.);
+        let total_interval = result.nucleation_rate_interval(config.confidence);
+        let nucleation_rate_intervals = counts
+            .iter()
+            .map(|&count| {
+                let f = wilson_interval(fraction(count), classified, z);
+                if count == 0 || rate == 0. {
+                    return ConfidenceInterval {
+                        estimate: rate * f.estimate,
+                        lower: 0.,
+                        upper: total_interval.upper * f.upper,
+                    };
+                }
+                let relative_variance =
+                    result.nucleation_rate_relative_variance() + (1. - f.estimate) / count as f64;
+                let sigma = relative_variance.ln_1p().sqrt();
+                let estimate = rate * f.estimate;
+                ConfidenceInterval {
+                    estimate,
+                    lower: estimate * (-z * sigma).exp(),
+                    upper: estimate * (z * sigma).exp(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            names: structures.iter().map(|s| s.name.clone()).collect(),
+            surfaces,
+            outcomes,
+            dissolved,
+            nucleation_rates: counts.iter().map(|&c| rate * fraction(c)).collect(),
+            unmatched_rate: rate * fraction(unmatched),
+            nucleation_rate_intervals,
+        })
+    }
+}
+
+/// Creates a state with the tiles of `config`, with rates calculated for `system`.
+fn state_from_config<Sy: System, St: StateWithCreate<Params = (usize, usize)>>(
+    system: &Sy,
+    config: &Array2<Tile>,
+) -> Result<St, GrowError> {
+    let mut state = St::empty(config.dim())?;
+    let tiles: Vec<_> = config
+        .indexed_iter()
+        .filter(|(_, &t)| t != 0)
+        .map(|(p, &t)| (p, t))
+        .collect();
+    if !tiles.is_empty() {
+        system.set_points(&mut state, &tiles);
+    }
+    Ok(state)
+}
+
+/// The outcome of a single FFS trial.
+enum Trial<St, T> {
+    /// Reached the next surface, with information on where it started.
+    Success(St, T),
+    /// Went back to zero tiles.
+    Failure,
+    /// Ran out of events (or time) between the surfaces.
+    Stalled(St),
+}
+
+fn report_stalled<St: State + ?Sized>(state: &St) {
+    log::warn!(
+        "Ran out of events: {} tiles, {} events, {} time, {} total rate.",
+        state.n_tiles(),
+        state.total_events(),
+        state.time(),
+        state.total_rate(),
+    );
+}
+
+/// The generator for one trial.  With a seed, each trial's generator depends only on the seed,
+/// the surface and the trial's index, so results don't depend on how trials are scheduled.
+fn trial_rng(seed: Option<u64>, surface: NumTiles, trial: usize) -> SimRng {
+    match seed {
+        Some(seed) => SimRng::seed_from_u64(
+            seed ^ (surface as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                ^ (trial as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9),
+        ),
+        None => new_sim_rng(),
+    }
+}
+
+/// The number of trials to run at once.
+fn trial_batch_size() -> usize {
+    #[cfg(feature = "use_rayon")]
+    return 4 * rayon::current_num_threads();
+    #[cfg(not(feature = "use_rayon"))]
+    return 1;
+}
+
+/// Runs `trial` for each of `trials`, in parallel if rayon is available, returning results in
+/// order.  Each thread has a scratch value, initially `S::default()`, that trials can use to
+/// reuse allocations.
+fn run_trials<S: Default, T: Send>(
+    trials: std::ops::Range<usize>,
+    trial: impl Fn(&mut S, usize) -> Result<T, GrowError> + Sync + Send,
+) -> Result<Vec<T>, GrowError> {
+    #[cfg(feature = "use_rayon")]
+    return trials.into_par_iter().map_init(S::default, trial).collect();
+    #[cfg(not(feature = "use_rayon"))]
+    {
+        let mut scratch = S::default();
+        trials.map(|t| trial(&mut scratch, t)).collect()
+    }
+}
+
+/// The Wilson score interval for a binomial proportion `p` from `n` trials, with `z` standard
+/// deviations.  With no trials, nothing is known, and the interval is [0, 1].
+fn wilson_interval(p: f64, n: usize, z: f64) -> ConfidenceInterval {
+    if n == 0 {
+        return ConfidenceInterval {
+            estimate: p,
+            lower: 0.,
+            upper: 1.,
+        };
+    }
+    let n = n as f64;
+    let z2n = z * z / n;
+    let center = (p + z2n / 2.) / (1. + z2n);
+    let half = z / (1. + z2n) * (p * (1. - p) / n + z2n / (4. * n)).sqrt();
+    ConfidenceInterval {
+        estimate: p,
+        lower: (center - half).max(0.),
+        upper: (center + half).min(1.),
+    }
+}
+
+/// The quantile function of the standard normal distribution, using Acklam's rational
+/// approximation (relative error below 1.2e-9).
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p <= 0. {
+        return f64::NEG_INFINITY;
+    } else if p >= 1. {
+        return f64::INFINITY;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    if p < P_LOW {
+        tail((-2. * p.ln()).sqrt())
+    } else if p > 1. - P_LOW {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    }
+}
+
+/// A draw from a binomial distribution, as a float.  Large samples use the normal approximation.
+fn sample_binomial(n: usize, p: f64, rng: &mut SimRng) -> f64 {
+    let nf = n as f64;
+    let var = nf * p * (1. - p);
+    if var > 1000. {
+        let z = normal_quantile(rng.gen::<f64>());
+        (nf * p + z * var.sqrt()).round().clamp(0., nf)
+    } else {
+        (0..n).filter(|_| rng.gen_bool(p)).count() as f64
+    }
 }
 
 fn variance_over_mean2(num_success: usize, num_trials: usize) -> f64 {
@@ -647,6 +2655,38 @@ impl BoxedFFSResult {
         self.0.dimerization_rate()
     }
 
+    /// Wilson score confidence intervals for each forward probability.
+    #[pyo3(signature = (confidence = 0.95))]
+    fn forward_prob_intervals(&self, confidence: f64) -> Vec<ConfidenceInterval> {
+        self.0.forward_prob_intervals(confidence)
+    }
+
+    /// The estimated variance of the nucleation rate, relative to its square, treating surfaces
+    /// as independent.
+    #[getter]
+    fn get_nucleation_rate_relative_variance(&self) -> f64 {
+        self.0.nucleation_rate_relative_variance()
+    }
+
+    /// A confidence interval for the nucleation rate, from the analytical variance estimate.
+    #[pyo3(signature = (confidence = 0.95))]
+    fn nucleation_rate_interval(&self, confidence: f64) -> ConfidenceInterval {
+        self.0.nucleation_rate_interval(confidence)
+    }
+
+    /// A bootstrap confidence interval for the nucleation rate, resampling along the lineages of
+    /// configurations.
+    #[pyo3(signature = (confidence = 0.95, samples = 1000, seed = None))]
+    fn nucleation_rate_bootstrap(
+        &self,
+        confidence: f64,
+        samples: usize,
+        seed: Option<u64>,
+        py: Python<'_>,
+    ) -> ConfidenceInterval {
+        py.allow_threads(|| self.0.nucleation_rate_bootstrap(confidence, samples, seed))
+    }
+
     #[getter]
     fn get_surfaces(&self) -> Vec<FFSLevelRef> {
         self.0
@@ -672,6 +2712,28 @@ impl BoxedFFSResult {
         self.__str__()
     }
 
+    /// The configuration the run was made (or last extended) with.
+    #[getter]
+    fn get_config(&self) -> FFSRunConfig {
+        self.0.config().clone()
+    }
+
+    /// Saves the result (surfaces, kept configurations, lineage and statistics) to a file, from
+    /// which it can be loaded with `FFSResult.load`, and resumed or extended with `resume_ffs`.
+    fn save(&self, path: &str, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| self.0.save(path))
+            .map_err(|err| PyErr::from(GrowError::from(err)))
+    }
+
+    /// Loads a result saved with `save`, or by a checkpointing run.
+    #[staticmethod]
+    fn load(path: &str, py: Python<'_>) -> PyResult<Self> {
+        let data = py
+            .allow_threads(|| FFSRunData::load(path))
+            .map_err(|err| PyErr::from(GrowError::from(err)))?;
+        Ok(Self(Arc::new(Box::new(data))))
+    }
+
     #[getter]
     fn previous_indices(&self) -> Vec<Vec<usize>> {
         self.get_surfaces()
@@ -704,4 +2766,42 @@ impl FFSLevelRef {
     fn get_previous_indices(&self) -> Vec<usize> {
         self.res.surfaces()[self.level].previous_list()
     }
+
+    #[getter]
+    fn get_num_states(&self) -> usize {
+        self.res.surfaces()[self.level].num_states()
+    }
+
+    #[getter]
+    fn get_num_trials(&self) -> usize {
+        self.res.surfaces()[self.level].num_trials()
+    }
+
+    #[getter]
+    fn get_target_size(&self) -> NumTiles {
+        self.res.surfaces()[self.level].target_size()
+    }
+
+    /// The number of trials started from each configuration of the previous surface.
+    #[getter]
+    fn get_parent_trials(&self) -> Vec<usize> {
+        self.res.surfaces()[self.level].parent_trials()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wilson_interval() {
+        let z = normal_quantile(0.975);
+        let interval = wilson_interval(0.5, 100, z);
+        assert!((interval.lower > 0.39) & (interval.upper < 0.61));
+        assert!(interval.contains(0.5));
+
+        // With no trials, nothing is known about the proportion.
+        let interval = wilson_interval(0., 0, z);
+        assert_eq!((interval.lower, interval.upper), (0., 1.));
+    }
 }
@@ -10,6 +10,11 @@ pub mod tileset;
 
 pub mod parser_xgrow;
 
+pub mod export;
+
+pub mod ca_compiler;
+pub mod proofreading;
+
 pub mod colors;
 
 pub mod base;
@@ -17,6 +22,10 @@ pub mod canvas;
 pub mod ffs;
 pub mod state;
 pub mod system;
+pub mod umbrella;
+
+#[cfg(feature = "use_rayon")]
+pub mod parallel;
 
 pub mod ratestore;
 
@@ -3,8 +3,10 @@ extern crate ndarray;
 
 use clap::Parser;
 
-use rgrow::base::RgrowError;
+use rgrow::base::GrowError;
+use rgrow::ca_compiler::CASpec;
 use rgrow::ffs;
+use rgrow::proofreading::ProofreadingKind;
 use rgrow::tileset::TileSet;
 
 use std::fs::File;
@@ -20,6 +22,25 @@ struct Opts {
 enum SubCommand {
     Run(PO),
     NucRate(FFSOptions),
+    /// Compile a cellular automaton rule table (YAML) into a tile set.
+    CompileCa(CACompileOptions),
+}
+
+#[derive(Parser)]
+struct CACompileOptions {
+    /// YAML file with the rule table and layout.
+    input: String,
+    /// Output tile set file.
+    output: String,
+    /// Output format: "yaml" or "xgrow".
+    #[arg(short, long, default_value = "yaml")]
+    format: String,
+    /// Apply k×k proofreading, overriding the rule table file.
+    #[arg(short, long)]
+    proofreading: Option<usize>,
+    /// Use snaked rather than uniform proofreading (diagonal layout only).
+    #[arg(long)]
+    snaked: bool,
 }
 
 #[derive(Parser)]
@@ -37,6 +58,22 @@ struct FFSOptions {
     cutoff_surfaces: usize,
     #[arg(short, long, default_value_t = 30)]
     min_cutoff_size: u32,
+    /// Seed for reproducible results.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Confidence level for the reported intervals.
+    #[arg(long, default_value_t = 0.95)]
+    confidence: f64,
+    /// Number of bootstrap samples for an additional interval on the nucleation rate (0 for none).
+    #[arg(long, default_value_t = 0)]
+    bootstrap: usize,
+    /// File to save the run to after each surface.  If the file exists, the run is resumed from
+    /// it, or extended to the target size.
+    #[arg(long)]
+    checkpoint: Option<String>,
+    /// Print progress to stderr after each surface.
+    #[arg(long)]
+    progress: bool,
 }
 
 impl From<FFSOptions> for ffs::FFSRunConfig {
@@ -48,6 +85,23 @@ impl From<FFSOptions> for ffs::FFSRunConfig {
             cutoff_probability: opts.cutoff_probability,
             cutoff_number: opts.cutoff_surfaces,
             min_cutoff_size: opts.min_cutoff_size,
+            seed: opts.seed,
+            checkpoint: opts.checkpoint,
+            progress: opts.progress.then(|| {
+                ffs::FFSProgressCallback::new(|p| {
+                    eprintln!(
+                        "Surface {} (size {}): {}/{} trials, p_f {:.4}, rate {:e}, {:.1} s",
+                        p.surface,
+                        p.target_size,
+                        p.num_successes,
+                        p.num_trials,
+                        p.forward_prob,
+                        p.nucleation_rate,
+                        p.elapsed
+                    );
+                    true
+                })
+            }),
             ..Default::default()
         }
     }
@@ -62,6 +116,8 @@ struct PO {
 }
 
 fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
     let opts = Opts::parse();
 
     match opts.subcmd {
@@ -81,17 +137,61 @@ fn main() -> anyhow::Result<()> {
             nucrate(po)?;
             Ok(())
         }
+        SubCommand::CompileCa(co) => compile_ca(co),
+    }
+}
+
+fn compile_ca(co: CACompileOptions) -> anyhow::Result<()> {
+    let mut spec: CASpec = serde_yaml::from_reader(File::open(&co.input)?)?;
+    if co.proofreading.is_some() {
+        spec.proofreading = co.proofreading;
+    }
+    if co.snaked {
+        spec.proofreading_kind = ProofreadingKind::Snaked;
     }
+    let tileset = spec.to_tileset()?;
+
+    match co.format.as_str() {
+        "yaml" => serde_yaml::to_writer(File::create(&co.output)?, &tileset)?,
+        "xgrow" => tileset.write_xgrow(&co.output)?,
+        f => anyhow::bail!("Unknown format {f}.  Valid options are \"yaml\" and \"xgrow\"."),
+    }
+    Ok(())
 }
 
-fn nucrate(po: FFSOptions) -> Result<(), RgrowError> {
-    let tileset: TileSet =
-        serde_yaml::from_reader(File::open(po.input.clone()).expect("Input file not found."))
-            .expect("Input file parse erorr.");
+fn nucrate(po: FFSOptions) -> anyhow::Result<()> {
+    let tileset = TileSet::from_file(&po.input)?;
 
-    let ffsrun = tileset.run_ffs(&po.into())?;
+    let confidence = po.confidence;
+    let bootstrap = po.bootstrap;
+    let seed = po.seed;
+
+    let config: ffs::FFSRunConfig = po.into();
+    let ffsrun = match &config.checkpoint {
+        Some(path) if std::path::Path::new(path).exists() => {
+            let data = ffs::FFSRunData::load(path).map_err(GrowError::from)?;
+            tileset.resume_ffs(&data, &config)?
+        }
+        _ => tileset.run_ffs(&config)?,
+    };
 
-    println!("Nuc rate: {:e}", ffsrun.nucleation_rate());
+    let interval = ffsrun.nucleation_rate_interval(confidence);
+    println!(
+        "Nuc rate: {:e} ({}% CI: {:e} to {:e})",
+        ffsrun.nucleation_rate(),
+        confidence * 100.,
+        interval.lower,
+        interval.upper
+    );
+    if bootstrap > 0 {
+        let interval = ffsrun.nucleation_rate_bootstrap(confidence, bootstrap, seed);
+        println!(
+            "Bootstrap {}% CI: {:e} to {:e}",
+            confidence * 100.,
+            interval.lower,
+            interval.upper
+        );
+    }
     let forward_vec_string = ffsrun
         .forward_vec()
         .iter()
@@ -99,5 +199,12 @@ fn nucrate(po: FFSOptions) -> Result<(), RgrowError> {
         .collect::<Vec<String>>()
         .join(", ");
     println!("Forward probabilities: [{forward_vec_string}]");
+    let intervals_string = ffsrun
+        .forward_prob_intervals(confidence)
+        .iter()
+        .map(|x| format!("({:.2e}, {:.2e})", x.lower, x.upper))
+        .collect::<Vec<String>>()
+        .join(", ");
+    println!("Forward probability CIs: [{intervals_string}]");
     Ok(())
 }
@@ -6,7 +6,8 @@ use crate::{
     tileset::{FromTileSet, ProcessedTileSet, TileSet},
 };
 
-use crate::base::{HashMapType, HashSetType};
+use super::friends::{FriendTable, FriendUnion};
+use crate::base::HashMapType;
 use ndarray::prelude::*;
 use rand::prelude::Distribution;
 use serde::{Deserialize, Serialize};
@@ -69,19 +70,19 @@ pub struct ATAM {
     pub(crate) energy_ns: Array2<Energy>,
     pub(crate) energy_we: Array2<Energy>,
 
-    /// Each "friends" hashset gives the potential tile attachments
+    /// Each "friends" table gives the potential tile attachments
     /// at point P if tile T is in that direction.  Eg, friends_e[T]
     /// is a set of tiles that might attach at point P if T is east of
     /// point P.  The ones other than NESW are only for duples.
-    friends_n: Vec<HashSetType<Tile>>,
-    friends_e: Vec<HashSetType<Tile>>,
-    friends_s: Vec<HashSetType<Tile>>,
-    friends_w: Vec<HashSetType<Tile>>,
-    friends_ne: Vec<HashSetType<Tile>>,
-    friends_ee: Vec<HashSetType<Tile>>,
-    friends_se: Vec<HashSetType<Tile>>,
-    friends_ss: Vec<HashSetType<Tile>>,
-    friends_sw: Vec<HashSetType<Tile>>,
+    friends_n: FriendTable,
+    friends_e: FriendTable,
+    friends_s: FriendTable,
+    friends_w: FriendTable,
+    friends_ne: FriendTable,
+    friends_ee: FriendTable,
+    friends_se: FriendTable,
+    friends_ss: FriendTable,
+    friends_sw: FriendTable,
 
     has_duples: bool,
     double_to_left: Array1<Tile>,
@@ -179,7 +180,7 @@ impl System for ATAM {
     ) -> &Self {
         let event = Event::MonomerAttachment(point, tile);
 
-        self.perform_event(state, &event)
+        self.perform_event_with_counts(state, &event)
             .update_after_event(state, &event);
         self
     }
@@ -310,7 +311,6 @@ impl System for ATAM {
     }
 
     fn calc_mismatch_locations<S: State + ?Sized>(&self, state: &S) -> Array2<usize> {
-        let threshold = self.threshold / 4.0; // FIXME: this is a hack
         let mut mismatch_locations = Array2::<usize>::zeros((state.nrows(), state.ncols()));
 
         // TODO: this should use an iterator from the canvas, which we should implement.
@@ -321,62 +321,68 @@ impl System for ATAM {
                 }
                 let p = PointSafe2((i, j));
 
-                let t = state.tile_at_point(p);
+                mismatch_locations[(i, j)] = self.mismatch_code_at_point(state, p);
+            }
+        }
 
-                if t == 0 {
-                    continue;
-                }
+        mismatch_locations
+    }
 
-                let tn;
-                let te;
-                let ts;
-                let tw;
+    fn mismatch_code_at_point<S: State + ?Sized>(&self, state: &S, p: PointSafe2) -> usize {
+        let threshold = self.threshold / 4.0; // FIXME: this is a hack
 
-                // We set duple directions to 0, because these will be
-                // excluded from the mismatch calculation.
-                match self.tile_shape(t) {
-                    TileShape::Single => {
-                        tn = state.tile_to_n(p);
-                        te = state.tile_to_e(p);
-                        ts = state.tile_to_s(p);
-                        tw = state.tile_to_w(p);
-                    }
-                    TileShape::DupleToRight(_) => {
-                        tn = state.tile_to_n(p);
-                        te = 0;
-                        ts = state.tile_to_s(p);
-                        tw = state.tile_to_w(p);
-                    }
-                    TileShape::DupleToBottom(_) => {
-                        tn = state.tile_to_n(p);
-                        te = state.tile_to_e(p);
-                        ts = 0;
-                        tw = state.tile_to_w(p);
-                    }
-                    TileShape::DupleToLeft(_) => {
-                        tn = state.tile_to_n(p);
-                        te = state.tile_to_e(p);
-                        ts = state.tile_to_s(p);
-                        tw = 0;
-                    }
-                    TileShape::DupleToTop(_) => {
-                        tn = 0;
-                        te = state.tile_to_e(p);
-                        ts = state.tile_to_s(p);
-                        tw = state.tile_to_w(p);
-                    }
-                }
+        let t = state.tile_at_point(p);
 
-                let mm_n = ((tn != 0) & (self.get_energy_ns(tn, t) < threshold)) as usize;
-                let mm_e = ((te != 0) & (self.get_energy_we(t, te) < threshold)) as usize;
-                let mm_s = ((ts != 0) & (self.get_energy_ns(t, ts) < threshold)) as usize;
-                let mm_w = ((tw != 0) & (self.get_energy_we(tw, t) < threshold)) as usize;
+        if t == 0 {
+            return 0;
+        }
+
+        let tn;
+        let te;
+        let ts;
+        let tw;
 
-                mismatch_locations[(i, j)] = 8 * mm_n + 4 * mm_e + 2 * mm_s + mm_w;
+        // We set duple directions to 0, because these will be
+        // excluded from the mismatch calculation.
+        match self.tile_shape(t) {
+            TileShape::Single => {
+                tn = state.tile_to_n(p);
+                te = state.tile_to_e(p);
+                ts = state.tile_to_s(p);
+                tw = state.tile_to_w(p);
+            }
+            TileShape::DupleToRight(_) => {
+                tn = state.tile_to_n(p);
+                te = 0;
+                ts = state.tile_to_s(p);
+                tw = state.tile_to_w(p);
+            }
+            TileShape::DupleToBottom(_) => {
+                tn = state.tile_to_n(p);
+                te = state.tile_to_e(p);
+                ts = 0;
+                tw = state.tile_to_w(p);
+            }
+            TileShape::DupleToLeft(_) => {
+                tn = state.tile_to_n(p);
+                te = state.tile_to_e(p);
+                ts = state.tile_to_s(p);
+                tw = 0;
+            }
+            TileShape::DupleToTop(_) => {
+                tn = 0;
+                te = state.tile_to_e(p);
+                ts = state.tile_to_s(p);
+                tw = state.tile_to_w(p);
             }
         }
 
-        mismatch_locations
+        let mm_n = ((tn != 0) & (self.get_energy_ns(tn, t) < threshold)) as usize;
+        let mm_e = ((te != 0) & (self.get_energy_we(t, te) < threshold)) as usize;
+        let mm_s = ((ts != 0) & (self.get_energy_ns(t, ts) < threshold)) as usize;
+        let mm_w = ((tw != 0) & (self.get_energy_we(tw, t) < threshold)) as usize;
+
+        8 * mm_n + 4 * mm_e + 2 * mm_s + mm_w
     }
 
     fn system_info(&self) -> String {
@@ -459,19 +465,19 @@ impl ATAM {
         let te = state.tile_to_e(p);
         let ts = state.tile_to_s(p);
 
-        let mut friends = HashSetType::<Tile>::default();
+        let mut friends = FriendUnion::new();
 
         if tn.nonzero() {
-            friends.extend(&self.friends_n[tn as usize]);
+            friends.add(&self.friends_n, tn);
         }
         if te.nonzero() {
-            friends.extend(&self.friends_e[te as usize]);
+            friends.add(&self.friends_e, te);
         }
         if ts.nonzero() {
-            friends.extend(&self.friends_s[ts as usize]);
+            friends.add(&self.friends_s, ts);
         }
         if tw.nonzero() {
-            friends.extend(&self.friends_w[tw as usize]);
+            friends.add(&self.friends_w, tw);
         }
 
         if self.has_duples {
@@ -481,20 +487,20 @@ impl ATAM {
             let tse = state.tile_to_se(p);
 
             if tss.nonzero() {
-                friends.extend(&self.friends_ss[tss as usize])
+                friends.add(&self.friends_ss, tss)
             }
             if tne.nonzero() {
-                friends.extend(&self.friends_ne[tne as usize])
+                friends.add(&self.friends_ne, tne)
             }
             if tee.nonzero() {
-                friends.extend(&self.friends_ee[tee as usize])
+                friends.add(&self.friends_ee, tee)
             }
             if tse.nonzero() {
-                friends.extend(&self.friends_se[tse as usize])
+                friends.add(&self.friends_se, tse)
             }
         }
 
-        for t in friends.drain() {
+        for t in friends {
             // FIXME: this is likely rather slow, but it's better than giving very confusing rates (many
             // possible double-tile attachements at a point that aren't actually possible, because they are
             // blocked).
@@ -645,15 +651,15 @@ impl ATAM {
             tile_colors: Vec::new(),
             energy_ns: Array2::zeros((ntiles + 1, ntiles + 1)),
             energy_we: Array2::zeros((ntiles + 1, ntiles + 1)),
-            friends_n: Vec::new(),
-            friends_e: Vec::new(),
-            friends_s: Vec::new(),
-            friends_w: Vec::new(),
-            friends_ne: Vec::new(),
-            friends_ee: Vec::new(),
-            friends_se: Vec::new(),
-            friends_ss: Vec::new(),
-            friends_sw: Vec::new(),
+            friends_n: FriendTable::default(),
+            friends_e: FriendTable::default(),
+            friends_s: FriendTable::default(),
+            friends_w: FriendTable::default(),
+            friends_ne: FriendTable::default(),
+            friends_ee: FriendTable::default(),
+            friends_se: FriendTable::default(),
+            friends_ss: FriendTable::default(),
+            friends_sw: FriendTable::default(),
             has_duples: false,
             double_to_left: Array1::zeros(ntiles + 1),
             double_to_top: Array1::zeros(ntiles + 1),
@@ -746,87 +752,76 @@ impl ATAM {
             self.has_duples = false;
         }
 
-        self.friends_n.drain(..);
-        self.friends_e.drain(..);
-        self.friends_s.drain(..);
-        self.friends_w.drain(..);
-        self.friends_ne.drain(..);
-        self.friends_ee.drain(..);
-        self.friends_se.drain(..);
-        self.friends_ss.drain(..);
-        self.friends_sw.drain(..);
-        for _ in 0..ntiles {
-            self.friends_n.push(HashSetType::default());
-            self.friends_e.push(HashSetType::default());
-            self.friends_s.push(HashSetType::default());
-            self.friends_w.push(HashSetType::default());
-            self.friends_ne.push(HashSetType::default());
-            self.friends_ee.push(HashSetType::default());
-            self.friends_se.push(HashSetType::default());
-            self.friends_ss.push(HashSetType::default());
-            self.friends_sw.push(HashSetType::default());
-        }
+        self.friends_n = FriendTable::new(ntiles as usize);
+        self.friends_e = FriendTable::new(ntiles as usize);
+        self.friends_s = FriendTable::new(ntiles as usize);
+        self.friends_w = FriendTable::new(ntiles as usize);
+        self.friends_ne = FriendTable::new(ntiles as usize);
+        self.friends_ee = FriendTable::new(ntiles as usize);
+        self.friends_se = FriendTable::new(ntiles as usize);
+        self.friends_ss = FriendTable::new(ntiles as usize);
+        self.friends_sw = FriendTable::new(ntiles as usize);
         for t1 in 0..ntiles {
             for t2 in 0..ntiles {
                 match self.tile_shape(t1) {
                     TileShape::Single => {
                         if self.get_energy_ns(t2, t1) != 0. {
-                            self.friends_n[t2 as usize].insert(t1);
+                            self.friends_n.insert(t2, t1);
                         }
                         if self.get_energy_we(t2, t1) != 0. {
-                            self.friends_w[t2 as usize].insert(t1);
+                            self.friends_w.insert(t2, t1);
                         }
                         if self.get_energy_ns(t1, t2) != 0. {
-                            self.friends_s[t2 as usize].insert(t1);
+                            self.friends_s.insert(t2, t1);
                         }
                         if self.get_energy_we(t1, t2) != 0. {
-                            self.friends_e[t2 as usize].insert(t1);
+                            self.friends_e.insert(t2, t1);
                         }
                     }
                     TileShape::DupleToRight(td) => {
                         if self.get_energy_ns(t2, td) != 0. {
-                            self.friends_ne[t2 as usize].insert(t1);
+                            self.friends_ne.insert(t2, t1);
                         }
                         if self.get_energy_ns(td, t2) != 0. {
-                            self.friends_se[t2 as usize].insert(t1);
+                            self.friends_se.insert(t2, t1);
                         }
                         if self.get_energy_we(td, t2) != 0. {
-                            self.friends_ee[t2 as usize].insert(t1);
+                            self.friends_ee.insert(t2, t1);
                         }
                         if self.get_energy_ns(t2, t1) != 0. {
-                            self.friends_n[t2 as usize].insert(t1);
+                            self.friends_n.insert(t2, t1);
                         }
                         if self.get_energy_we(t2, t1) != 0. {
-                            self.friends_w[t2 as usize].insert(t1);
+                            self.friends_w.insert(t2, t1);
                         }
                         if self.get_energy_ns(t1, t2) != 0. {
-                            self.friends_s[t2 as usize].insert(t1);
+                            self.friends_s.insert(t2, t1);
                         }
                         if self.get_energy_we(t1, t2) != 0. {
-                            self.friends_e[t2 as usize].insert(t1);
+                            self.friends_e.insert(t2, t1);
                         }
                     }
                     TileShape::DupleToBottom(td) => {
                         if self.get_energy_we(t2, td) != 0. {
-                            self.friends_sw[t2 as usize].insert(t1);
+                            self.friends_sw.insert(t2, t1);
                         }
                         if self.get_energy_we(td, t2) != 0. {
-                            self.friends_se[t2 as usize].insert(t1);
+                            self.friends_se.insert(t2, t1);
                         }
                         if self.get_energy_ns(td, t2) != 0. {
-                            self.friends_ss[t2 as usize].insert(t1);
+                            self.friends_ss.insert(t2, t1);
                         }
                         if self.get_energy_ns(t2, t1) != 0. {
-                            self.friends_n[t2 as usize].insert(t1);
+                            self.friends_n.insert(t2, t1);
                         }
                         if self.get_energy_we(t2, t1) != 0. {
-                            self.friends_w[t2 as usize].insert(t1);
+                            self.friends_w.insert(t2, t1);
                         }
                         if self.get_energy_ns(t1, t2) != 0. {
-                            self.friends_s[t2 as usize].insert(t1);
+                            self.friends_s.insert(t2, t1);
                         }
                         if self.get_energy_we(t1, t2) != 0. {
-                            self.friends_e[t2 as usize].insert(t1);
+                            self.friends_e.insert(t2, t1);
                         }
                     }
                     TileShape::DupleToLeft(_) => (),
@@ -935,4 +930,17 @@ impl SystemWithDimers for ATAM {
     fn calc_dimers(&self) -> Vec<crate::system::DimerInfo> {
         todo!()
     }
+
+    /// The aTAM has no concentrations or rates for assemblies to have, so there are none.
+    fn calc_nmers(&self, _max_size: crate::base::NumTiles) -> Vec<crate::system::NmerInfo> {
+        Vec::new()
+    }
+
+    fn bond_energy_ns(&self, tn: Tile, ts: Tile) -> f64 {
+        self.get_energy_ns(tn, ts)
+    }
+
+    fn bond_energy_we(&self, tw: Tile, te: Tile) -> f64 {
+        self.get_energy_we(tw, te)
+    }
 }
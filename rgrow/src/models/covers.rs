@@ -1,14 +1,14 @@
 use ndarray::Array2;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use super::oldktam::OldKTAM;
 use crate::{
-    base::{HashMapType, Rate, RgrowError, Tile},
+    base::{GrowError, HashMapType, NumTiles, Rate, RgrowError, SimRng, Tile},
     canvas::{PointSafe2, PointSafeHere},
     models::oldktam::Seed,
     state::State,
     system::{
-        ChunkSize, DimerInfo, Event, StepOutcome, System, SystemInfo, SystemWithDimers,
+        ChunkSize, DimerInfo, Event, NmerInfo, StepOutcome, System, SystemInfo, SystemWithDimers,
         TileBondInfo,
     },
     tileset::{FromTileSet, ProcessedTileSet, TileSet, GMC_DEFAULT, GSE_DEFAULT},
@@ -162,27 +162,41 @@ impl System for StaticKTAMCover {
         self.inner.calc_mismatch_locations(state)
     }
 
+    fn mismatch_code_at_point<S: State + ?Sized>(&self, state: &S, p: PointSafe2) -> usize {
+        self.inner.mismatch_code_at_point(state, p)
+    }
+
     fn take_single_step<S: State + ?Sized>(
         &self,
         state: &mut S,
         max_time_step: f64,
-    ) -> StepOutcome {
-        let time_step = -f64::ln(thread_rng().gen()) / state.total_rate();
+        rng: &mut SimRng,
+    ) -> Result<StepOutcome, GrowError> {
+        let time_step = -f64::ln(rng.gen()) / state.total_rate();
         if time_step > max_time_step {
             state.add_time(max_time_step);
-            return StepOutcome::NoEventIn(max_time_step);
+            return Ok(StepOutcome::NoEventIn(max_time_step));
         }
-        let (point, remainder) = state.choose_point(); // todo: resultify
+        let (point, remainder) = match state.choose_point(rng) {
+            Ok(x) => x,
+            Err(_) => {
+                // Accumulated floating-point error can leave the rate store's sums
+                // inconsistent; resum exactly and try once more before giving up.
+                state.resum();
+                state.choose_point(rng)?
+            }
+        };
         let event = self.choose_event_at_point(state, PointSafe2(point), remainder); // FIXME
+        self.record_event_stats(state, &event);
         if let Event::None = event {
             state.add_time(time_step);
-            return StepOutcome::DeadEventAt(time_step);
+            return Ok(StepOutcome::DeadEventAt(time_step));
         }
 
-        self.perform_event(state, &event);
+        self.perform_event_with_counts(state, &event);
         self.update_after_event(state, &event);
         state.add_time(time_step);
-        StepOutcome::HadEventAt(time_step)
+        Ok(StepOutcome::HadEventAt(time_step))
     }
 
     fn set_safe_point<S: State + ?Sized>(
@@ -233,6 +247,18 @@ impl SystemWithDimers for StaticKTAMCover {
     fn calc_dimers(&self) -> Vec<DimerInfo> {
         self.inner.calc_dimers()
     }
+
+    fn calc_nmers(&self, max_size: NumTiles) -> Vec<NmerInfo> {
+        self.inner.calc_nmers(max_size)
+    }
+
+    fn bond_energy_ns(&self, tn: Tile, ts: Tile) -> f64 {
+        self.inner.bond_energy_ns(tn, ts)
+    }
+
+    fn bond_energy_we(&self, tw: Tile, te: Tile) -> f64 {
+        self.inner.bond_energy_we(tw, te)
+    }
 }
 
 impl StaticKTAMCover {
@@ -0,0 +1,181 @@
+//! Precomputed tables of possible attachments ("friends") at a point, given the tile in a
+//! particular direction, shared by the kTAM and aTAM implementations.
+//!
+//! Each table stores, for every neighboring tile, a bitset over tile numbers.  Finding the
+//! candidate attachments at a point is then a word-wise union of at most a handful of rows,
+//! which needs no allocation and iterates in tile order.
+
+use serde::{Deserialize, Serialize};
+
+use crate::base::Tile;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// The maximum number of rows that can be combined in a [FriendUnion]: the four direct
+/// neighbors, plus the five additional positions used for duples.
+pub const MAX_UNION_ROWS: usize = 9;
+
+/// For each neighboring tile T, the set of tiles that might attach at a point if T is in the
+/// table's direction from that point.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FriendTable {
+    /// Number of u64 words in each row.
+    words: usize,
+    /// Row-major bitsets, one row of `words` words per neighboring tile.
+    bits: Vec<u64>,
+    /// For each row, the half-open range of words that might be nonzero.
+    ranges: Vec<(u32, u32)>,
+}
+
+impl FriendTable {
+    pub fn new(ntiles: usize) -> Self {
+        let words = ntiles.div_ceil(WORD_BITS);
+        Self {
+            words,
+            bits: vec![0; words * ntiles],
+            ranges: vec![(0, 0); ntiles],
+        }
+    }
+
+    /// Number of neighboring tiles the table covers.
+    pub fn ntiles(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Records that `friend` might attach next to `neighbor`.
+    pub fn insert(&mut self, neighbor: Tile, friend: Tile) {
+        let (n, f) = (neighbor as usize, friend as usize);
+        let w = f / WORD_BITS;
+        self.bits[n * self.words + w] |= 1 << (f % WORD_BITS);
+
+        let range = &mut self.ranges[n];
+        let w = w as u32;
+        *range = if range.0 == range.1 {
+            (w, w + 1)
+        } else {
+            (range.0.min(w), range.1.max(w + 1))
+        };
+    }
+
+    pub fn contains(&self, neighbor: Tile, friend: Tile) -> bool {
+        let (n, f) = (neighbor as usize, friend as usize);
+        (self.bits[n * self.words + f / WORD_BITS] >> (f % WORD_BITS)) & 1 == 1
+    }
+
+    fn row(&self, neighbor: Tile) -> (&[u64], (u32, u32)) {
+        let n = neighbor as usize;
+        (
+            &self.bits[n * self.words..(n + 1) * self.words],
+            self.ranges[n],
+        )
+    }
+
+    /// Iterates over the friends of a single neighboring tile.
+    pub fn friends_of(&self, neighbor: Tile) -> FriendUnion<'_> {
+        let mut u = FriendUnion::new();
+        u.add(self, neighbor);
+        u
+    }
+}
+
+/// An iterator over the union of several rows of [FriendTable]s, without duplicates and in
+/// ascending tile order.
+#[derive(Debug, Clone)]
+pub struct FriendUnion<'a> {
+    rows: [&'a [u64]; MAX_UNION_ROWS],
+    nrows: usize,
+    word: usize,
+    end: usize,
+    current: u64,
+}
+
+impl<'a> Default for FriendUnion<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> FriendUnion<'a> {
+    pub fn new() -> Self {
+        Self {
+            rows: [&[]; MAX_UNION_ROWS],
+            nrows: 0,
+            word: usize::MAX,
+            end: 0,
+            current: 0,
+        }
+    }
+
+    /// Adds the friends of `neighbor` in `table` to the union.  This must be done before
+    /// iteration starts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than [MAX_UNION_ROWS] rows are added.
+    pub fn add(&mut self, table: &'a FriendTable, neighbor: Tile) {
+        let (row, (start, end)) = table.row(neighbor);
+        if start == end {
+            return;
+        }
+        self.rows[self.nrows] = row;
+        self.nrows += 1;
+        self.word = self.word.min(start as usize);
+        self.end = self.end.max(end as usize);
+    }
+
+    fn load_word(&mut self) {
+        self.current = self.rows[..self.nrows]
+            .iter()
+            .fold(0, |acc, row| acc | row[self.word]);
+    }
+}
+
+impl<'a> Iterator for FriendUnion<'a> {
+    type Item = Tile;
+
+    fn next(&mut self) -> Option<Tile> {
+        if self.nrows == 0 {
+            return None;
+        }
+        while self.current == 0 {
+            if self.word >= self.end {
+                return None;
+            }
+            self.load_word();
+            self.word += 1;
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(((self.word - 1) * WORD_BITS + bit) as Tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_friend_union() {
+        let mut a = FriendTable::new(200);
+        let mut b = FriendTable::new(200);
+        a.insert(3, 1);
+        a.insert(3, 130);
+        a.insert(3, 64);
+        b.insert(5, 64);
+        b.insert(5, 199);
+        b.insert(5, 2);
+
+        assert!(a.contains(3, 130));
+        assert!(!a.contains(3, 131));
+        assert_eq!(a.friends_of(3).collect::<Vec<_>>(), vec![1, 64, 130]);
+        assert_eq!(a.friends_of(4).count(), 0);
+
+        let mut u = FriendUnion::new();
+        u.add(&a, 3);
+        u.add(&b, 5);
+        u.add(&b, 4);
+        assert_eq!(u.collect::<Vec<_>>(), vec![1, 2, 64, 130, 199]);
+
+        assert_eq!(FriendUnion::new().next(), None);
+    }
+}
@@ -9,17 +9,19 @@
 /// - Unlike Xgrow, there is no *requirement* that there be a seed.
 use super::ktam_fission::*;
 use crate::{
-    base::{GrowError, RgrowError},
+    base::{GrowError, NumTiles, RgrowError},
     canvas::{PointSafe2, PointSafeHere},
     state::State,
     system::{
-        ChunkHandling, ChunkSize, DimerInfo, Event, FissionHandling, NeededUpdate, Orientation,
-        System, SystemInfo, SystemWithDimers, TileBondInfo,
+        ChunkHandling, ChunkSize, DimerInfo, Event, FissionHandling, NeededUpdate, NmerInfo,
+        Orientation, System, SystemInfo, SystemWithDimers, TileBondInfo,
     },
     tileset::{FromTileSet, ProcessedTileSet, TileSet, GMC_DEFAULT, GSE_DEFAULT},
 };
 
-use crate::base::{HashMapType, HashSetType};
+use super::friends::{FriendTable, FriendUnion};
+use super::nmers::NmerModel;
+use crate::base::HashMapType;
 use ndarray::prelude::*;
 use rand::prelude::Distribution;
 use serde::{Deserialize, Serialize};
@@ -96,28 +98,90 @@ pub struct KTAM {
     pub glue_names: Vec<String>,
 
     // End of public stuff, now moving to calculated stuff.
-    pub(crate) energy_ns: Array2<Energy>,
-    pub(crate) energy_we: Array2<Energy>,
+    /// Bond strengths between glues.  Energies between tiles are looked up through their edge
+    /// glues, so storage does not grow with the square of the number of tiles.
+    pub(crate) glue_bonds: GlueBonds,
 
-    /// Each "friends" hashset gives the potential tile attachments
+    /// Each "friends" table gives the potential tile attachments
     /// at point P if tile T is in that direction.  Eg, friends_e[T]
     /// is a set of tiles that might attach at point P if T is east of
     /// point P.  The ones other than NESW are only for duples.
-    friends_n: Vec<HashSetType<Tile>>,
-    friends_e: Vec<HashSetType<Tile>>,
-    friends_s: Vec<HashSetType<Tile>>,
-    friends_w: Vec<HashSetType<Tile>>,
-    friends_ne: Vec<HashSetType<Tile>>,
-    friends_ee: Vec<HashSetType<Tile>>,
-    friends_se: Vec<HashSetType<Tile>>,
-    friends_ss: Vec<HashSetType<Tile>>,
-    friends_sw: Vec<HashSetType<Tile>>,
+    friends_n: FriendTable,
+    friends_e: FriendTable,
+    friends_s: FriendTable,
+    friends_w: FriendTable,
+    friends_ne: FriendTable,
+    friends_ee: FriendTable,
+    friends_se: FriendTable,
+    friends_ss: FriendTable,
+    friends_sw: FriendTable,
 
     has_duples: bool,
     duple_info: Array1<TileShape>,
     should_be_counted: Array1<bool>,
 }
 
+/// Bond strengths between pairs of glues, stored sparsely: each glue's strength binding to
+/// itself, and a sorted list of nonzero links to other glues.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct GlueBonds {
+    self_strengths: Vec<Strength>,
+    links: Vec<Vec<(Glue, Strength)>>,
+}
+
+impl GlueBonds {
+    fn new(glue_strengths: &Array1<Strength>, glue_links: &Array2<Strength>) -> Self {
+        let mut links = vec![Vec::new(); glue_strengths.len()];
+        for ((g1, g2), s) in glue_links.indexed_iter() {
+            if (g1 != g2) && (*s != 0.) {
+                links[g1].push((g2, *s));
+            }
+        }
+        Self {
+            self_strengths: glue_strengths.to_vec(),
+            links,
+        }
+    }
+
+    /// Strength of glue `g1` binding to glue `g2`, where `g1` is on the north or west side.
+    #[inline(always)]
+    fn get(&self, g1: Glue, g2: Glue) -> Strength {
+        if g1 == g2 {
+            return self.self_strengths[g1];
+        }
+        let l = &self.links[g1];
+        if l.is_empty() {
+            return 0.;
+        }
+        match l.binary_search_by_key(&g2, |(g, _)| *g) {
+            Ok(i) => l[i].1,
+            Err(_) => 0.,
+        }
+    }
+
+    fn set_self_strengths(&mut self, glue_strengths: &Array1<Strength>) {
+        self.self_strengths.clear();
+        self.self_strengths.extend(glue_strengths.iter());
+    }
+
+    /// For each glue, the glues it might bind to, in either orientation.
+    fn partners(&self) -> Vec<Vec<Glue>> {
+        let mut partners = vec![Vec::new(); self.self_strengths.len()];
+        for (g, s) in self.self_strengths.iter().enumerate() {
+            if *s != 0. {
+                partners[g].push(g);
+            }
+        }
+        for (g1, l) in self.links.iter().enumerate() {
+            for (g2, _) in l {
+                partners[g1].push(*g2);
+                partners[*g2].push(g1);
+            }
+        }
+        partners
+    }
+}
+
 impl System for KTAM {
     fn update_after_event<S: State + ?Sized>(&self, state: &mut S, event: &Event) {
         match event {
@@ -527,7 +591,6 @@ impl System for KTAM {
     }
 
     fn calc_mismatch_locations<S: State + ?Sized>(&self, state: &S) -> Array2<usize> {
-        let threshold = 0.5; // Todo: fix this
         let mut mismatch_locations = Array2::<usize>::zeros((state.nrows(), state.ncols()));
 
         // TODO: this should use an iterator from the canvas, which we should implement.
@@ -538,62 +601,68 @@ impl System for KTAM {
                 }
                 let p = PointSafe2((i, j));
 
-                let t = state.tile_at_point(p);
+                mismatch_locations[(i, j)] = self.mismatch_code_at_point(state, p);
+            }
+        }
 
-                if t == 0 {
-                    continue;
-                }
+        mismatch_locations
+    }
 
-                let tn;
-                let te;
-                let ts;
-                let tw;
-
-                // We set duple directions to 0, because these will be
-                // excluded from the mismatch calculation.
-                match self.tile_shape(t) {
-                    TileShape::Single => {
-                        tn = state.tile_to_n(p);
-                        te = state.tile_to_e(p);
-                        ts = state.tile_to_s(p);
-                        tw = state.tile_to_w(p);
-                    }
-                    TileShape::DupleToRight(_) => {
-                        tn = state.tile_to_n(p);
-                        te = 0;
-                        ts = state.tile_to_s(p);
-                        tw = state.tile_to_w(p);
-                    }
-                    TileShape::DupleToBottom(_) => {
-                        tn = state.tile_to_n(p);
-                        te = state.tile_to_e(p);
-                        ts = 0;
-                        tw = state.tile_to_w(p);
-                    }
-                    TileShape::DupleToLeft(_) => {
-                        tn = state.tile_to_n(p);
-                        te = state.tile_to_e(p);
-                        ts = state.tile_to_s(p);
-                        tw = 0;
-                    }
-                    TileShape::DupleToTop(_) => {
-                        tn = 0;
-                        te = state.tile_to_e(p);
-                        ts = state.tile_to_s(p);
-                        tw = state.tile_to_w(p);
-                    }
-                }
+    fn mismatch_code_at_point<S: State + ?Sized>(&self, state: &S, p: PointSafe2) -> usize {
+        let threshold = 0.5; // Todo: fix this
 
-                let mm_n = ((tn != 0) & (self.get_energy_ns(tn, t) < threshold)) as usize;
-                let mm_e = ((te != 0) & (self.get_energy_we(t, te) < threshold)) as usize;
-                let mm_s = ((ts != 0) & (self.get_energy_ns(t, ts) < threshold)) as usize;
-                let mm_w = ((tw != 0) & (self.get_energy_we(tw, t) < threshold)) as usize;
+        let t = state.tile_at_point(p);
+
+        if t == 0 {
+            return 0;
+        }
 
-                mismatch_locations[(i, j)] = 8 * mm_n + 4 * mm_e + 2 * mm_s + mm_w;
+        let tn;
+        let te;
+        let ts;
+        let tw;
+
+        // We set duple directions to 0, because these will be
+        // excluded from the mismatch calculation.
+        match self.tile_shape(t) {
+            TileShape::Single => {
+                tn = state.tile_to_n(p);
+                te = state.tile_to_e(p);
+                ts = state.tile_to_s(p);
+                tw = state.tile_to_w(p);
+            }
+            TileShape::DupleToRight(_) => {
+                tn = state.tile_to_n(p);
+                te = 0;
+                ts = state.tile_to_s(p);
+                tw = state.tile_to_w(p);
+            }
+            TileShape::DupleToBottom(_) => {
+                tn = state.tile_to_n(p);
+                te = state.tile_to_e(p);
+                ts = 0;
+                tw = state.tile_to_w(p);
+            }
+            TileShape::DupleToLeft(_) => {
+                tn = state.tile_to_n(p);
+                te = state.tile_to_e(p);
+                ts = state.tile_to_s(p);
+                tw = 0;
+            }
+            TileShape::DupleToTop(_) => {
+                tn = 0;
+                te = state.tile_to_e(p);
+                ts = state.tile_to_s(p);
+                tw = state.tile_to_w(p);
             }
         }
 
-        mismatch_locations
+        let mm_n = ((tn != 0) & (self.get_energy_ns(tn, t) < threshold)) as usize;
+        let mm_e = ((te != 0) & (self.get_energy_we(t, te) < threshold)) as usize;
+        let mm_s = ((ts != 0) & (self.get_energy_ns(t, ts) < threshold)) as usize;
+        let mm_w = ((tw != 0) & (self.get_energy_we(tw, t) < threshold)) as usize;
+
+        8 * mm_n + 4 * mm_e + 2 * mm_s + mm_w
     }
 
     fn set_param(
@@ -606,8 +675,13 @@ impl System for KTAM {
                 let g_se = value
                     .downcast_ref::<f64>()
                     .ok_or(GrowError::WrongParameterType(name.to_string()))?;
+                // Energies are scaled by g_se on lookup, so only the friend tables might need
+                // recalculating, if bonds appear or disappear.
+                let friends_changed = (self.g_se == 0.) != (*g_se == 0.);
                 self.g_se = *g_se;
-                self.update_system();
+                if friends_changed {
+                    self.update_friends();
+                }
                 Ok(NeededUpdate::NonZero)
             }
             "alpha" => {
@@ -615,7 +689,6 @@ impl System for KTAM {
                     .downcast_ref::<f64>()
                     .ok_or(GrowError::WrongParameterType(name.to_string()))?;
                 self.alpha = *alpha;
-                self.update_system();
                 Ok(NeededUpdate::NonZero)
             }
             "kf" => {
@@ -623,7 +696,6 @@ impl System for KTAM {
                     .downcast_ref::<f64>()
                     .ok_or(GrowError::WrongParameterType(name.to_string()))?;
                 self.kf = *kf;
-                self.update_system();
                 Ok(NeededUpdate::NonZero)
             }
             "tile_concs" => {
@@ -638,8 +710,20 @@ impl System for KTAM {
                 let glue_strengths = value
                     .downcast_ref::<Array1<f64>>()
                     .ok_or(GrowError::WrongParameterType(name.to_string()))?;
-                self.glue_strengths = glue_strengths.clone();
-                self.update_system();
+                if glue_strengths.len() != self.glue_strengths.len() {
+                    self.glue_strengths = glue_strengths.clone();
+                    self.update_system();
+                } else {
+                    let friends_changed = glue_strengths
+                        .iter()
+                        .zip(self.glue_strengths.iter())
+                        .any(|(new, old)| (*new == 0.) != (*old == 0.));
+                    self.glue_strengths = glue_strengths.clone();
+                    self.glue_bonds.set_self_strengths(&self.glue_strengths);
+                    if friends_changed {
+                        self.update_friends();
+                    }
+                }
                 Ok(NeededUpdate::NonZero)
             }
             "glue_links" => {
@@ -685,34 +769,77 @@ impl SystemWithDimers for KTAM {
         // a double tile, are we double-counting the rates?  Note also that this relies on
         let mut dvec = Vec::new();
 
-        for ((t1, t2), e) in self.energy_ns.indexed_iter() {
-            if *e > 0. {
+        let ntiles = self.tile_concs.len();
+        let tile_pairs = || (0..ntiles).flat_map(|t1| (0..ntiles).map(move |t2| (t1, t2)));
+
+        for (t1, t2) in tile_pairs() {
+            let e = self.get_energy_ns(t1 as Tile, t2 as Tile);
+            if e > 0. {
                 let biconc = self.tile_concs[t1] * self.tile_concs[t2];
                 dvec.push(DimerInfo {
                     t1: t1 as Tile,
                     t2: t2 as Tile,
                     orientation: Orientation::NS,
                     formation_rate: self.kf * biconc / 1e9, // FIXME: 1e9 because we're using nM for concs
-                    equilibrium_conc: biconc * f64::exp(*e - self.alpha),
+                    equilibrium_conc: biconc * f64::exp(e - self.alpha),
                 });
             }
         }
 
-        for ((t1, t2), e) in self.energy_we.indexed_iter() {
-            if *e > 0. {
+        for (t1, t2) in tile_pairs() {
+            let e = self.get_energy_we(t1 as Tile, t2 as Tile);
+            if e > 0. {
                 let biconc = f64::exp(2. * self.alpha) * self.tile_concs[t1] * self.tile_concs[t2];
                 dvec.push(DimerInfo {
                     t1: t1 as Tile,
                     t2: t2 as Tile,
                     orientation: Orientation::WE,
                     formation_rate: self.kf * biconc / 1e9, // FIXME: 1e9 because we're using nM for concs
-                    equilibrium_conc: biconc * f64::exp(*e - self.alpha),
+                    equilibrium_conc: biconc * f64::exp(e - self.alpha),
                 });
             }
         }
 
         dvec
     }
+
+    fn calc_nmers(&self, max_size: NumTiles) -> Vec<NmerInfo> {
+        // Detachment rates are kf u0 exp(alpha - energy), with u0 = 1 M = 1e9 nM.
+        let activity_scale = f64::exp(-self.alpha) / 1e9;
+        let model = NmerModel {
+            monomer_activity: self
+                .tile_concs
+                .iter()
+                .enumerate()
+                .map(|(t, &c)| {
+                    if (t == 0) || (self.has_duples && self.is_fake_duple(t as Tile)) {
+                        0.
+                    } else {
+                        c * activity_scale
+                    }
+                })
+                .collect(),
+            attachment_rate: self.tile_concs.iter().map(|&c| self.kf * c).collect(),
+            duple_part: (0..self.tile_concs.len())
+                .map(|t| match self.duple_info[t] {
+                    TileShape::DupleToRight(part) => Some(((0, 1), part)),
+                    TileShape::DupleToBottom(part) => Some(((1, 0), part)),
+                    _ => None,
+                })
+                .collect(),
+            energy_ns: &|tn, ts| self.get_energy_ns(tn, ts),
+            energy_we: &|tw, te| self.get_energy_we(tw, te),
+        };
+        model.enumerate(max_size)
+    }
+
+    fn bond_energy_ns(&self, tn: Tile, ts: Tile) -> f64 {
+        self.get_energy_ns(tn, ts)
+    }
+
+    fn bond_energy_we(&self, tw: Tile, te: Tile) -> f64 {
+        self.get_energy_we(tw, te)
+    }
 }
 
 impl TileBondInfo for KTAM {
@@ -769,17 +896,16 @@ impl KTAM {
             seed: Seed::None(),
             tile_colors: Vec::new(),
             fission_handling: FissionHandling::NoFission,
-            energy_ns: Array2::zeros((ntiles + 1, ntiles + 1)),
-            energy_we: Array2::zeros((ntiles + 1, ntiles + 1)),
-            friends_n: Vec::new(),
-            friends_e: Vec::new(),
-            friends_s: Vec::new(),
-            friends_w: Vec::new(),
-            friends_ne: Vec::new(),
-            friends_ee: Vec::new(),
-            friends_se: Vec::new(),
-            friends_ss: Vec::new(),
-            friends_sw: Vec::new(),
+            glue_bonds: GlueBonds::default(),
+            friends_n: FriendTable::default(),
+            friends_e: FriendTable::default(),
+            friends_s: FriendTable::default(),
+            friends_w: FriendTable::default(),
+            friends_ne: FriendTable::default(),
+            friends_ee: FriendTable::default(),
+            friends_se: FriendTable::default(),
+            friends_ss: FriendTable::default(),
+            friends_sw: FriendTable::default(),
             has_duples: false,
             duple_info: Array1::default(ntiles + 1),
             should_be_counted: Array1::default(ntiles + 1),
@@ -863,19 +989,9 @@ impl KTAM {
     pub fn update_system(&mut self) {
         let ntiles = self.tile_concs.len() as Tile;
 
+        self.glue_bonds = GlueBonds::new(&self.glue_strengths, &self.glue_links);
+
         for t1 in 0..(ntiles as usize) {
-            for t2 in 0..(ntiles as usize) {
-                let t1r = self.tile_edges.row(t1);
-                let t2r = self.tile_edges.row(t2);
-                self.energy_ns[(t1, t2)] = self.g_se * self.glue_links[(t1r[2], t2r[0])];
-                if t1r[2] == t2r[0] {
-                    self.energy_ns[(t1, t2)] = self.g_se * self.glue_strengths[t1r[2]]
-                }
-                self.energy_we[(t1, t2)] = self.g_se * self.glue_links[(t1r[1], t2r[3])];
-                if t1r[1] == t2r[3] {
-                    self.energy_we[(t1, t2)] = self.g_se * self.glue_strengths[t1r[1]]
-                }
-            }
             self.should_be_counted[t1] = (t1 > 0) && (self.tile_concs[t1] > 0.);
         }
 
@@ -887,7 +1003,6 @@ impl KTAM {
                     self.duple_info[t2] = TileShape::DupleToLeft(t1 as Tile);
                     self.duple_info[t1] = TileShape::DupleToRight(t2 as Tile);
                     self.should_be_counted[t2] = false;
-                    self.energy_we[(t1, t2)] = 0.0;
                 }
             }
             for (t1, t2) in self.double_to_bottom.indexed_iter() {
@@ -896,102 +1011,116 @@ impl KTAM {
                     self.duple_info[t2] = TileShape::DupleToTop(t1 as Tile);
                     self.duple_info[t1] = TileShape::DupleToBottom(t2 as Tile);
                     self.should_be_counted[t2] = false;
-                    self.energy_ns[(t1, t2)] = 0.0;
                 }
             }
         } else {
             self.has_duples = false;
         }
 
-        self.friends_n.drain(..);
-        self.friends_e.drain(..);
-        self.friends_s.drain(..);
-        self.friends_w.drain(..);
-        self.friends_ne.drain(..);
-        self.friends_ee.drain(..);
-        self.friends_se.drain(..);
-        self.friends_ss.drain(..);
-        self.friends_sw.drain(..);
-        for _ in 0..ntiles {
-            self.friends_n.push(HashSetType::default());
-            self.friends_e.push(HashSetType::default());
-            self.friends_s.push(HashSetType::default());
-            self.friends_w.push(HashSetType::default());
-            self.friends_ne.push(HashSetType::default());
-            self.friends_ee.push(HashSetType::default());
-            self.friends_se.push(HashSetType::default());
-            self.friends_ss.push(HashSetType::default());
-            self.friends_sw.push(HashSetType::default());
+        self.update_friends();
+    }
+
+    /// Recalculates the friend tables.  Rather than checking every pair of tiles, candidates are
+    /// found through the glues that might bind to each tile's edges, then checked exactly.
+    fn update_friends(&mut self) {
+        let ntiles = self.tile_concs.len();
+        let partners = self.glue_bonds.partners();
+
+        // Tiles with each glue, by side (N, E, S, W).
+        let mut tiles_with_glue = vec![vec![Vec::new(); partners.len()]; 4];
+        for t in 0..ntiles {
+            for (side, tiles) in tiles_with_glue.iter_mut().enumerate() {
+                tiles[self.tile_edges[(t, side)]].push(t as Tile);
+            }
         }
-        for t1 in 0..(ntiles) {
-            for t2 in 0..(ntiles) {
-                let t1t = t1 as Tile;
-                match self.tile_shape(t1t) {
-                    TileShape::Single => {
-                        if self.get_energy_ns(t2, t1) != 0. {
-                            self.friends_n[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_we(t2, t1) != 0. {
-                            self.friends_w[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_ns(t1, t2) != 0. {
-                            self.friends_s[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_we(t1, t2) != 0. {
-                            self.friends_e[t2 as usize].insert(t1);
-                        }
-                    }
-                    TileShape::DupleToRight(td) => {
+        // Tiles whose `side` might bind to `tile`'s `tile_side`.
+        let candidates = |tile: Tile, tile_side: usize, side: usize| {
+            partners[self.tile_edges[(tile as usize, tile_side)]]
+                .iter()
+                .flat_map(|g| tiles_with_glue[side][*g].iter().copied())
+                .collect::<Vec<_>>()
+        };
+
+        let mut friends_n = FriendTable::new(ntiles);
+        let mut friends_e = FriendTable::new(ntiles);
+        let mut friends_s = FriendTable::new(ntiles);
+        let mut friends_w = FriendTable::new(ntiles);
+        let mut friends_ne = FriendTable::new(ntiles);
+        let mut friends_ee = FriendTable::new(ntiles);
+        let mut friends_se = FriendTable::new(ntiles);
+        let mut friends_ss = FriendTable::new(ntiles);
+        let mut friends_sw = FriendTable::new(ntiles);
+
+        for t1 in 0..(ntiles as Tile) {
+            match self.tile_shape(t1) {
+                TileShape::DupleToLeft(_) | TileShape::DupleToTop(_) => continue,
+                TileShape::Single => (),
+                TileShape::DupleToRight(td) => {
+                    for t2 in candidates(td, 0, 2) {
                         if self.get_energy_ns(t2, td) != 0. {
-                            self.friends_ne[t2 as usize].insert(t1);
+                            friends_ne.insert(t2, t1);
                         }
+                    }
+                    for t2 in candidates(td, 2, 0) {
                         if self.get_energy_ns(td, t2) != 0. {
-                            self.friends_se[t2 as usize].insert(t1);
+                            friends_se.insert(t2, t1);
                         }
+                    }
+                    for t2 in candidates(td, 1, 3) {
                         if self.get_energy_we(td, t2) != 0. {
-                            self.friends_ee[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_ns(t2, t1) != 0. {
-                            self.friends_n[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_we(t2, t1) != 0. {
-                            self.friends_w[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_ns(t1, t2) != 0. {
-                            self.friends_s[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_we(t1, t2) != 0. {
-                            self.friends_e[t2 as usize].insert(t1);
+                            friends_ee.insert(t2, t1);
                         }
                     }
-                    TileShape::DupleToBottom(td) => {
+                }
+                TileShape::DupleToBottom(td) => {
+                    for t2 in candidates(td, 3, 1) {
                         if self.get_energy_we(t2, td) != 0. {
-                            self.friends_sw[t2 as usize].insert(t1);
+                            friends_sw.insert(t2, t1);
                         }
+                    }
+                    for t2 in candidates(td, 1, 3) {
                         if self.get_energy_we(td, t2) != 0. {
-                            self.friends_se[t2 as usize].insert(t1);
+                            friends_se.insert(t2, t1);
                         }
+                    }
+                    for t2 in candidates(td, 2, 0) {
                         if self.get_energy_ns(td, t2) != 0. {
-                            self.friends_ss[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_ns(t2, t1) != 0. {
-                            self.friends_n[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_we(t2, t1) != 0. {
-                            self.friends_w[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_ns(t1, t2) != 0. {
-                            self.friends_s[t2 as usize].insert(t1);
-                        }
-                        if self.get_energy_we(t1, t2) != 0. {
-                            self.friends_e[t2 as usize].insert(t1);
+                            friends_ss.insert(t2, t1);
                         }
                     }
-                    TileShape::DupleToLeft(_) => (),
-                    TileShape::DupleToTop(_) => (),
-                };
+                }
+            };
+            for t2 in candidates(t1, 0, 2) {
+                if self.get_energy_ns(t2, t1) != 0. {
+                    friends_n.insert(t2, t1);
+                }
+            }
+            for t2 in candidates(t1, 3, 1) {
+                if self.get_energy_we(t2, t1) != 0. {
+                    friends_w.insert(t2, t1);
+                }
+            }
+            for t2 in candidates(t1, 2, 0) {
+                if self.get_energy_ns(t1, t2) != 0. {
+                    friends_s.insert(t2, t1);
+                }
+            }
+            for t2 in candidates(t1, 1, 3) {
+                if self.get_energy_we(t1, t2) != 0. {
+                    friends_e.insert(t2, t1);
+                }
             }
         }
+
+        self.friends_n = friends_n;
+        self.friends_e = friends_e;
+        self.friends_s = friends_s;
+        self.friends_w = friends_w;
+        self.friends_ne = friends_ne;
+        self.friends_ee = friends_ee;
+        self.friends_se = friends_se;
+        self.friends_ss = friends_ss;
+        self.friends_sw = friends_sw;
     }
 
     pub fn is_seed(&self, p: PointSafe2) -> bool {
@@ -1201,37 +1330,37 @@ impl KTAM {
             }
         }
 
-        let mut friends = HashSetType::<Tile>::default();
+        let mut friends = FriendUnion::new();
 
         if tn.nonzero() {
-            friends.extend(&self.friends_n[tn as usize]);
+            friends.add(&self.friends_n, tn);
         }
         if te.nonzero() {
-            friends.extend(&self.friends_e[te as usize]);
+            friends.add(&self.friends_e, te);
         }
         if ts.nonzero() {
-            friends.extend(&self.friends_s[ts as usize]);
+            friends.add(&self.friends_s, ts);
         }
         if tw.nonzero() {
-            friends.extend(&self.friends_w[tw as usize]);
+            friends.add(&self.friends_w, tw);
         }
 
         if self.has_duples {
             if tss.nonzero() {
-                friends.extend(&self.friends_ss[tss as usize])
+                friends.add(&self.friends_ss, tss)
             }
             if tne.nonzero() {
-                friends.extend(&self.friends_ne[tne as usize])
+                friends.add(&self.friends_ne, tne)
             }
             if tee.nonzero() {
-                friends.extend(&self.friends_ee[tee as usize])
+                friends.add(&self.friends_ee, tee)
             }
             if tse.nonzero() {
-                friends.extend(&self.friends_se[tse as usize])
+                friends.add(&self.friends_se, tse)
             }
         }
 
-        for t in friends.drain() {
+        for t in friends {
             // FIXME: this is likely rather slow, but it's better than giving very confusing rates (many
             // possible double-tile attachements at a point that aren't actually possible, because they are
             // blocked).
@@ -1316,16 +1445,26 @@ impl KTAM {
 
     #[inline(always)]
     pub(crate) fn get_energy_ns(&self, tn: Tile, ts: Tile) -> Energy {
-        {
-            *self.energy_ns.get((tn as usize, ts as usize)).unwrap()
+        if self.has_duples && (ts != 0) && (self.double_to_bottom[tn as usize] == ts) {
+            return 0.;
         }
+        self.g_se
+            * self.glue_bonds.get(
+                self.tile_edges[(tn as usize, 2)],
+                self.tile_edges[(ts as usize, 0)],
+            )
     }
 
     #[inline(always)]
     pub(crate) fn get_energy_we(&self, tw: Tile, te: Tile) -> Energy {
-        {
-            *self.energy_we.get((tw as usize, te as usize)).unwrap()
+        if self.has_duples && (te != 0) && (self.double_to_right[tw as usize] == te) {
+            return 0.;
         }
+        self.g_se
+            * self.glue_bonds.get(
+                self.tile_edges[(tw as usize, 1)],
+                self.tile_edges[(te as usize, 3)],
+            )
     }
 
     #[inline(always)]
@@ -1548,4 +1687,58 @@ mod tests {
             .context("CanvasTube")?;
         Ok(())
     }
+
+    fn assert_energies_dense(system: &KTAM) {
+        let ntiles = system.tile_concs.len();
+        let e = &system.tile_edges;
+        let energy = |g1: Glue, g2: Glue| {
+            system.g_se
+                * if g1 == g2 {
+                    system.glue_strengths[g1]
+                } else {
+                    system.glue_links[(g1, g2)]
+                }
+        };
+        for t1 in 0..ntiles {
+            for t2 in 1..ntiles {
+                let (t1t, t2t) = (t1 as Tile, t2 as Tile);
+                let ns = if system.double_to_bottom[t1] == t2t {
+                    0.
+                } else {
+                    energy(e[(t1, 2)], e[(t2, 0)])
+                };
+                let we = if system.double_to_right[t1] == t2t {
+                    0.
+                } else {
+                    energy(e[(t1, 1)], e[(t2, 3)])
+                };
+                assert_eq!(system.get_energy_ns(t1t, t2t), ns);
+                assert_eq!(system.get_energy_we(t1t, t2t), we);
+            }
+        }
+    }
+
+    #[test]
+    fn test_glue_keyed_energies() -> Result<(), anyhow::Error> {
+        let ts = TileSet::from_file("examples/barish-perfect.yaml")?;
+        let mut system = KTAM::from_tileset(&ts)?;
+        assert_energies_dense(&system);
+
+        let mut g = system.glue_strengths.clone();
+        g[1] = 0.;
+        g[2] *= 1.5;
+        system.set_param("g_se", Box::new(7.5))?;
+        system.set_param("glue_strengths", Box::new(g))?;
+        assert_energies_dense(&system);
+
+        // Incremental updates should give the same friends as a full recalculation.
+        let mut full = system.clone();
+        full.update_system();
+        assert_eq!(system.friends_n, full.friends_n);
+        assert_eq!(system.friends_e, full.friends_e);
+        assert_eq!(system.friends_s, full.friends_s);
+        assert_eq!(system.friends_w, full.friends_w);
+
+        Ok(())
+    }
 }
@@ -305,9 +305,7 @@ impl KTAM {
             let ps = canvas.move_sa_s(p);
             let ts = canvas.v_sh(ps);
 
-            if (unsafe { *self.energy_ns.uget((tn as usize, t as usize)) } != 0.)
-                || (self.double_to_bottom[tn as usize] > 0)
-            {
+            if (self.get_energy_ns(tn, t) != 0.) || (self.double_to_bottom[tn as usize] > 0) {
                 let pn = PointSafe2(pn.0); // FIXME
                 match groupinfo.merge_or_add(&p, &pn) {
                     true => {}
@@ -317,9 +315,7 @@ impl KTAM {
                 }
             }
 
-            if (unsafe { *self.energy_we.uget((t as usize, te as usize)) } != 0.)
-                || (self.double_to_right[t as usize] > 0)
-            {
+            if (self.get_energy_we(t, te) != 0.) || (self.double_to_right[t as usize] > 0) {
                 let pe = PointSafe2(pe.0); // FIXME
                 match groupinfo.merge_or_add(&p, &pe) {
                     true => {}
@@ -329,9 +325,7 @@ impl KTAM {
                 }
             }
 
-            if (unsafe { *self.energy_ns.uget((t as usize, ts as usize)) } != 0.)
-                || (self.double_to_bottom[t as usize] > 0)
-            {
+            if (self.get_energy_ns(t, ts) != 0.) || (self.double_to_bottom[t as usize] > 0) {
                 let ps = PointSafe2(ps.0); // FIXME
                 match groupinfo.merge_or_add(&p, &ps) {
                     true => {}
@@ -341,9 +335,7 @@ impl KTAM {
                 }
             }
 
-            if (unsafe { *self.energy_we.uget((tw as usize, t as usize)) } != 0.)
-                || (self.double_to_right[tw as usize] > 0)
-            {
+            if (self.get_energy_we(tw, t) != 0.) || (self.double_to_right[tw as usize] > 0) {
                 let pw = PointSafe2(pw.0); // FIXME
                 match groupinfo.merge_or_add(&p, &pw) {
                     true => {}
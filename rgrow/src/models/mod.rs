@@ -1,4 +1,6 @@
 pub mod atam;
+pub mod friends;
+pub(crate) mod nmers;
 
 pub mod ktam;
 pub mod ktam_fission;
@@ -0,0 +1,216 @@
+//! Exhaustive enumeration of small assemblies ("n-mers"), shared by the kTAM implementations.
+//!
+//! Assemblies are grown one unit (a tile, or a duple with its accessory part) at a time from
+//! monomers, keeping those held together by bonds, and deduplicated up to translation.  A duple
+//! counts as one tile.  As the kTAM satisfies detailed balance, each assembly's equilibrium
+//! concentration, relative to free monomers, is the product of their activities and
+//! exp(bond energy); its formation rate is the equilibrium flux of monomer attachments to
+//! smaller assemblies (of one fewer tile) that make it.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::base::{NumTiles, Rate, Tile};
+use crate::system::NmerInfo;
+
+type Cell = (i32, i32);
+
+/// What the enumeration needs to know about a system.
+pub(crate) struct NmerModel<'a> {
+    /// For each tile, its activity as a monomer (its concentration, in M, corrected for the
+    /// model's detachment rate prefactor), or 0 if it cannot attach on its own.
+    pub monomer_activity: Vec<f64>,
+    /// For each tile, its rate of attachment to a site where it binds.
+    pub attachment_rate: Vec<Rate>,
+    /// For each tile that is the main part of a duple, the offset and number of its accessory
+    /// part.
+    pub duple_part: Vec<Option<(Cell, Tile)>>,
+    pub energy_ns: &'a dyn Fn(Tile, Tile) -> f64,
+    pub energy_we: &'a dyn Fn(Tile, Tile) -> f64,
+}
+
+/// An assembly, as its cells and the units (main tiles) that occupy them.
+#[derive(Clone)]
+struct Assembly {
+    cells: BTreeMap<Cell, Tile>,
+    units: Vec<(Cell, Tile)>,
+    energy: f64,
+}
+
+impl NmerModel<'_> {
+    /// The cells occupied by tile `t` placed at `at`.
+    fn unit_cells(&self, at: Cell, t: Tile) -> Vec<(Cell, Tile)> {
+        let mut cells = vec![(at, t)];
+        if let Some(((dr, dc), part)) = self.duple_part[t as usize] {
+            cells.push(((at.0 + dr, at.1 + dc), part));
+        }
+        cells
+    }
+
+    /// The total bond energy between `cells` and the other cells of `assembly`.
+    fn contact_energy(&self, cells: &[(Cell, Tile)], assembly: &BTreeMap<Cell, Tile>) -> f64 {
+        let mut energy = 0.;
+        for &((r, c), t) in cells {
+            let neighbor = |cell: Cell| {
+                if cells.iter().any(|(p, _)| *p == cell) {
+                    None
+                } else {
+                    assembly.get(&cell).copied()
+                }
+            };
+            if let Some(n) = neighbor((r - 1, c)) {
+                energy += (self.energy_ns)(n, t);
+            }
+            if let Some(s) = neighbor((r + 1, c)) {
+                energy += (self.energy_ns)(t, s);
+            }
+            if let Some(w) = neighbor((r, c - 1)) {
+                energy += (self.energy_we)(w, t);
+            }
+            if let Some(e) = neighbor((r, c + 1)) {
+                energy += (self.energy_we)(t, e);
+            }
+        }
+        energy
+    }
+
+    fn activity(&self, units: &[(Cell, Tile)], energy: f64) -> f64 {
+        units
+            .iter()
+            .map(|&(_, t)| self.monomer_activity[t as usize])
+            .product::<f64>()
+            * energy.exp()
+    }
+
+    /// Whether the units of `assembly`, other than unit `skip`, are held together by bonds.
+    fn connected_without(&self, assembly: &Assembly, skip: usize) -> bool {
+        let units: Vec<_> = (0..assembly.units.len()).filter(|&i| i != skip).collect();
+        let cells: Vec<Vec<(Cell, Tile)>> = units
+            .iter()
+            .map(|&i| self.unit_cells(assembly.units[i].0, assembly.units[i].1))
+            .collect();
+        let bonded = |a: usize, b: usize| {
+            let other: BTreeMap<Cell, Tile> = cells[b].iter().copied().collect();
+            self.contact_energy(&cells[a], &other) > 0.
+        };
+
+        let mut seen = vec![false; units.len()];
+        let mut stack = vec![0];
+        seen[0] = true;
+        while let Some(a) = stack.pop() {
+            for (b, s) in seen.iter_mut().enumerate() {
+                if !*s && bonded(a, b) {
+                    *s = true;
+                    stack.push(b);
+                }
+            }
+        }
+        seen.into_iter().all(|s| s)
+    }
+
+    /// All assemblies of 2 to `max_size` tiles.
+    pub fn enumerate(&self, max_size: NumTiles) -> Vec<NmerInfo> {
+        let tiles: Vec<Tile> = (0..self.monomer_activity.len())
+            .filter(|&t| self.monomer_activity[t] > 0.)
+            .map(|t| t as Tile)
+            .collect();
+
+        let mut current: BTreeMap<Vec<(Cell, Tile)>, Assembly> = tiles
+            .iter()
+            .map(|&t| {
+                let cells: BTreeMap<_, _> = self.unit_cells((0, 0), t).into_iter().collect();
+                (
+                    cells.iter().map(|(&p, &t)| (p, t)).collect(),
+                    Assembly {
+                        cells,
+                        units: vec![((0, 0), t)],
+                        energy: 0.,
+                    },
+                )
+            })
+            .collect();
+
+        let mut nmers = Vec::new();
+        for _ in 2..=max_size {
+            let mut next = BTreeMap::new();
+            for assembly in current.values() {
+                let sites: BTreeSet<Cell> = assembly
+                    .cells
+                    .keys()
+                    .flat_map(|&(r, c)| [(r - 1, c), (r + 1, c), (r, c - 1), (r, c + 1)])
+                    .filter(|p| !assembly.cells.contains_key(p))
+                    .collect();
+                for &site in &sites {
+                    for &t in &tiles {
+                        // A duple can touch the assembly with either of its parts.
+                        let mut anchors = vec![site];
+                        if let Some(((dr, dc), _)) = self.duple_part[t as usize] {
+                            anchors.push((site.0 - dr, site.1 - dc));
+                        }
+                        for at in anchors {
+                            let cells = self.unit_cells(at, t);
+                            if cells.iter().any(|(p, _)| assembly.cells.contains_key(p)) {
+                                continue;
+                            }
+                            let contact = self.contact_energy(&cells, &assembly.cells);
+                            if contact <= 0. {
+                                continue;
+                            }
+                            let mut grown = assembly.clone();
+                            grown.cells.extend(cells);
+                            grown.units.push((at, t));
+                            grown.energy += contact;
+                            let key = canonical(&grown.cells);
+                            next.entry(key).or_insert(grown);
+                        }
+                    }
+                }
+            }
+
+            for (key, assembly) in &next {
+                nmers.push(self.info(key, assembly));
+            }
+            current = next;
+        }
+        nmers
+    }
+
+    fn info(&self, key: &[(Cell, Tile)], assembly: &Assembly) -> NmerInfo {
+        let formation_rate = (0..assembly.units.len())
+            .filter(|&i| self.connected_without(assembly, i))
+            .map(|i| {
+                let (at, t) = assembly.units[i];
+                let cells = self.unit_cells(at, t);
+                let rest: Vec<_> = assembly
+                    .units
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, &u)| u)
+                    .collect();
+                let energy = assembly.energy - self.contact_energy(&cells, &assembly.cells);
+                self.activity(&rest, energy) * self.attachment_rate[t as usize]
+            })
+            .sum();
+
+        NmerInfo {
+            tiles: key
+                .iter()
+                .map(|&((r, c), t)| (r as usize, c as usize, t))
+                .collect(),
+            size: assembly.units.len() as NumTiles,
+            bond_energy: assembly.energy,
+            formation_rate,
+            equilibrium_conc: self.activity(&assembly.units, assembly.energy),
+        }
+    }
+}
+
+/// The cells of an assembly, translated so that its bounding box starts at (0, 0), in order.
+fn canonical(cells: &BTreeMap<Cell, Tile>) -> Vec<(Cell, Tile)> {
+    let r0 = cells.keys().map(|p| p.0).min().unwrap_or(0);
+    let c0 = cells.keys().map(|p| p.1).min().unwrap_or(0);
+    cells
+        .iter()
+        .map(|(&(r, c), &t)| ((r - r0, c - c0), t))
+        .collect()
+}
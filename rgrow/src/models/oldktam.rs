@@ -11,16 +11,18 @@ use rand::prelude::Distribution;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    base::{Energy, Glue, ModelError, Point, Rate, RgrowError, Tile},
+    base::{Energy, Glue, ModelError, NumTiles, Point, Rate, RgrowError, Tile},
     canvas::{PointSafe2, PointSafeHere},
     state::State,
     system::{
-        ChunkHandling, ChunkSize, DimerInfo, Event, FissionHandling, Orientation, System,
+        ChunkHandling, ChunkSize, DimerInfo, Event, FissionHandling, NmerInfo, Orientation, System,
         SystemInfo, SystemWithDimers, TileBondInfo,
     },
     tileset::{FromTileSet, ProcessedTileSet, TileSet},
 };
 
+use super::nmers::NmerModel;
+
 type Cache = SizedCache<(Tile, Tile, Tile, Tile), f64>;
 
 #[derive(Debug)]
@@ -799,34 +801,39 @@ impl System for OldKTAM {
     }
 
     fn calc_mismatch_locations<S: State + ?Sized>(&self, state: &S) -> Array2<usize> {
-        let threshold = 0.1;
         let mut arr = Array2::zeros(state.raw_array().raw_dim());
 
-        for y in 1..(arr.nrows() - 1) {
-            for x in 1..(arr.ncols() - 1) {
-                let p = PointSafe2((y, x));
-                let t = state.tile_at_point(p);
-
-                if t == 0 {
-                    arr[(y, x)] = 0;
+        for y in 0..arr.nrows() {
+            for x in 0..arr.ncols() {
+                if !state.inbounds((y, x)) {
                     continue;
                 }
+                arr[(y, x)] = self.mismatch_code_at_point(state, PointSafe2((y, x)));
+            }
+        }
 
-                let tn = state.tile_to_n(p);
-                let te = state.tile_to_e(p);
-                let ts = state.tile_to_s(p);
-                let tw = state.tile_to_w(p);
+        arr
+    }
 
-                let mm_n = ((tn != 0) & (self.get_energy_ns(tn, t) < threshold)) as usize;
-                let mm_e = ((te != 0) & (self.get_energy_we(t, te) < threshold)) as usize;
-                let mm_s = ((ts != 0) & (self.get_energy_ns(t, ts) < threshold)) as usize;
-                let mm_w = ((tw != 0) & (self.get_energy_we(tw, t) < threshold)) as usize;
+    fn mismatch_code_at_point<S: State + ?Sized>(&self, state: &S, p: PointSafe2) -> usize {
+        let threshold = 0.1;
+        let t = state.tile_at_point(p);
 
-                arr[(y, x)] = 8 * mm_n + 4 * mm_e + 2 * mm_s + mm_w;
-            }
+        if t == 0 {
+            return 0;
         }
 
-        arr
+        let tn = state.tile_to_n(p);
+        let te = state.tile_to_e(p);
+        let ts = state.tile_to_s(p);
+        let tw = state.tile_to_w(p);
+
+        let mm_n = ((tn != 0) & (self.get_energy_ns(tn, t) < threshold)) as usize;
+        let mm_e = ((te != 0) & (self.get_energy_we(t, te) < threshold)) as usize;
+        let mm_s = ((ts != 0) & (self.get_energy_ns(t, ts) < threshold)) as usize;
+        let mm_w = ((tw != 0) & (self.get_energy_we(tw, t) < threshold)) as usize;
+
+        8 * mm_n + 4 * mm_e + 2 * mm_s + mm_w
     }
 
     fn system_info(&self) -> String {
@@ -874,6 +881,35 @@ impl SystemWithDimers for OldKTAM {
 
         dvec
     }
+
+    fn calc_nmers(&self, max_size: NumTiles) -> Vec<NmerInfo> {
+        // Attachment and detachment rates share the k_f exp(alpha) prefactor, with u0 = 1 M.
+        let model = NmerModel {
+            monomer_activity: self
+                .tile_adj_concs
+                .iter()
+                .enumerate()
+                .map(|(t, &c)| if t == 0 { 0. } else { c })
+                .collect(),
+            attachment_rate: self
+                .tile_adj_concs
+                .iter()
+                .map(|&c| self.k_f_hat() * c)
+                .collect(),
+            duple_part: vec![None; self.tile_adj_concs.len()],
+            energy_ns: &|tn, ts| self.get_energy_ns(tn, ts),
+            energy_we: &|tw, te| self.get_energy_we(tw, te),
+        };
+        model.enumerate(max_size)
+    }
+
+    fn bond_energy_ns(&self, tn: Tile, ts: Tile) -> f64 {
+        self.get_energy_ns(tn, ts)
+    }
+
+    fn bond_energy_we(&self, tw: Tile, te: Tile) -> f64 {
+        self.get_energy_we(tw, te)
+    }
 }
 
 impl FromTileSet for OldKTAM {
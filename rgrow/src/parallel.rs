@@ -0,0 +1,632 @@
+//! Parallel evolution of a single state, by splitting its canvas into domains.
+//!
+//! This uses the synchronous sublattice method (Shim and Amar, Phys. Rev. B 71, 125432 (2005)).
+//! The canvas is divided into rectangular domains, and each domain into up to four regions of
+//! different colors, such that regions of the same color are never close to each other.  In
+//! each cycle, for each color in turn (in random order), every region of that color is evolved
+//! in parallel, with exact kinetic Monte Carlo restricted to events in the region, for the
+//! cycle's time step τ.  Events in a region can only affect tiles and rates near it, so regions
+//! evolved at the same time see each other as fixed, and their results are merged exactly.
+//!
+//! The approximation is a splitting of the dynamics: in a cycle, events near the border of a
+//! region see the neighboring regions as they were at the end of their own last phase, rather
+//! than continuously changing.  The error thus goes to zero with τ, which is chosen so that an
+//! average region has a certain number of events (`parallel_phase_events` in
+//! [EvolveBounds]) per phase.
+//!
+//! Events that would change tiles more than two sites outside the region they occur in are
+//! rejected, and treated as null events.  This is not an approximation that goes away with τ:
+//! such events never happen in parallel evolution.  In particular, with fission handling, an
+//! assembly spanning several regions cannot lose a fragment that reaches outside the region of
+//! the detaching tile, and polymer attachments (eg, dimers) and chunk detachments are suppressed
+//! where they would cross a region's border by more than two sites.  Assemblies will thus tend
+//! to be larger than in serial evolution when fission or large polymer events matter; domains
+//! should then be large compared to the features involved, or evolution should be serial.
+//!
+//! Domains must be at least [MIN_DOMAIN_SIZE] on a side, and the canvas must be a square or
+//! periodic canvas, where moves on the lattice are moves in the canvas array.
+
+use std::fmt::Debug;
+use std::ops::Range;
+
+use ndarray::prelude::*;
+use rand::prelude::*;
+use rayon::prelude::*;
+
+use crate::base::{GrowError, NumEvents, NumTiles, Point, Rate, SimRng, Tile};
+use crate::canvas::{Canvas, PointSafe2, PointSafeHere};
+use crate::ratestore::{CreateSizedRateStore, QuadTreeRectArray, RateStore};
+use crate::state::{EventStats, State, StateStatus};
+use crate::system::{Event, EvolveBounds, EvolveOutcome, System};
+
+/// How far from its region a domain may change tiles (eg, for duples).
+const WRITE_MARGIN: usize = 2;
+
+/// The minimum size of each region along each split dimension.  An event changes tiles up to
+/// [WRITE_MARGIN] from its region, changing rates up to two further away, which depend on
+/// tiles up to two further still; this must not reach another region's changes.
+const MIN_SEPARATION: usize = 2 * WRITE_MARGIN + 4;
+
+/// The minimum side length of a domain.
+pub const MIN_DOMAIN_SIZE: usize = 2 * MIN_SEPARATION;
+
+/// The default for `parallel_phase_events` in [EvolveBounds].
+pub const DEFAULT_PHASE_EVENTS: f64 = 2.;
+
+/// Splits `len` into bands of at least `size`, each split in half (with halves labeled 0 and
+/// 1), unless there is only one band.
+fn split_dimension(len: usize, size: usize) -> (Vec<Range<usize>>, Vec<usize>) {
+    let nbands = (len / size).max(1);
+    let mut ranges = Vec::with_capacity(2 * nbands);
+    let mut parities = Vec::with_capacity(2 * nbands);
+    for i in 0..nbands {
+        let (start, end) = (i * len / nbands, (i + 1) * len / nbands);
+        if nbands == 1 {
+            ranges.push(start..end);
+            parities.push(0);
+        } else {
+            let mid = (start + end) / 2;
+            ranges.extend([start..mid, mid..end]);
+            parities.extend([0, 1]);
+        }
+    }
+    (ranges, parities)
+}
+
+/// Finds the range containing `x`.
+fn range_index(ranges: &[Range<usize>], x: usize) -> usize {
+    ranges.partition_point(|r| r.end <= x)
+}
+
+/// A region of the canvas, with a copy of the tiles around it and the rates of events in it.
+#[derive(Debug)]
+struct Domain {
+    rows: Range<usize>,
+    cols: Range<usize>,
+    color: usize,
+    /// Corner of the window of tiles, which extends [WRITE_MARGIN] beyond the region (wrapping
+    /// around the canvas).
+    window_origin: Point,
+    window: Array2<Tile>,
+    rates: QuadTreeRectArray<Rate>,
+}
+
+impl Domain {
+    fn new<St: State + ?Sized>(
+        state: &St,
+        rows: Range<usize>,
+        cols: Range<usize>,
+        color: usize,
+    ) -> Self {
+        let (nrows, ncols) = (state.nrows(), state.ncols());
+        let window_origin = (
+            (rows.start + nrows - WRITE_MARGIN) % nrows,
+            (cols.start + ncols - WRITE_MARGIN) % ncols,
+        );
+        let shape = (
+            (rows.len() + 2 * WRITE_MARGIN).min(nrows),
+            (cols.len() + 2 * WRITE_MARGIN).min(ncols),
+        );
+        let window = Array2::from_shape_fn(shape, |(r, c)| unsafe {
+            state.uv_p(((window_origin.0 + r) % nrows, (window_origin.1 + c) % ncols))
+        });
+
+        let mut rates = QuadTreeRectArray::<Rate>::new_with_size(rows.len(), cols.len());
+        let initial = rows
+            .clone()
+            .flat_map(|r| cols.clone().map(move |c| (r, c)))
+            .filter_map(|p| {
+                let rate = state.rate_at_point(PointSafeHere(p));
+                (rate > 0.).then_some((PointSafeHere((p.0 - rows.start, p.1 - cols.start)), rate))
+            })
+            .collect::<Vec<_>>();
+        rates.update_multiple(&initial);
+
+        Self {
+            rows,
+            cols,
+            color,
+            window_origin,
+            window,
+            rates,
+        }
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        self.rows.contains(&p.0) && self.cols.contains(&p.1)
+    }
+
+    fn local(&self, p: Point) -> Point {
+        (p.0 - self.rows.start, p.1 - self.cols.start)
+    }
+
+    fn window_index(&self, p: Point, shape: (usize, usize)) -> Option<Point> {
+        let r = (p.0 + shape.0 - self.window_origin.0) % shape.0;
+        let c = (p.1 + shape.1 - self.window_origin.1) % shape.1;
+        (r < self.window.nrows() && c < self.window.ncols()).then_some((r, c))
+    }
+}
+
+/// The domains of a canvas, and how to find them.
+struct Domains {
+    row_ranges: Vec<Range<usize>>,
+    col_ranges: Vec<Range<usize>>,
+    domains: Vec<Domain>,
+    ncolors: usize,
+}
+
+impl Domains {
+    fn new<St: State + ?Sized>(state: &St, size: usize) -> Self {
+        let size = size.max(MIN_DOMAIN_SIZE);
+        let (row_ranges, row_parities) = split_dimension(state.nrows(), size);
+        let (col_ranges, col_parities) = split_dimension(state.ncols(), size);
+        let col_colors = col_parities.iter().max().unwrap() + 1;
+        let ncolors = (row_parities.iter().max().unwrap() + 1) * col_colors;
+
+        let domains = row_ranges
+            .iter()
+            .zip(&row_parities)
+            .flat_map(|(rows, rp)| {
+                col_ranges.iter().zip(&col_parities).map(move |(cols, cp)| {
+                    Domain::new(state, rows.clone(), cols.clone(), rp * col_colors + cp)
+                })
+            })
+            .collect();
+
+        Self {
+            row_ranges,
+            col_ranges,
+            domains,
+            ncolors,
+        }
+    }
+
+    /// Index of the domain whose region contains `p`.
+    fn owner(&self, p: Point) -> usize {
+        range_index(&self.row_ranges, p.0) * self.col_ranges.len()
+            + range_index(&self.col_ranges, p.1)
+    }
+
+    /// Indices of domains whose windows might contain `p`.
+    fn window_candidates(&self, p: Point) -> impl Iterator<Item = usize> + '_ {
+        let (nr, nc) = (self.row_ranges.len(), self.col_ranges.len());
+        let (ri, ci) = (
+            range_index(&self.row_ranges, p.0),
+            range_index(&self.col_ranges, p.1),
+        );
+        let mut rows = vec![(ri + nr - 1) % nr, ri, (ri + 1) % nr];
+        let mut cols = vec![(ci + nc - 1) % nc, ci, (ci + 1) % nc];
+        rows.sort_unstable();
+        rows.dedup();
+        cols.sort_unstable();
+        cols.dedup();
+        rows.into_iter()
+            .flat_map(move |r| cols.clone().into_iter().map(move |c| r * nc + c))
+    }
+}
+
+/// What a domain did during a phase.
+struct PhaseResult {
+    events: Vec<Event>,
+    /// Null and rejected events.
+    dead: NumEvents,
+    written: Vec<Point>,
+    rates: Vec<(PointSafeHere, Rate)>,
+}
+
+/// A view of a state for evolving one domain.  Tiles near the domain are read from and
+/// written to the domain's window; others are read from the state, which doesn't change during
+/// a phase.  Only events in the domain's region are in its rate store, and all rate updates are
+/// also recorded, to be applied to the state.
+///
+/// Whole-canvas queries (eg, [Canvas::raw_array]) see the state as of the start of the phase.
+pub(crate) struct DomainState<'a, St: State + ?Sized> {
+    base: &'a St,
+    domain: &'a mut Domain,
+    ntiles: NumTiles,
+    events: NumEvents,
+    time: f64,
+    written: Vec<Point>,
+    rates: Vec<(PointSafeHere, Rate)>,
+}
+
+impl<'a, St: State + ?Sized> Debug for DomainState<'a, St> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DomainState")
+            .field("rows", &self.domain.rows)
+            .field("cols", &self.domain.cols)
+            .field("events", &self.events)
+            .field("time", &self.time)
+            .finish()
+    }
+}
+
+impl<'a, St: State + ?Sized> DomainState<'a, St> {
+    fn new(base: &'a St, domain: &'a mut Domain) -> Self {
+        Self {
+            base,
+            domain,
+            ntiles: base.n_tiles(),
+            events: 0,
+            time: 0.,
+            written: Vec::new(),
+            rates: Vec::new(),
+        }
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        (self.base.nrows(), self.base.ncols())
+    }
+
+    /// Evolves the domain's region for `time`.
+    fn evolve<Sy: System + ?Sized>(
+        mut self,
+        system: &Sy,
+        time: f64,
+        rng: &mut SimRng,
+    ) -> Result<PhaseResult, GrowError> {
+        let mut events = Vec::new();
+        let mut dead = 0;
+        loop {
+            let total_rate = self.domain.rates.total_rate();
+            if total_rate <= 0. {
+                break;
+            }
+            self.time += -f64::ln(rng.gen()) / total_rate;
+            if self.time > time {
+                break;
+            }
+            let (point, remainder) = self.choose_point(rng)?;
+            let event = system.choose_event_at_point(&self, PointSafe2(point), remainder);
+            let shape = self.shape();
+            let writable = |p: Point| self.domain.window_index(p, shape).is_some();
+            let local = match &event {
+                Event::None => {
+                    dead += 1;
+                    continue;
+                }
+                Event::MonomerAttachment(p, _)
+                | Event::MonomerDetachment(p)
+                | Event::MonomerChange(p, _) => writable(p.0),
+                Event::PolymerAttachment(changelist) | Event::PolymerChange(changelist) => {
+                    changelist.iter().all(|(p, _)| writable(p.0))
+                }
+                Event::PolymerDetachment(changelist) => changelist.iter().all(|p| writable(p.0)),
+            };
+            if !local {
+                dead += 1;
+                continue;
+            }
+            system.perform_event(&mut self, &event);
+            system.update_after_event(&mut self, &event);
+            events.push(event);
+        }
+
+        let mut written = self.written;
+        written.sort_unstable();
+        written.dedup();
+
+        // Keep only the last rate recorded for each point.
+        let mut rates = self.rates;
+        rates.reverse();
+        rates.sort_by_key(|(p, _)| p.0);
+        rates.dedup_by_key(|(p, _)| p.0);
+
+        Ok(PhaseResult {
+            events,
+            dead,
+            written,
+            rates,
+        })
+    }
+}
+
+impl<'a, St: State + ?Sized> Canvas for DomainState<'a, St> {
+    unsafe fn uv_pr(&self, p: Point) -> &Tile {
+        match self.domain.window_index(p, self.shape()) {
+            Some(w) => self.domain.window.uget(w),
+            None => self.base.uv_pr(p),
+        }
+    }
+
+    unsafe fn uvm_p(&mut self, p: Point) -> &mut Tile {
+        let w = self
+            .domain
+            .window_index(p, self.shape())
+            .expect("Tile changed outside of domain window");
+        self.written.push(p);
+        self.domain.window.uget_mut(w)
+    }
+
+    fn u_move_point_n(&self, p: Point) -> Point {
+        self.base.u_move_point_n(p)
+    }
+
+    fn u_move_point_e(&self, p: Point) -> Point {
+        self.base.u_move_point_e(p)
+    }
+
+    fn u_move_point_s(&self, p: Point) -> Point {
+        self.base.u_move_point_s(p)
+    }
+
+    fn u_move_point_w(&self, p: Point) -> Point {
+        self.base.u_move_point_w(p)
+    }
+
+    fn inbounds(&self, p: Point) -> bool {
+        self.base.inbounds(p)
+    }
+
+    fn calc_n_tiles(&self) -> NumTiles {
+        self.base.calc_n_tiles()
+    }
+
+    fn calc_n_tiles_with_tilearray(&self, should_be_counted: &Array1<bool>) -> NumTiles {
+        self.base.calc_n_tiles_with_tilearray(should_be_counted)
+    }
+
+    fn raw_array(&self) -> ArrayView2<'_, Tile> {
+        self.base.raw_array()
+    }
+
+    fn nrows(&self) -> usize {
+        self.base.nrows()
+    }
+
+    fn ncols(&self) -> usize {
+        self.base.ncols()
+    }
+
+    fn set_sa(&mut self, p: &PointSafe2, t: &Tile) {
+        let r = unsafe { self.uvm_p(p.0) };
+        let old_tile = std::mem::replace(r, *t);
+        if (old_tile == 0) & (*t > 0) {
+            self.ntiles += 1
+        }
+        if (old_tile > 0) & (*t == 0) {
+            self.ntiles -= 1
+        }
+    }
+
+    fn set_sa_countabletilearray(
+        &mut self,
+        p: &PointSafe2,
+        t: &Tile,
+        should_be_counted: &Array1<bool>,
+    ) {
+        let r = unsafe { self.uvm_p(p.0) };
+        let old_tile = std::mem::replace(r, *t);
+        if should_be_counted[old_tile as usize] & !should_be_counted[*t as usize] {
+            self.ntiles -= 1
+        }
+        if !should_be_counted[old_tile as usize] & should_be_counted[*t as usize] {
+            self.ntiles += 1
+        }
+    }
+}
+
+impl<'a, St: State + ?Sized> RateStore for DomainState<'a, St> {
+    fn choose_point(&self, rng: &mut SimRng) -> Result<(Point, Rate), GrowError> {
+        let ((r, c), remainder) = self.domain.rates.choose_point(rng)?;
+        Ok((
+            (r + self.domain.rows.start, c + self.domain.cols.start),
+            remainder,
+        ))
+    }
+
+    fn rate_at_point(&self, point: PointSafeHere) -> Rate {
+        if self.domain.contains(point.0) {
+            self.domain
+                .rates
+                .rate_at_point(PointSafeHere(self.domain.local(point.0)))
+        } else {
+            self.base.rate_at_point(point)
+        }
+    }
+
+    fn update_point(&mut self, point: PointSafeHere, new_rate: Rate) {
+        self.update_multiple(&[(point, new_rate)]);
+    }
+
+    fn update_multiple(&mut self, to_update: &[(PointSafeHere, Rate)]) {
+        let local = to_update
+            .iter()
+            .filter(|(p, _)| self.domain.contains(p.0))
+            .map(|(p, r)| (PointSafeHere(self.domain.local(p.0)), *r))
+            .collect::<Vec<_>>();
+        self.domain.rates.update_multiple(&local);
+        self.rates.extend_from_slice(to_update);
+    }
+
+    fn total_rate(&self) -> Rate {
+        self.domain.rates.total_rate()
+    }
+
+    fn resum(&mut self) {
+        self.domain.rates.resum()
+    }
+
+    fn check_consistency(&self) -> Result<(), GrowError> {
+        self.domain.rates.check_consistency()
+    }
+}
+
+impl<'a, St: State + ?Sized> StateStatus for DomainState<'a, St> {
+    fn n_tiles(&self) -> NumTiles {
+        self.ntiles
+    }
+
+    fn total_events(&self) -> NumEvents {
+        self.base.total_events() + self.events
+    }
+
+    fn add_events(&mut self, n: NumEvents) {
+        self.events += n;
+    }
+
+    fn reset_events(&mut self) {
+        self.events = 0;
+    }
+
+    fn add_time(&mut self, time: f64) {
+        self.time += time;
+    }
+
+    fn time(&self) -> f64 {
+        self.base.time() + self.time
+    }
+
+    /// Mismatches are counted when events are merged into the state, not in the domain.
+    fn mismatch_count(&self) -> Option<usize> {
+        None
+    }
+
+    fn set_mismatch_count(&mut self, _count: Option<usize>) {}
+
+    fn tile_counts(&self) -> Option<ArrayView1<'_, NumTiles>> {
+        None
+    }
+
+    fn track_tile_counts(&mut self, _ntiles: Option<usize>) {}
+
+    /// Events are recorded when they are merged into the state.
+    fn event_stats(&self) -> Option<&EventStats> {
+        None
+    }
+
+    fn event_stats_mut(&mut self) -> Option<&mut EventStats> {
+        None
+    }
+
+    fn track_event_stats(&mut self, _ntiles: Option<usize>) {}
+}
+
+impl<'a, St: State + ?Sized> State for DomainState<'a, St> {
+    fn panicinfo(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Evolves `state` in parallel domains of about `domain_size` on a side; see the module
+/// documentation.  Bounds are checked between cycles, so event and size bounds may be
+/// overshot by up to a cycle.
+pub fn evolve_in_domains<Sy: System + ?Sized, St: State + ?Sized>(
+    system: &Sy,
+    state: &mut St,
+    bounds: EvolveBounds,
+    domain_size: usize,
+    rng: &mut SimRng,
+) -> Result<EvolveOutcome, GrowError> {
+    let bounds = bounds.with_total_events_as_for_events(state.total_events());
+    if bounds.leap_tolerance.is_some() {
+        return Err(GrowError::NotImplemented(
+            "Leaping is not implemented for parallel evolution".to_string(),
+        ));
+    }
+    if state.u_move_point_e((1, 1)) != (1, 2) || state.u_move_point_s((1, 1)) != (2, 1) {
+        return Err(GrowError::NotImplemented(
+            "Parallel evolution needs a square or periodic canvas".to_string(),
+        ));
+    }
+
+    let mut domains = Domains::new(state, domain_size);
+    let phase_events = bounds.parallel_phase_events.unwrap_or(DEFAULT_PHASE_EVENTS);
+    let mut rngs = (0..domains.domains.len())
+        .map(|_| SimRng::from_rng(&mut *rng).unwrap())
+        .collect::<Vec<_>>();
+    let mut colors = (0..domains.ncolors).collect::<Vec<_>>();
+
+    let mut events = 0;
+    let mut rtime = bounds.for_time.unwrap_or(f64::INFINITY);
+    if let Some(t) = bounds.total_time {
+        rtime = rtime.min(t - state.time());
+    }
+    let start_time = bounds.for_wall_time.map(|_| std::time::Instant::now());
+
+    loop {
+        if bounds.size_min.is_some_and(|ms| state.n_tiles() <= ms) {
+            return Ok(EvolveOutcome::ReachedSizeMin);
+        } else if bounds.size_max.is_some_and(|ms| state.n_tiles() >= ms) {
+            return Ok(EvolveOutcome::ReachedSizeMax);
+        } else if rtime <= 0. {
+            return Ok(EvolveOutcome::ReachedTimeMax);
+        } else if bounds
+            .for_wall_time
+            .is_some_and(|t| start_time.unwrap().elapsed() >= t)
+        {
+            return Ok(EvolveOutcome::ReachedWallTimeMax);
+        } else if bounds.for_events.is_some_and(|e| events >= e) {
+            return Ok(EvolveOutcome::ReachedEventsMax);
+        } else if state.total_rate() == 0. {
+            return Ok(EvolveOutcome::ReachedZeroRate);
+        }
+
+        // Choose τ so that an average active domain has about phase_events events per phase.
+        let active = domains
+            .domains
+            .iter()
+            .filter(|d| d.rates.total_rate() > 0.)
+            .count();
+        if active == 0 {
+            // The state's total rate can be left above zero by rounding after all rates have
+            // gone to zero; resum and rebuild the domains rather than looping with τ = 0.
+            state.resum();
+            domains = Domains::new(state, domain_size);
+            if domains.domains.iter().all(|d| d.rates.total_rate() <= 0.) {
+                return Ok(EvolveOutcome::ReachedZeroRate);
+            }
+            continue;
+        }
+        let tau = (phase_events * active as f64 / state.total_rate()).min(rtime);
+
+        colors.shuffle(rng);
+        for &color in &colors {
+            let base = &*state;
+            let results = domains
+                .domains
+                .par_iter_mut()
+                .zip(rngs.par_iter_mut())
+                .filter(|(d, _)| d.color == color && d.rates.total_rate() > 0.)
+                .map(|(d, rng)| DomainState::new(base, d).evolve(system, tau, rng))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Domains affect disjoint parts of the state, so their results can be merged in any
+            // order.
+            let mut written = Vec::new();
+            let mut rates = Vec::new();
+            for result in results {
+                for event in &result.events {
+                    system.record_event_stats(state, event);
+                    system.perform_event_with_counts(state, event);
+                }
+                if let Some(stats) = state.event_stats_mut() {
+                    stats.dead_events += result.dead;
+                }
+                events += result.events.len() as NumEvents;
+                written.extend(result.written);
+                rates.extend(result.rates);
+            }
+            for p in written {
+                let t = unsafe { state.uv_p(p) };
+                let shape = (state.nrows(), state.ncols());
+                for i in domains.window_candidates(p).collect::<Vec<_>>() {
+                    let d = &mut domains.domains[i];
+                    if let Some(w) = d.window_index(p, shape) {
+                        d.window[w] = t;
+                    }
+                }
+            }
+            for &(p, r) in &rates {
+                let owner = domains.owner(p.0);
+                let d = &mut domains.domains[owner];
+                let local = PointSafeHere(d.local(p.0));
+                d.rates.update_point(local, r);
+            }
+            state.update_multiple(&rates);
+        }
+
+        state.add_time(tau);
+        rtime -= tau;
+    }
+}
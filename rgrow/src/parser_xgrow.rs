@@ -3,7 +3,7 @@
 
 use crate::{
     base::Glue,
-    system::FissionHandling,
+    system::{ChunkHandling, ChunkSize, FissionHandling},
     tileset::{CanvasType, Model, Size, TileShape},
 };
 
@@ -15,7 +15,7 @@ use nom::{
     character::complete::multispace1,
     character::complete::{digit1, not_line_ending, space0},
     combinator::all_consuming,
-    combinator::{map, map_res, opt},
+    combinator::{consumed, map, map_res, opt, recognize},
     error::ParseError,
     multi::many1,
     number::complete::recognize_float,
@@ -23,11 +23,34 @@ use nom::{
     IResult,
 };
 
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::prelude::*;
 
 type GlueVec = Vec<(GlueIdent, GlueIdent, f64)>;
 
+/// An Xgrow option that was not applied to the parsed [tileset::TileSet].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoredOption {
+    /// An Xgrow option that rgrow recognizes, but has no equivalent for (eg, `anneal`, `addflakes`).
+    Unsupported(String),
+    /// An option that was not recognized.
+    Unknown(String),
+    /// A recognized option with a value that can't be used (eg, a negative `emax`, or `stoic`
+    /// for a nonexistent tile).
+    Invalid(String),
+}
+
+impl Display for IgnoredOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unsupported(o) => write!(f, "\"{o}\" is not supported by rgrow"),
+            Self::Unknown(o) => write!(f, "\"{o}\" is not a known option"),
+            Self::Invalid(o) => write!(f, "\"{o}\" has an invalid value"),
+        }
+    }
+}
+
 fn std_delim<'a, P, O, E: ParseError<&'a str>>(
     parser: P,
 ) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
@@ -98,7 +121,7 @@ fn tilelist(input: &str) -> IResult<&str, Vec<tileset::Tile>> {
     )(input)
 }
 
-fn parse(input: &str) -> IResult<&str, tileset::TileSet> {
+fn parse(input: &str) -> IResult<&str, (tileset::TileSet, Vec<IgnoredOption>)> {
     // Consume initial comments
     let (input, _) = opt(std_delim(tag("tile edges matches {{N E S W}*}")))(input)?;
 
@@ -119,14 +142,30 @@ fn parse(input: &str) -> IResult<&str, tileset::TileSet> {
         )),
     )))(input)?;
 
-    let (input, tiles) = tilelist(input)?;
+    let (input, mut tiles) = tilelist(input)?;
 
     let (input, bondstrengths) = opt(std_delim(preceded(
         tag("binding strengths="),
         std_delim(delimited(tag("{"), many1(std_delim(string_f64)), tag("}"))),
     )))(input)?;
 
-    let (input, (mut options, glues)) = xgrow_args(input)?;
+    let (
+        input,
+        XgrowOptions {
+            tileset: mut options,
+            gluelinks: glues,
+            stoics,
+            mut ignored,
+        },
+    ) = xgrow_args(input)?;
+
+    // Stoichiometries set as options override those in the tile list.
+    for (t, stoic) in stoics {
+        match tiles.get_mut((t as usize).wrapping_sub(1)) {
+            Some(tile) => tile.stoic = Some(stoic),
+            None => ignored.push(IgnoredOption::Invalid(format!("stoic={t},{stoic}"))),
+        }
+    }
 
     // todo: checks
 
@@ -170,7 +209,7 @@ fn parse(input: &str) -> IResult<&str, tileset::TileSet> {
     options.glues = glues;
     options.cover_strands = None;
 
-    Ok((input, options))
+    Ok((input, (options, ignored)))
 }
 
 enum XgrowArgs<'a> {
@@ -179,7 +218,16 @@ enum XgrowArgs<'a> {
     Gse(f64),
     Gmc(f64),
     UpdateRate(u64),
+    Tmax(f64),
+    Emax(u64),
+    Smax(u32),
+    Smin(u32),
+    Fission(FissionHandling),
+    ChunkFission,
+    Stoic(u32, f64),
+    Unsupported(&'a str),
     Unhandled(&'a str),
+    Invalid(&'a str),
     Seed(tileset::Seed),
     T(f64),
     GlueLink(u32, u32, f64),
@@ -225,6 +273,69 @@ fn arg_update_rate(input: &str) -> IResult<&str, XgrowArgs> {
     })(input)
 }
 
+fn arg_tmax(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    map(preceded(tag("tmax="), string_f64), XgrowArgs::Tmax)(input)
+}
+
+fn arg_emax(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    // Xgrow reads this as a double, so allow 1e7, etc., as long as it is a whole number of events.
+    map(
+        consumed(preceded(tag("emax="), string_f64)),
+        |(option, x)| {
+            if x >= 0. && x.fract() == 0. && x <= u64::MAX as f64 {
+                XgrowArgs::Emax(x as u64)
+            } else {
+                XgrowArgs::Invalid(option)
+            }
+        },
+    )(input)
+}
+
+fn arg_smax(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    map(preceded(tag("smax="), take_u32), XgrowArgs::Smax)(input)
+}
+
+fn arg_smin(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    map(preceded(tag("smin="), take_u32), XgrowArgs::Smin)(input)
+}
+
+fn arg_fission(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    // Xgrow's fission keeps the seeded fragment, as does "on" in rgrow.  These are whole words,
+    // so that eg "fission=1" is not taken as "fission".
+    map_res(is_not(" \t\r\n%"), |word| match word {
+        "no_fission" => Ok(XgrowArgs::Fission(FissionHandling::NoFission)),
+        "chunk_fission" => Ok(XgrowArgs::ChunkFission),
+        "fission" => Ok(XgrowArgs::Fission(FissionHandling::KeepSeeded)),
+        _ => Err(()),
+    })(input)
+}
+
+fn arg_stoic(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    preceded(
+        tag("stoic="),
+        map(tuple((take_u32, tag(","), string_f64)), |(t, _, s)| {
+            XgrowArgs::Stoic(t, s)
+        }),
+    )(input)
+}
+
+/// Xgrow options that we recognize, but that have no rgrow equivalent.
+fn arg_unsupported(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    map(
+        recognize(preceded(
+            alt((
+                tag("anneal"),
+                tag("addflakes"),
+                tag("wander"),
+                tag("importfile"),
+                tag("untiltiles"),
+            )),
+            opt(is_not(" \t\r\n%")),
+        )),
+        XgrowArgs::Unsupported,
+    )(input)
+}
+
 fn arg_seed(input: &str) -> IResult<&str, XgrowArgs> {
     preceded(
         tag("seed="),
@@ -271,10 +382,9 @@ fn unhandled_option(input: &str) -> IResult<&str, XgrowArgs> {
     map(is_not(" \t\r\n%"), XgrowArgs::Unhandled)(input)
 }
 
-fn xgrow_args(input: &str) -> IResult<&str, (tileset::TileSet, GlueVec)> {
-    let mut args = tileset::TileSet::default();
-
-    let parsers = (
+// nom's alt only takes 21 parsers, so options are split into groups.
+fn arg_basic(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    alt((
         arg_block,
         arg_size,
         arg_gmc,
@@ -286,13 +396,39 @@ fn xgrow_args(input: &str) -> IResult<&str, (tileset::TileSet, GlueVec)> {
         arg_periodic,
         arg_hdoubletile,
         arg_vdoubletile,
-        unhandled_option,
-    );
+    ))(input)
+}
+
+fn arg_limits(input: &str) -> IResult<&str, XgrowArgs<'_>> {
+    alt((
+        arg_tmax,
+        arg_emax,
+        arg_smax,
+        arg_smin,
+        arg_fission,
+        arg_stoic,
+        arg_unsupported,
+    ))(input)
+}
+
+struct XgrowOptions {
+    tileset: tileset::TileSet,
+    gluelinks: GlueVec,
+    stoics: Vec<(u32, f64)>,
+    ignored: Vec<IgnoredOption>,
+}
+
+fn xgrow_args(input: &str) -> IResult<&str, XgrowOptions> {
+    let mut args = tileset::TileSet::default();
+
+    let parsers = (arg_basic, arg_limits, unhandled_option);
 
     let mut i2 = input;
     args.size = Some(Size::Single(132));
 
     let mut gluelinks = Vec::new();
+    let mut stoics = Vec::new();
+    let mut ignored = Vec::new();
 
     while let Ok((input, x)) = std_delim(alt(parsers))(i2) {
         match x {
@@ -309,8 +445,35 @@ fn xgrow_args(input: &str) -> IResult<&str, (tileset::TileSet, GlueVec)> {
                 args.gmc = Some(x);
             }
             XgrowArgs::Unhandled(u) => {
-                println!("Warning: \"{u}\" unhandled.");
+                ignored.push(IgnoredOption::Unknown(u.to_string()));
+            }
+            XgrowArgs::Unsupported(u) => {
+                ignored.push(IgnoredOption::Unsupported(u.to_string()));
+            }
+            XgrowArgs::Invalid(u) => {
+                ignored.push(IgnoredOption::Invalid(u.to_string()));
+            }
+            XgrowArgs::Tmax(x) => {
+                args.tmax = Some(x);
+            }
+            XgrowArgs::Emax(x) => {
+                args.emax = Some(x);
+            }
+            XgrowArgs::Smax(x) => {
+                args.smax = Some(x);
+            }
+            XgrowArgs::Smin(x) => {
+                args.smin = Some(x);
             }
+            XgrowArgs::Fission(f) => {
+                args.fission = Some(f);
+            }
+            XgrowArgs::ChunkFission => {
+                args.fission = Some(FissionHandling::KeepSeeded);
+                args.chunk_handling = Some(ChunkHandling::Detach);
+                args.chunk_size = Some(ChunkSize::Dimer);
+            }
+            XgrowArgs::Stoic(t, s) => stoics.push((t, s)),
             XgrowArgs::UpdateRate(x) => {
                 args.update_rate = Some(x);
             }
@@ -361,14 +524,27 @@ fn xgrow_args(input: &str) -> IResult<&str, (tileset::TileSet, GlueVec)> {
         {
             let nx = (x).clamp(2, size - 3);
             let ny = (y).clamp(2, size - 3);
-            println!("Warning: seed position {x}, {y} is out of bounds for rgrow.  Adjusting to {nx}, {ny}.");
+            log::warn!(
+                "Seed position {x}, {y} is out of bounds for rgrow.  Adjusting to {nx}, {ny}."
+            );
             args.seed = Some(tileset::Seed::Single(nx, ny, t.clone()));
         }
     }
 
-    args.fission = Some(FissionHandling::NoFission);
+    // Xgrow does not allow fission unless asked to.
+    if args.fission.is_none() {
+        args.fission = Some(FissionHandling::NoFission);
+    }
 
-    Ok((i2, (args, gluelinks)))
+    Ok((
+        i2,
+        XgrowOptions {
+            tileset: args,
+            gluelinks,
+            stoics,
+            ignored,
+        },
+    ))
 }
 
 pub fn parse_xgrow<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<tileset::TileSet> {
@@ -380,8 +556,88 @@ pub fn parse_xgrow<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<tileset
     parse_xgrow_string(&tilestring)
 }
 
+/// Parses an Xgrow tileset, logging a warning for each option that was ignored.
 pub fn parse_xgrow_string(tilestring: &str) -> anyhow::Result<tileset::TileSet> {
+    let (ts, ignored) = parse_xgrow_string_with_ignored(tilestring)?;
+    for i in ignored {
+        log::warn!("{i}; ignoring it.");
+    }
+    Ok(ts)
+}
+
+/// Parses an Xgrow tileset, returning the options that could not be applied rather than
+/// printing warnings.
+pub fn parse_xgrow_string_with_ignored(
+    tilestring: &str,
+) -> anyhow::Result<(tileset::TileSet, Vec<IgnoredOption>)> {
     parse(tilestring)
         .map_err(|x| x.to_owned().into())
         .map(|x| x.1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xgrow_options() -> anyhow::Result<()> {
+        let s = "tile edges={
+{1 0 0 1}
+{1 0 1 0}[0.5]
+}
+tmax=100.5 emax=1e6 smax=200 smin=2 chunk_fission stoic=1,2.5
+anneal=10,1000 untiltiles=1,2 foo=3
+";
+        let (ts, ignored) = parse_xgrow_string_with_ignored(&format!(
+            "num tile types=2\nnum binding types=1\n{s}"
+        ))?;
+
+        assert_eq!(ts.tmax, Some(100.5));
+        assert_eq!(ts.emax, Some(1_000_000));
+        assert_eq!(ts.smax, Some(200));
+        assert_eq!(ts.smin, Some(2));
+        assert!(matches!(ts.fission, Some(FissionHandling::KeepSeeded)));
+        assert!(matches!(ts.chunk_size, Some(ChunkSize::Dimer)));
+        assert_eq!(ts.tiles[0].stoic, Some(2.5));
+        assert_eq!(ts.tiles[1].stoic, Some(0.5));
+        assert_eq!(
+            ignored,
+            vec![
+                IgnoredOption::Unsupported("anneal=10,1000".to_string()),
+                IgnoredOption::Unsupported("untiltiles=1,2".to_string()),
+                IgnoredOption::Unknown("foo=3".to_string()),
+            ]
+        );
+
+        let bounds = ts.get_bounds();
+        assert_eq!(bounds.total_time, Some(100.5));
+        assert_eq!(bounds.size_min, Some(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xgrow_invalid_options() -> anyhow::Result<()> {
+        let s = "tile edges={
+{1 0 0 1}
+}
+emax=-5 emax=2.5 fission=1 stoic=3,2
+";
+        let (ts, ignored) = parse_xgrow_string_with_ignored(&format!(
+            "num tile types=1\nnum binding types=1\n{s}"
+        ))?;
+
+        assert_eq!(ts.emax, None);
+        assert!(matches!(ts.fission, Some(FissionHandling::NoFission)));
+        assert_eq!(
+            ignored,
+            vec![
+                IgnoredOption::Invalid("emax=-5".to_string()),
+                IgnoredOption::Invalid("emax=2.5".to_string()),
+                IgnoredOption::Unknown("fission=1".to_string()),
+                IgnoredOption::Invalid("stoic=3,2".to_string()),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,553 @@
+//! Proofreading transformations of tile sets, replacing each tile with a block of tiles.
+//!
+//! In uniform k×k proofreading (Winfree & Bekbolatov, 2003), each tile is replaced by a k×k block.
+//! Each external glue of the original tile is split into k glues along the block's edge, while
+//! the block's internal glues are unique to it, so that an error in one position of a block can
+//! only be kept by making further errors.
+//!
+//! Snaked proofreading (Chen & Goel, 2004) additionally sets the strengths of the internal
+//! vertical bonds so that each block can only assemble along a snake-like path, which also
+//! protects against errors from growth on the block's output sides.
+//!
+//! The transformations return a [ProofreadTileSet], which keeps the mapping from new tiles
+//! back to the original tiles, so that assemblies can be analysed in the original tile space.
+
+use ndarray::{Array1, Array2, ArrayView2};
+#[cfg(feature = "python")]
+use numpy::PyArray2;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::base::{Glue, GlueIdent, GrowError, RgrowError, StringConvError, Tile, TileIdent};
+#[cfg(feature = "python")]
+use crate::canvas::Canvas;
+#[cfg(feature = "python")]
+use crate::python::PyState;
+use crate::tileset::{self, Bond, ProcessedTileSet, Seed, Size, TileSet};
+
+/// The type of proofreading block to use.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ProofreadingKind {
+    /// Uniform k×k proofreading.
+    #[default]
+    #[serde(alias = "uniform")]
+    Uniform,
+    /// Snaked k×k proofreading.  This assumes that growth proceeds to the northwest, with
+    /// inputs on the south and east sides of each tile, as in the Sierpinski examples.
+    #[serde(alias = "snaked")]
+    Snaked,
+}
+
+impl TryFrom<&str> for ProofreadingKind {
+    type Error = StringConvError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "snaked" => Ok(Self::Snaked),
+            _ => Err(StringConvError(format!(
+                "Unknown proofreading kind: {}. Valid values are \"uniform\", \"snaked\".",
+                s
+            ))),
+        }
+    }
+}
+
+/// A proofreading version of a tile set, along with the mapping of its tiles back to the
+/// original tile set.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass)]
+pub struct ProofreadTileSet {
+    /// The transformed tile set.
+    pub tileset: TileSet,
+    pub kind: ProofreadingKind,
+    pub block_size: usize,
+    /// For each new tile number, the original tile number (0 for empty).
+    pub original_tiles: Vec<Tile>,
+    /// For each new tile number, its (row, column) position within its block.
+    pub block_positions: Vec<(usize, usize)>,
+    original_edges: Array2<Glue>,
+    original_glue_strengths: Array1<f64>,
+    original_gluelinks: Vec<(Glue, Glue, f64)>,
+}
+
+impl ProofreadTileSet {
+    /// Returns the original tile number of a new tile.
+    pub fn original_tile(&self, tile: Tile) -> Tile {
+        self.original_tiles[tile as usize]
+    }
+
+    /// Maps a canvas of the proofread tile set to a canvas of original tiles, with each
+    /// k×k block of the canvas becoming a single location.  A block is mapped to an original
+    /// tile only if it is complete, and each of its tiles is from that tile's block, in the
+    /// right position; otherwise, it is mapped to 0.
+    pub fn original_canvas(&self, canvas: ArrayView2<Tile>) -> Array2<Tile> {
+        let k = self.block_size;
+        let (nrows, ncols) = canvas.dim();
+        Array2::from_shape_fn((nrows / k, ncols / k), |(y, x)| {
+            let t = self.original_tile(canvas[(y * k, x * k)]);
+            let consistent = (0..k).all(|r| {
+                (0..k).all(|c| {
+                    let nt = canvas[(y * k + r, x * k + c)] as usize;
+                    self.original_tiles[nt] == t && self.block_positions[nt] == (r, c)
+                })
+            });
+            if consistent {
+                t
+            } else {
+                0
+            }
+        })
+    }
+
+    fn original_energy(&self, g1: Glue, g2: Glue) -> f64 {
+        let linked = self
+            .original_gluelinks
+            .iter()
+            .find(|(a, b, _)| (*a == g1 && *b == g2) || (*a == g2 && *b == g1));
+        match linked {
+            Some((_, _, v)) => *v,
+            None if g1 == g2 => self.original_glue_strengths[g1],
+            None => 0.,
+        }
+    }
+
+    /// Returns an array of mismatch locations in the original tile space (see
+    /// [ProofreadTileSet::original_canvas]).  At each point, mismatches are designated by
+    /// 8*N+4*E+2*S+1*W, as in [crate::system::System::calc_mismatch_locations].
+    pub fn original_mismatch_locations(&self, canvas: ArrayView2<Tile>) -> Array2<usize> {
+        let threshold = 0.5;
+        let orig = self.original_canvas(canvas);
+        let (nrows, ncols) = orig.dim();
+        let edge = |t: Tile, d: usize| self.original_edges[(t as usize, d)];
+
+        Array2::from_shape_fn((nrows, ncols), |(y, x)| {
+            let t = orig[(y, x)];
+            if t == 0 {
+                return 0;
+            }
+            let neighbors = [
+                (y > 0).then(|| orig[(y - 1, x)]),
+                (x + 1 < ncols).then(|| orig[(y, x + 1)]),
+                (y + 1 < nrows).then(|| orig[(y + 1, x)]),
+                (x > 0).then(|| orig[(y, x - 1)]),
+            ];
+            neighbors
+                .iter()
+                .enumerate()
+                .map(|(d, n)| match n {
+                    Some(n) if *n != 0 => {
+                        let e = self.original_energy(edge(t, d), edge(*n, (d + 2) % 4));
+                        ((e < threshold) as usize) << (3 - d)
+                    }
+                    _ => 0,
+                })
+                .sum()
+        })
+    }
+
+    /// Returns the number of mismatches in the original tile space.
+    pub fn original_mismatches(&self, canvas: ArrayView2<Tile>) -> usize {
+        let mut arr = self.original_mismatch_locations(canvas);
+        arr.map_inplace(|x| *x = (*x & 0b01) + ((*x & 0b10) / 2));
+        arr.sum()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl ProofreadTileSet {
+    /// The transformed tile set.
+    #[getter(tileset)]
+    fn py_tileset(&self) -> TileSet {
+        self.tileset.clone()
+    }
+
+    /// For each new tile number, the original tile number (0 for empty).
+    #[getter(original_tiles)]
+    fn py_original_tiles(&self) -> Vec<Tile> {
+        self.original_tiles.clone()
+    }
+
+    #[getter(block_size)]
+    fn py_block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// The state's canvas, mapped to original tiles, with one location per block.
+    #[pyo3(name = "original_canvas")]
+    fn py_original_canvas<'py>(&self, state: &PyState, py: Python<'py>) -> &'py PyArray2<Tile> {
+        PyArray2::from_array(py, &self.original_canvas(state.0.raw_array()))
+    }
+
+    #[pyo3(name = "original_mismatch_locations")]
+    fn py_original_mismatch_locations<'py>(
+        &self,
+        state: &PyState,
+        py: Python<'py>,
+    ) -> &'py PyArray2<usize> {
+        PyArray2::from_array(py, &self.original_mismatch_locations(state.0.raw_array()))
+    }
+
+    #[pyo3(name = "original_mismatches")]
+    fn py_original_mismatches(&self, state: &PyState) -> usize {
+        self.original_mismatches(state.0.raw_array())
+    }
+}
+
+/// Strength of the internal bond on the north side of block position (r, c), r > 0, for snaked
+/// proofreading with inputs on the south and east.  Rows assemble from the bottom, alternating
+/// east-to-west and west-to-east; the bond at the start of each row is doubled, and the bond at
+/// its end is removed if that end is on the (input) east side.
+fn snaked_vertical_strength(k: usize, r: usize, c: usize) -> f64 {
+    let (start, end) = if (k - r) % 2 == 1 {
+        (0, k - 1)
+    } else {
+        (k - 1, 0)
+    };
+    if c == start {
+        2.
+    } else if c == end && end == k - 1 {
+        0.
+    } else {
+        1.
+    }
+}
+
+impl TileSet {
+    /// Returns the k×k proofreading version of the tile set, with a mapping back to the
+    /// original tiles.  Seeds are replaced by their blocks, and the canvas size is scaled by k.
+    ///
+    /// Tile sets with duples or cover strands are not supported.
+    pub fn proofread(
+        &self,
+        kind: ProofreadingKind,
+        k: usize,
+    ) -> Result<ProofreadTileSet, RgrowError> {
+        if k == 0 {
+            return Err(GrowError::NotImplemented(
+                "Proofreading block size must be at least 1.".into(),
+            )
+            .into());
+        }
+        if self.cover_strands.as_ref().is_some_and(|c| !c.is_empty()) {
+            return Err(GrowError::NotImplemented("Proofreading of cover strands.".into()).into());
+        }
+
+        let proc = ProcessedTileSet::from_tileset(self)?;
+        if proc.has_duples {
+            return Err(GrowError::NotImplemented("Proofreading of duples.".into()).into());
+        }
+
+        let glue_name = |g: usize| match proc.glue_map.get_by_right(&g) {
+            Some(n) => n.clone(),
+            None => g.to_string(),
+        };
+        let external = |g: usize, j: usize| {
+            if g == 0 {
+                GlueIdent::Num(0)
+            } else {
+                GlueIdent::Name(format!("{}/{j}", glue_name(g)))
+            }
+        };
+        let block_tile_name =
+            |t: usize, r: usize, c: usize| format!("{}.{r}.{c}", proc.tile_names[t]);
+        let ntiles = (proc.tile_edges.nrows() - 1) * k * k;
+        let mut tiles = Vec::with_capacity(ntiles);
+        let mut original_tiles = Vec::with_capacity(ntiles + 1);
+        let mut block_positions = Vec::with_capacity(ntiles + 1);
+        let mut internal_bonds = Vec::new();
+        original_tiles.push(0);
+        block_positions.push((0, 0));
+
+        for (t, edges) in proc.tile_edges.outer_iter().enumerate().skip(1) {
+            let tname = &proc.tile_names[t];
+            let internal_h = |r: usize, c: usize| GlueIdent::Name(format!("{tname}.{r}.{c}h"));
+            // Blocks of tiles with strong glues, such as boundaries, have internal bonds of
+            // the same strength, so that they can assemble without further input.
+            let strong = edges
+                .iter()
+                .map(|g| proc.glue_strengths[*g])
+                .fold(1., f64::max);
+            let vertical_strength = |r: usize, c: usize| match kind {
+                _ if strong > 1. => strong,
+                ProofreadingKind::Uniform => 1.,
+                ProofreadingKind::Snaked => snaked_vertical_strength(k, r, c),
+            };
+            // The glue on the north side of (r, c), for r > 0.
+            let internal_v = |r: usize, c: usize| {
+                if vertical_strength(r, c) == 0. {
+                    GlueIdent::Num(0)
+                } else {
+                    GlueIdent::Name(format!("{tname}.{r}.{c}v"))
+                }
+            };
+
+            for r in 1..k {
+                for c in 0..k {
+                    let strength = vertical_strength(r, c);
+                    if strength > 1. {
+                        internal_bonds.push(Bond {
+                            name: internal_v(r, c),
+                            strength,
+                        });
+                    }
+                }
+            }
+            if strong > 1. {
+                for r in 0..k {
+                    for c in 1..k {
+                        internal_bonds.push(Bond {
+                            name: internal_h(r, c),
+                            strength: strong,
+                        });
+                    }
+                }
+            }
+
+            let original = &self.tiles[t - 1];
+            for r in 0..k {
+                for c in 0..k {
+                    let n = if r == 0 {
+                        external(edges[0], c)
+                    } else {
+                        internal_v(r, c)
+                    };
+                    let e = if c == k - 1 {
+                        external(edges[1], r)
+                    } else {
+                        internal_h(r, c + 1)
+                    };
+                    let s = if r == k - 1 {
+                        external(edges[2], c)
+                    } else {
+                        internal_v(r + 1, c)
+                    };
+                    let w = if c == 0 {
+                        external(edges[3], r)
+                    } else {
+                        internal_h(r, c)
+                    };
+                    tiles.push(tileset::Tile {
+                        name: Some(block_tile_name(t, r, c)),
+                        edges: vec![n, e, s, w],
+                        stoic: original.stoic,
+                        color: original.color.clone(),
+                        shape: None,
+                    });
+                    original_tiles.push(t as Tile);
+                    block_positions.push((r, c));
+                }
+            }
+        }
+
+        let bonds = (1..proc.glue_strengths.len())
+            .flat_map(|g| (0..k).map(move |j| (g, j)))
+            .map(|(g, j)| Bond {
+                name: external(g, j),
+                strength: proc.glue_strengths[g],
+            })
+            .chain(internal_bonds)
+            .collect();
+
+        let glues = proc
+            .gluelinks
+            .iter()
+            .flat_map(|(g1, g2, v)| (0..k).map(move |j| (*g1, *g2, *v, j)))
+            .map(|(g1, g2, v, j)| (external(g1, j), external(g2, j), v))
+            .collect();
+
+        let seed = if proc.seed.is_empty() {
+            None
+        } else {
+            Some(Seed::Multi(
+                proc.seed
+                    .iter()
+                    .flat_map(|(y, x, t)| {
+                        (0..k).flat_map(move |r| (0..k).map(move |c| (*y, *x, *t, r, c)))
+                    })
+                    .map(|(y, x, t, r, c)| {
+                        (
+                            y * k + r,
+                            x * k + c,
+                            TileIdent::Name(block_tile_name(t as usize, r, c)),
+                        )
+                    })
+                    .collect(),
+            ))
+        };
+
+        let size = self.size.map(|s| match s {
+            Size::Single(n) => Size::Single(n * k),
+            Size::Pair((a, b)) => Size::Pair((a * k, b * k)),
+        });
+
+        Ok(ProofreadTileSet {
+            tileset: TileSet {
+                tiles,
+                bonds,
+                glues,
+                seed,
+                size,
+                ..self.clone()
+            },
+            kind,
+            block_size: k,
+            original_tiles,
+            block_positions,
+            original_edges: proc.tile_edges,
+            original_glue_strengths: proc.glue_strengths,
+            original_gluelinks: proc.gluelinks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::state::StateStatus;
+    use crate::system::{DynSystem, EvolveBounds};
+    use crate::tileset::Model;
+
+    fn block_tile(pts: &ProofreadTileSet, t: Tile, pos: (usize, usize)) -> Tile {
+        (0..pts.original_tiles.len())
+            .find(|&n| pts.original_tiles[n] == t && pts.block_positions[n] == pos)
+            .unwrap() as Tile
+    }
+
+    #[test]
+    fn test_proofread_sierpinski() -> anyhow::Result<()> {
+        let mut ts = TileSet::from_file("examples/sierpinski.yaml")?;
+        ts.model = Some(Model::ATAM);
+
+        for kind in [ProofreadingKind::Uniform, ProofreadingKind::Snaked] {
+            let pts = ts.proofread(kind, 2)?;
+            assert_eq!(pts.tileset.tiles.len(), 4 * ts.tiles.len());
+            assert_eq!(pts.original_tiles.len(), pts.tileset.tiles.len() + 1);
+
+            let (sys, mut state) = pts.tileset.create_system_and_state()?;
+            assert_eq!(state.nrows(), 256);
+            sys.evolve(
+                &mut state,
+                EvolveBounds {
+                    for_events: Some(2000),
+                    ..Default::default()
+                },
+            )?;
+            assert_eq!(state.n_tiles(), 2004);
+
+            // aTAM growth is error-free, so there should be many complete blocks, and
+            // no mismatches between them.
+            let orig = pts.original_canvas(state.raw_array());
+            assert_eq!(orig.dim(), (128, 128));
+            assert_eq!(orig[(120, 120)], 1);
+            assert!(orig.iter().filter(|t| **t != 0).count() > 400);
+            assert_eq!(pts.original_mismatches(state.raw_array()), 0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_snaked_k3() -> anyhow::Result<()> {
+        let mut ts = TileSet::from_file("examples/sierpinski.yaml")?;
+        ts.model = Some(Model::ATAM);
+        let k = 3;
+        let pts = ts.proofread(ProofreadingKind::Snaked, k)?;
+        assert_eq!(pts.tileset.tiles.len(), 9 * ts.tiles.len());
+
+        let strength = |g: &GlueIdent| match g {
+            GlueIdent::Num(0) => 0.,
+            _ => pts
+                .tileset
+                .bonds
+                .iter()
+                .find(|b| &b.name == g)
+                .map_or(1., |b| b.strength),
+        };
+        let edges = |t: Tile, pos| &pts.tileset.tiles[block_tile(&pts, t, pos) as usize - 1].edges;
+
+        // Within each block, neighboring tiles bind with matching glues.
+        for t in 1..=ts.tiles.len() as Tile {
+            for r in 0..k {
+                for c in 0..k {
+                    let here = edges(t, (r, c));
+                    if c + 1 < k {
+                        let east = edges(t, (r, c + 1));
+                        assert_eq!(here[1], east[3]);
+                        assert!(strength(&here[1]) > 0.);
+                    }
+                    if r + 1 < k {
+                        assert_eq!(here[2], edges(t, (r + 1, c))[0]);
+                    }
+                }
+            }
+        }
+        let vertical = |t: Tile| {
+            (1..k)
+                .map(|r| {
+                    (0..k)
+                        .map(|c| strength(&edges(t, (r, c))[0]))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        // Blocks of strength-1 tiles have the snaked pattern of vertical bonds (see
+        // snaked_vertical_strength).
+        assert_eq!(vertical(5), vec![vec![1., 1., 2.], vec![2., 1., 0.]]);
+        // Boundary blocks hold together on their own.
+        assert_eq!(vertical(2), vec![vec![2.; 3], vec![2.; 3]]);
+
+        // External glues are split by position along the edge.
+        assert_eq!(edges(5, (0, 2))[0], GlueIdent::Name("v0/2".into()));
+        assert_eq!(edges(5, (1, 2))[1], GlueIdent::Name("v1/1".into()));
+
+        // Error-free aTAM growth assembles complete blocks without mismatches.
+        let (sys, mut state) = pts.tileset.create_system_and_state()?;
+        sys.evolve(
+            &mut state,
+            EvolveBounds {
+                for_events: Some(3000),
+                ..Default::default()
+            },
+        )?;
+        let orig = pts.original_canvas(state.raw_array());
+        assert_eq!(orig[(120, 120)], 1);
+        assert!(orig.iter().filter(|t| **t != 0).count() > 200);
+        assert_eq!(pts.original_mismatches(state.raw_array()), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_original_mismatches() -> anyhow::Result<()> {
+        let ts = TileSet::from_file("examples/sierpinski.yaml")?;
+        let pts = ts.proofread(ProofreadingKind::Snaked, 2)?;
+
+        // Tile 5 has v1 on its east side, and tile 4 has v0 on its west side.
+        let mut canvas = Array2::<Tile>::zeros((4, 6));
+        for r in 0..2 {
+            for c in 0..2 {
+                canvas[(r, c)] = block_tile(&pts, 4, (r, c));
+                canvas[(r, c + 2)] = block_tile(&pts, 5, (r, c));
+                canvas[(r, c + 4)] = block_tile(&pts, 4, (r, c));
+            }
+        }
+        let orig = pts.original_canvas(canvas.view());
+        assert_eq!(orig.row(0).to_vec(), vec![4, 5, 4]);
+        assert_eq!(orig.row(1).to_vec(), vec![0, 0, 0]);
+        assert_eq!(pts.original_mismatches(canvas.view()), 1);
+        assert_eq!(pts.original_mismatch_locations(canvas.view())[(0, 1)], 4);
+        assert_eq!(pts.original_mismatch_locations(canvas.view())[(0, 2)], 1);
+
+        // An incomplete or inconsistent block maps to 0.
+        canvas[(0, 4)] = 0;
+        canvas[(1, 0)] = block_tile(&pts, 4, (0, 0));
+        assert_eq!(
+            pts.original_canvas(canvas.view()).row(0).to_vec(),
+            vec![0, 5, 0]
+        );
+        assert_eq!(pts.original_mismatches(canvas.view()), 0);
+        Ok(())
+    }
+}
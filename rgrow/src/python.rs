@@ -1,17 +1,22 @@
 use std::ops::DerefMut;
 use std::time::Duration;
 
-use crate::base::{NumEvents, NumTiles, RustAny, Tile};
+use crate::base::{GrowError, NumEvents, NumTiles, RustAny, Tile};
 use crate::canvas::Canvas;
-use crate::ffs::{BoxedFFSResult, FFSRunConfig};
+use crate::ffs::{
+    BoxedFFSResult, CommittorAnalysis, CommittorConfig, FFSRunConfig, StructureAnalysis,
+    StructureConfig, StructurePattern, TargetStructure,
+};
 use crate::ratestore::RateStore;
-use crate::state::{StateEnum, StateStatus};
+use crate::state::{EventStats, StateEnum, StateStatus};
 use crate::system::{
-    DynSystem, EvolveBounds, EvolveOutcome, NeededUpdate, SystemEnum, TileBondInfo,
+    DynSystem, EvolveBounds, EvolveOutcome, NeededUpdate, NmerInfo, SystemEnum, SystemWithDimers,
+    TileBondInfo,
 };
 use crate::tileset::CanvasType;
+use crate::umbrella::{FreeEnergyProfile, UmbrellaConfig};
 use ndarray::Array2;
-use numpy::{IntoPyArray, PyArray2};
+use numpy::{IntoPyArray, PyArray1, PyArray2};
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
@@ -72,6 +77,27 @@ impl PyState {
         self.0.time()
     }
 
+    /// The running count of mismatched bonds, or None if the state is not keeping counts
+    /// (see `System.track_counts`).
+    #[getter]
+    pub fn mismatch_count(&self) -> Option<usize> {
+        self.0.mismatch_count()
+    }
+
+    /// Running counts of tiles of each type on the canvas, indexed by tile number, or None if
+    /// the state is not keeping counts (see `System.track_counts`).
+    #[getter]
+    pub fn tile_counts<'py>(&self, py: Python<'py>) -> Option<&'py PyArray1<NumTiles>> {
+        self.0.tile_counts().map(|c| PyArray1::from_array(py, &c))
+    }
+
+    /// Counts of events by type and tile since statistics were started, or None if the state
+    /// is not keeping them (see `System.track_event_stats`).
+    #[getter]
+    pub fn event_stats(&self) -> Option<EventStats> {
+        self.0.event_stats().cloned()
+    }
+
     pub fn __repr__(&self) -> String {
         format!(
             "State(n_tiles={}, time={} s, events={}, size=({}, {}), total_rate={})",
@@ -115,6 +141,9 @@ impl PySystem {
                     size_min=None,
                     size_max=None,
                     for_wall_time=None,
+                    leap_tolerance=None,
+                    parallel_domain_size=None,
+                    parallel_phase_events=None,
                     require_strong_bound=true,
                     show_window=false,)
     )]
@@ -131,6 +160,9 @@ impl PySystem {
         size_min: Option<u32>,
         size_max: Option<u32>,
         for_wall_time: Option<f64>,
+        leap_tolerance: Option<f64>,
+        parallel_domain_size: Option<usize>,
+        parallel_phase_events: Option<f64>,
         require_strong_bound: bool,
         show_window: bool,
         py: Python<'py>,
@@ -143,6 +175,9 @@ impl PySystem {
             size_min,
             size_max,
             for_wall_time: for_wall_time.map(Duration::from_secs_f64),
+            leap_tolerance,
+            parallel_domain_size,
+            parallel_phase_events,
         };
 
         if require_strong_bound & !bounds.is_strongly_bounded() {
@@ -210,6 +245,25 @@ impl PySystem {
         Ok(PyArray2::from_array(py, &ra))
     }
 
+    /// Check that the state's stored rates are consistent with each other and with the
+    /// system, raising an error if not.
+    fn validate_rates(&self, state: &PyState) -> PyResult<()> {
+        Ok(self.0.validate_rates(&state.0)?)
+    }
+
+    /// Start keeping running counts of mismatches and of tiles of each type on the state, which
+    /// are updated as the state evolves, and available as `State.mismatch_count` and
+    /// `State.tile_counts`.
+    fn track_counts(&self, state: &mut PyState) {
+        self.0.track_counts(&mut state.0)
+    }
+
+    /// Start (or restart) keeping counts of events on the state, by type and tile, which are
+    /// available as `State.event_stats`.
+    fn track_event_stats(&self, state: &mut PyState) {
+        self.0.track_event_stats(&mut state.0)
+    }
+
     fn set_param(&mut self, param_name: &str, value: RustAny) -> PyResult<NeededUpdate> {
         Ok(self.0.set_param(param_name, value.0)?)
     }
@@ -258,11 +312,15 @@ impl PySystem {
         self.0.update_all(&mut state.0, needed)
     }
 
-    #[pyo3(name = "run_ffs", signature = (config = FFSRunConfig::default(), canvas_type = None, **kwargs))]
+    /// Runs FFS.  By default, this starts from dimers, measuring the nucleation rate; with
+    /// `start_states`, or `from_seed=True`, it starts from the given states, or the seeded state,
+    /// measuring the rate at which they reach the target size.
+    #[pyo3(name = "run_ffs", signature = (config = FFSRunConfig::default(), canvas_type = None, start_states = None, **kwargs))]
     fn py_run_ffs(
         &mut self,
         config: FFSRunConfig,
         canvas_type: Option<CanvasType>,
+        start_states: Option<Vec<PyRef<PyState>>>,
         kwargs: Option<&PyDict>,
         py: Python<'_>,
     ) -> PyResult<BoxedFFSResult> {
@@ -274,7 +332,159 @@ impl PySystem {
             }
         }
 
-        let res = py.allow_threads(|| self.0.run_ffs(&c, canvas_type));
+        let configs = start_states.map(|states| {
+            states
+                .iter()
+                .map(|state| state.0.raw_array().to_owned())
+                .collect::<Vec<_>>()
+        });
+
+        let res = py.allow_threads(|| match configs {
+            Some(configs) => self.0.run_ffs_from_configs(&configs, &c, canvas_type),
+            None => self.0.run_ffs(&c, canvas_type),
+        });
+        match res {
+            Ok(res) => Ok(res),
+            Err(GrowError::Python(err)) => Err(err),
+            Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// Resumes an FFS run made with this system, saved or loaded as `previous`, or extends it to a
+    /// larger target size.  The configuration defaults to that of `previous`, with any changes
+    /// given as keyword arguments.
+    #[pyo3(name = "resume_ffs", signature = (previous, config = None, canvas_type = None, **kwargs))]
+    fn py_resume_ffs(
+        &mut self,
+        previous: &BoxedFFSResult,
+        config: Option<FFSRunConfig>,
+        canvas_type: Option<CanvasType>,
+        kwargs: Option<&PyDict>,
+        py: Python<'_>,
+    ) -> PyResult<BoxedFFSResult> {
+        let mut c = config.unwrap_or_else(|| previous.0.config().clone());
+
+        if let Some(dict) = kwargs {
+            for (k, v) in dict.iter() {
+                c._py_set(&k.extract::<String>()?, v, py)?;
+            }
+        }
+
+        let data = previous.0.to_data();
+        let res = py.allow_threads(|| self.0.resume_ffs(&data, &c, canvas_type));
+        match res {
+            Ok(res) => Ok(res),
+            Err(GrowError::Python(err)) => Err(err),
+            Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// Estimates committors for stored configurations of an FFS run made with this system,
+    /// by shooting `trials` trials from up to `max_configs` configurations on each surface.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(
+        name = "ffs_committor",
+        signature = (result, trials = 100, max_configs = 20, target = None, window = 0.2, seed = None, canvas_type = None)
+    )]
+    fn py_ffs_committor(
+        &self,
+        result: &BoxedFFSResult,
+        trials: usize,
+        max_configs: usize,
+        target: Option<NumTiles>,
+        window: f64,
+        seed: Option<u64>,
+        canvas_type: Option<CanvasType>,
+        py: Python<'_>,
+    ) -> PyResult<CommittorAnalysis> {
+        let config = CommittorConfig {
+            trials,
+            max_configs,
+            target,
+            window,
+            seed,
+            ..Default::default()
+        };
+        let res = py.allow_threads(|| self.0.ffs_committor(&**result.0, &config, canvas_type));
+        match res {
+            Ok(res) => Ok(res),
+            Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// Classifies the nucleated assemblies of an FFS run made with this system into
+    /// `structures`, a list of `(name, pattern)` in order of priority, giving the nucleation rate
+    /// of each.  With `grow_to`, assemblies are first grown to that order parameter value.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(
+        name = "ffs_structures",
+        signature = (result, structures, grow_to = None, confidence = 0.95, seed = None, canvas_type = None)
+    )]
+    fn py_ffs_structures(
+        &self,
+        result: &BoxedFFSResult,
+        structures: Vec<(String, StructurePattern)>,
+        grow_to: Option<NumTiles>,
+        confidence: f64,
+        seed: Option<u64>,
+        canvas_type: Option<CanvasType>,
+        py: Python<'_>,
+    ) -> PyResult<StructureAnalysis> {
+        let config = StructureConfig {
+            structures: structures
+                .into_iter()
+                .map(|(name, pattern)| TargetStructure { name, pattern })
+                .collect(),
+            grow_to,
+            confidence,
+            seed,
+            ..Default::default()
+        };
+        let res = py.allow_threads(|| self.0.ffs_structures(&**result.0, &config, canvas_type));
+        match res {
+            Ok(res) => Ok(res),
+            Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// Enumerates every assembly of 2 to `max_size` tiles held together by bonds, with its bond
+    /// energy, formation rate and equilibrium concentration.
+    #[pyo3(name = "calc_nmers", signature = (max_size = 4))]
+    fn py_calc_nmers(&self, max_size: NumTiles, py: Python<'_>) -> Vec<NmerInfo> {
+        py.allow_threads(|| self.0.calc_nmers(max_size))
+    }
+
+    /// Samples the free energy landscape of an assembly by umbrella sampling.  By default, this
+    /// starts from the most stable dimer, or with `from_seed=True`, the seeded state; with
+    /// `start_state`, it starts from the given state.
+    #[pyo3(name = "free_energy_profile", signature = (config = UmbrellaConfig::default(), canvas_type = None, start_state = None, **kwargs))]
+    fn py_free_energy_profile(
+        &self,
+        config: UmbrellaConfig,
+        canvas_type: Option<CanvasType>,
+        start_state: Option<PyRef<PyState>>,
+        kwargs: Option<&PyDict>,
+        py: Python<'_>,
+    ) -> PyResult<FreeEnergyProfile> {
+        let mut c = config;
+
+        if let Some(dict) = kwargs {
+            for (k, v) in dict.iter() {
+                c._py_set(&k.extract::<String>()?, v, py)?;
+            }
+        }
+
+        let start = start_state.map(|state| state.0.raw_array().to_owned());
+
+        let res = py.allow_threads(|| self.0.free_energy_profile(&c, start.as_ref(), canvas_type));
         match res {
             Ok(res) => Ok(res),
             Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
@@ -287,3 +497,60 @@ impl PySystem {
         format!("System({})", self.0.system_info())
     }
 }
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl NmerInfo {
+    /// The assembly as an array of tiles, in its bounding box.
+    #[pyo3(name = "config")]
+    fn py_config(&self, py: Python<'_>) -> Py<PyArray2<Tile>> {
+        self.config().into_pyarray(py).to_owned()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "NmerInfo(size={}, tiles={:?}, bond_energy={}, equilibrium_conc={:e})",
+            self.size, self.tiles, self.bond_energy, self.equilibrium_conc
+        )
+    }
+}
+
+/// Forwards `log` records to Python's `logging` module, using loggers named for the records'
+/// targets (eg, `rgrow.ffs` for `rgrow::ffs`).
+struct PythonLogger;
+
+impl log::Log for PythonLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let level = match record.level() {
+            log::Level::Error => 40,
+            log::Level::Warn => 30,
+            log::Level::Info => 20,
+            log::Level::Debug => 10,
+            log::Level::Trace => 5,
+        };
+        Python::with_gil(|py| {
+            let logged = py.import("logging").and_then(|logging| {
+                logging
+                    .call_method1("getLogger", (record.target().replace("::", "."),))?
+                    .call_method1("log", (level, record.args().to_string()))
+            });
+            if let Err(err) = logged {
+                err.print(py);
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Sends rgrow's log records to Python's `logging` module, unless another logger has already
+/// been set.  Called when the Python module is imported.
+pub fn init_python_logging() {
+    if log::set_logger(&PythonLogger).is_ok() {
+        log::set_max_level(log::LevelFilter::Debug);
+    }
+}
@@ -5,8 +5,10 @@ use pyo3::{
 };
 
 use crate::{
-    base::GlueIdent,
+    base::{GlueIdent, GrowError, RgrowError},
+    ca_compiler::CASpec,
     ffs::{BoxedFFSResult, FFSRunConfig},
+    proofreading::{ProofreadTileSet, ProofreadingKind},
     python::{PyState, PySystem},
     tileset::{self, Bond, CoverStrand, Tile, TileSet},
 };
@@ -43,6 +45,9 @@ impl TileSet {
                     "size" => tileset.size = Some(v.extract()?),
                     "tau" => tileset.tau = Some(v.extract()?),
                     "smax" => tileset.smax = Some(v.extract()?),
+                    "smin" => tileset.smin = Some(v.extract()?),
+                    "tmax" => tileset.tmax = Some(v.extract()?),
+                    "emax" => tileset.emax = Some(v.extract()?),
                     "update_rate" => tileset.update_rate = Some(v.extract()?),
                     "k_f" | "kf" => tileset.kf = Some(v.extract()?),
                     "fission" => tileset.fission = Some(v.extract::<&str>()?.try_into()?),
@@ -52,6 +57,7 @@ impl TileSet {
                     }
                     "chunk_size" => tileset.chunk_size = Some(v.extract::<&str>()?.try_into()?),
                     "canvas_type" => tileset.canvas_type = Some(v.extract::<&str>()?.try_into()?),
+                    "rate_store" => tileset.rate_store = Some(v.extract::<&str>()?.try_into()?),
                     "hdoubletiles" => tileset.hdoubletiles = Some(v.extract()?),
                     "vdoubletiles" => tileset.vdoubletiles = Some(v.extract()?),
                     "model" => tileset.model = Some(v.extract::<&str>()?.try_into()?),
@@ -111,6 +117,44 @@ impl TileSet {
         Ok(ts)
     }
 
+    /// Reads a tile set from a file, also returning descriptions of the options in Xgrow
+    /// files that were ignored.
+    #[pyo3(name = "from_file_with_ignored")]
+    #[classmethod]
+    fn py_from_file_with_ignored(_cls: &PyType, path: &str) -> PyResult<(Self, Vec<String>)> {
+        let (ts, ignored) = tileset::TileSet::from_file_with_ignored(path)
+            .map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?;
+        Ok((ts, ignored.iter().map(|i| i.to_string()).collect()))
+    }
+
+    /// Compiles a cellular automaton rule table (as a dict, in the same form as the YAML rule
+    /// table files) into a TileSet.
+    #[pyo3(name = "from_ca_rules")]
+    #[classmethod]
+    fn py_from_ca_rules(_cls: &PyType, spec: PyObject) -> PyResult<Self> {
+        let json: String = Python::with_gil(|py| {
+            let json = PyModule::import(py, "json")?;
+            json.call_method1("dumps", (spec,))?.extract::<String>()
+        })?;
+
+        let spec: CASpec = serde_json::from_str(&json)
+            .map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))?;
+        Ok(spec.to_tileset()?)
+    }
+
+    /// Returns the tile set as a JSON string.
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> PyResult<String> {
+        serde_json::to_string(self).map_err(|err| PyErr::new::<PyValueError, _>(err.to_string()))
+    }
+
+    /// Returns the k×k proofreading version of the tile set, with a mapping back to the
+    /// original tiles.  `kind` is "uniform" or "snaked".
+    #[pyo3(name = "proofread", signature = (k, kind = "uniform"))]
+    fn py_proofread(&self, k: usize, kind: &str) -> PyResult<ProofreadTileSet> {
+        Ok(self.proofread(ProofreadingKind::try_from(kind)?, k)?)
+    }
+
     #[pyo3(name = "create_system")]
     fn py_create_system(&self) -> PyResult<PySystem> {
         let sys = self.create_dynsystem()?;
@@ -172,12 +216,61 @@ impl TileSet {
         let res = py.allow_threads(|| self.run_ffs(&c));
         match res {
             Ok(res) => Ok(BoxedFFSResult(res.into())),
+            Err(RgrowError::Grow(GrowError::Python(err))) => Err(err),
             Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
                 err.to_string(),
             )),
         }
     }
 
+    /// Resumes an FFS run of this tile set, saved or loaded as `previous`, or extends it to a
+    /// larger target size.  The configuration defaults to that of `previous`, with any changes
+    /// given as keyword arguments.
+    #[pyo3(name = "resume_ffs", signature = (previous, config = None, **kwargs))]
+    fn py_resume_ffs(
+        &self,
+        previous: &BoxedFFSResult,
+        config: Option<FFSRunConfig>,
+        kwargs: Option<&PyDict>,
+        py: Python<'_>,
+    ) -> PyResult<BoxedFFSResult> {
+        let mut c = config.unwrap_or_else(|| previous.0.config().clone());
+
+        if let Some(dict) = kwargs {
+            for (k, v) in dict.iter() {
+                c._py_set(&k.extract::<String>()?, v, py)?;
+            }
+        }
+
+        let data = previous.0.to_data();
+        let res = py.allow_threads(|| self.resume_ffs(&data, &c));
+        match res {
+            Ok(res) => Ok(BoxedFFSResult(res.into())),
+            Err(RgrowError::Grow(GrowError::Python(err))) => Err(err),
+            Err(err) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// Returns the tile set in Xgrow's .tiles format.
+    #[pyo3(name = "to_xgrow_string")]
+    fn py_to_xgrow_string(&self) -> PyResult<String> {
+        Ok(self.to_xgrow_string()?)
+    }
+
+    /// Writes the tile set to an Xgrow .tiles file.
+    #[pyo3(name = "write_xgrow")]
+    fn py_write_xgrow(&self, path: &str) -> PyResult<()> {
+        Ok(self.write_xgrow(path)?)
+    }
+
+    /// Writes the tile set as ISU TAS (and PyTAS) .tds and .tdp files, with the given path stem.
+    #[pyo3(name = "write_tas")]
+    fn py_write_tas(&self, path: &str) -> PyResult<()> {
+        Ok(self.write_tas(path)?)
+    }
+
     fn __repr__(&self) -> String {
         self.to_string()
     }
@@ -2,12 +2,11 @@ use std::borrow::BorrowMut;
 
 use crate::state::StateEnum;
 use enum_dispatch::enum_dispatch;
-use fnv::FnvHashSet;
-use ndarray::Array2;
-use rand::thread_rng;
+use fnv::{FnvHashMap, FnvHashSet};
+use ndarray::{s, Array2};
 use rand::Rng;
 
-use crate::base::{Point, Rate};
+use crate::base::{GrowError, Point, Rate, SimRng};
 use crate::canvas::PointSafeHere;
 // A RateStore stores event rates for points on a canvas, and allows a continuous-time Markov chain
 // choice of a point based on those rates.  It makes no assumptions about relationships between the
@@ -15,11 +14,39 @@ use crate::canvas::PointSafeHere;
 // rectilinear grid.
 #[enum_dispatch]
 pub trait RateStore {
-    fn choose_point(&self) -> (Point, Rate);
+    /// Chooses a point, weighted by rate, returning the point and the remaining (unused) part of
+    /// the random threshold.  This fails if floating-point error has made the stored sums
+    /// inconsistent; [RateStore::resum] should fix this.
+    fn choose_point(&self, rng: &mut SimRng) -> Result<(Point, Rate), GrowError>;
     fn rate_at_point(&self, point: PointSafeHere) -> Rate;
     fn update_point(&mut self, point: PointSafeHere, new_rate: Rate);
     fn update_multiple(&mut self, to_update: &[(PointSafeHere, Rate)]);
     fn total_rate(&self) -> Rate;
+    /// Recalculates all stored sums exactly from the rates at each point, removing accumulated
+    /// floating-point error.
+    fn resum(&mut self);
+    /// Checks that all stored sums agree with the rates at each point, to within floating-point
+    /// error.
+    fn check_consistency(&self) -> Result<(), GrowError>;
+}
+
+/// Relative tolerance for [RateStore::check_consistency].
+const CONSISTENCY_TOLERANCE: f64 = 1e-9;
+
+fn check_sum(
+    stored: Rate,
+    exact: Rate,
+    scale: Rate,
+    location: impl FnOnce() -> String,
+) -> Result<(), GrowError> {
+    if (stored - exact).abs() > CONSISTENCY_TOLERANCE * scale.max(exact.abs()) {
+        Err(GrowError::RateStoreInconsistent(format!(
+            "{} is {stored:?}, but should be {exact:?}",
+            location()
+        )))
+    } else {
+        Ok(())
+    }
 }
 
 pub trait RateTrait:
@@ -33,34 +60,62 @@ pub trait CreateSizedRateStore {
     fn new_with_size(rows: usize, cols: usize) -> Self;
 }
 
+pub trait CopyNonzeroRates {
+    /// Copies the nonzero rates of `source` into this store, which is assumed (but not checked)
+    /// to have all zero rates and the same size.  `f` is called on each point that is copied.
+    fn copy_nonzero_rates_from<F: FnMut(Point)>(&mut self, source: &Self, f: F);
+}
+
 /// A RateStore for a 2D canvas, using a:
 /// - A quadtree to store and choose rates.
-/// - Square arrays in the quadtree.
+/// - Arrays in the quadtree that are either square, with sides a power of two (`SQUARE = true`),
+///   or rectangular, with each level half the size (rounded up) of the one below it
+///   (`SQUARE = false`).
 /// - Linear rate storage.
+///
+/// Each level of the tree has even sides, so that every point in a level has four children in
+/// the level below.  The top level is always 2×2.
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct QuadTreeSquareArray<R: RateTrait>(pub Vec<Array2<R>>, pub R);
+pub struct QuadTreeArray<R: RateTrait, const SQUARE: bool>(pub Vec<Array2<R>>, pub R);
 
-impl<R: RateTrait> CreateSizedRateStore for QuadTreeSquareArray<R> {
-    fn new_with_size(rows: usize, cols: usize) -> Self {
-        let p = f64::log2(rows.max(cols) as f64).ceil() as u32;
+/// A quadtree RateStore with square levels, allocated for the smallest power-of-two square
+/// containing the canvas.
+pub type QuadTreeSquareArray<R> = QuadTreeArray<R, true>;
 
+/// A quadtree RateStore with rectangular levels, allocated for the shape of the canvas.  For
+/// elongated canvases, like ribbons and tubes, or non-power-of-two sizes, this is much smaller
+/// than [QuadTreeSquareArray], while choosing points identically.
+pub type QuadTreeRectArray<R> = QuadTreeArray<R, false>;
+
+impl<R: RateTrait, const SQUARE: bool> CreateSizedRateStore for QuadTreeArray<R, SQUARE> {
+    fn new_with_size(rows: usize, cols: usize) -> Self {
         let mut rates = Vec::<Array2<R>>::new();
 
-        for i in (1..=p).rev() {
-            rates.push(Array2::<R>::zeros((2usize.pow(i), 2usize.pow(i))))
+        if SQUARE {
+            let p = f64::log2(rows.max(cols) as f64).ceil() as u32;
+
+            for i in (1..=p).rev() {
+                rates.push(Array2::<R>::zeros((2usize.pow(i), 2usize.pow(i))))
+            }
+        } else {
+            let (mut r, mut c) = (rows.max(1), cols.max(1));
+            while (r, c) != (1, 1) {
+                (r, c) = (r.div_ceil(2), c.div_ceil(2));
+                rates.push(Array2::<R>::zeros((2 * r, 2 * c)));
+            }
         }
 
         Self(rates, R::zero())
     }
 }
 
-impl RateStore for QuadTreeSquareArray<f64> {
+impl<const SQUARE: bool> RateStore for QuadTreeArray<f64, SQUARE> {
     fn rate_at_point(&self, point: PointSafeHere) -> Rate {
         unsafe { *self.0[0].uget(point.0) }
     }
 
-    fn choose_point(&self) -> (Point, Rate) {
-        let mut threshold = self.1 * thread_rng().gen::<f64>();
+    fn choose_point(&self, rng: &mut SimRng) -> Result<(Point, Rate), GrowError> {
+        let mut threshold = self.1 * rng.gen::<f64>();
 
         let mut x: usize = 0;
         let mut y: usize = 0;
@@ -94,11 +149,15 @@ impl RateStore for QuadTreeSquareArray<f64> {
             if threshold - v <= 0. {
                 continue;
             } else {
-                panic!("Failure in quadtree position finding: remaining threshold {threshold:?}, ratetree array {r:?}.");
+                return Err(GrowError::RateStoreInconsistent(format!(
+                    "remaining threshold {threshold:?} in quadtree position finding at level of shape {:?}, point {:?}",
+                    r.dim(),
+                    (y, x)
+                )));
             }
         }
 
-        ((y, x), threshold)
+        Ok(((y, x), threshold))
     }
 
     #[inline(always)]
@@ -137,9 +196,36 @@ impl RateStore for QuadTreeSquareArray<f64> {
     fn total_rate(&self) -> Rate {
         self.1
     }
+
+    fn resum(&mut self) {
+        self._update_multiple_all(&[]);
+    }
+
+    fn check_consistency(&self) -> Result<(), GrowError> {
+        let scale = self.1.abs();
+        for (level, pair) in self.0.windows(2).enumerate() {
+            let (rt, rn) = (&pair[0], &pair[1]);
+            for ((y, x), v) in rn.indexed_iter() {
+                let exact = if (2 * y < rt.nrows()) && (2 * x < rt.ncols()) {
+                    rt[(2 * y, 2 * x)]
+                        + rt[(2 * y, 2 * x + 1)]
+                        + rt[(2 * y + 1, 2 * x)]
+                        + rt[(2 * y + 1, 2 * x + 1)]
+                } else {
+                    0.
+                };
+                check_sum(*v, exact, scale, || {
+                    format!("sum at level {}, point {:?},", level + 1, (y, x))
+                })?;
+            }
+        }
+        check_sum(self.1, self.0.last().unwrap().sum(), scale, || {
+            "total rate".to_string()
+        })
+    }
 }
 
-impl<Rate: RateTrait> QuadTreeSquareArray<Rate> {
+impl<Rate: RateTrait, const SQUARE: bool> QuadTreeArray<Rate, SQUARE> {
     pub fn _update_multiple_small(&mut self, to_update: &[(PointSafeHere, Rate)]) {
         let mut todo = Vec::<Point>::new();
 
@@ -148,11 +234,10 @@ impl<Rate: RateTrait> QuadTreeSquareArray<Rate> {
 
         for (p, r) in to_update {
             r_prev[p.0] = *r;
-            let n = (p.0 .0 / 2, p.0 .1 / 2);
-            if todo.iter().rev().all(|x| n != *x) {
-                todo.push(n);
-            }
+            todo.push((p.0 .0 / 2, p.0 .1 / 2));
         }
+        todo.sort_unstable();
+        todo.dedup();
 
         for r_next in rtiter {
             for p in todo.iter_mut() {
@@ -203,7 +288,9 @@ impl<Rate: RateTrait> QuadTreeSquareArray<Rate> {
         }
 
         for r_next in rtiter {
-            for p in r_next.indexed_iter_mut() {
+            // In rectangular trees, padding points in a level may have no children.
+            let (nr, nc) = (r_prev.nrows() / 2, r_prev.ncols() / 2);
+            for p in r_next.slice_mut(s![..nr, ..nc]).indexed_iter_mut() {
                 qt_update_level_val(p.1, r_prev, p.0);
             }
             r_prev = r_next;
@@ -213,6 +300,220 @@ impl<Rate: RateTrait> QuadTreeSquareArray<Rate> {
     }
 }
 
+impl<R: RateTrait + PartialOrd, const SQUARE: bool> CopyNonzeroRates for QuadTreeArray<R, SQUARE> {
+    fn copy_nonzero_rates_from<F: FnMut(Point)>(&mut self, source: &Self, mut f: F) {
+        let max_level = self.0.len() - 1;
+        self.copy_level_quad(source, max_level, (0, 0), &mut f);
+        self.1 = source.1;
+    }
+}
+
+impl<R: RateTrait + PartialOrd, const SQUARE: bool> QuadTreeArray<R, SQUARE> {
+    fn copy_level_quad<F: FnMut(Point)>(
+        &mut self,
+        source: &Self,
+        level: usize,
+        point: Point,
+        f: &mut F,
+    ) {
+        let (y, x) = point;
+
+        for (yy, xx) in [(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)] {
+            let z = source.0[level][(yy, xx)];
+            if z > R::zero() {
+                self.0[level][(yy, xx)] = z;
+                if level > 0 {
+                    self.copy_level_quad(source, level - 1, (yy * 2, xx * 2), f);
+                } else {
+                    f((yy, xx));
+                }
+            }
+        }
+    }
+}
+
+/// A RateStore that only stores points with nonzero rates, using:
+/// - A list of active points, with a hash map from point to index.
+/// - A Fenwick (binary indexed) tree over the rates of the active points, to choose points.
+///
+/// Memory use and update time depend only on the number of active points, not on the size of
+/// the canvas, so this is suited to large canvases containing small assemblies.
+#[derive(Clone, Debug)]
+pub struct SparseRateStore {
+    points: Vec<Point>,
+    index: FnvHashMap<Point, usize>,
+    rates: Vec<Rate>,
+    /// Fenwick tree over `rates`, 1-indexed, with a power-of-two capacity of `tree.len() - 1`.
+    tree: Vec<Rate>,
+}
+
+const SPARSE_INITIAL_CAPACITY: usize = 64;
+
+impl CreateSizedRateStore for SparseRateStore {
+    fn new_with_size(_rows: usize, _cols: usize) -> Self {
+        Self {
+            points: Vec::new(),
+            index: FnvHashMap::default(),
+            rates: Vec::new(),
+            tree: vec![0.; SPARSE_INITIAL_CAPACITY + 1],
+        }
+    }
+}
+
+impl SparseRateStore {
+    /// The number of points with nonzero rates.
+    pub fn n_active(&self) -> usize {
+        self.points.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    fn tree_add(&mut self, i: usize, delta: Rate) {
+        let mut j = i + 1;
+        while j < self.tree.len() {
+            self.tree[j] += delta;
+            j += j & j.wrapping_neg();
+        }
+    }
+
+    /// Rebuilds the Fenwick tree from the rates, with the given capacity.
+    fn rebuild(&mut self, capacity: usize) {
+        self.tree = vec![0.; capacity + 1];
+        for (i, r) in self.rates.iter().enumerate() {
+            self.tree[i + 1] += r;
+        }
+        for j in 1..=capacity {
+            let parent = j + (j & j.wrapping_neg());
+            if parent <= capacity {
+                self.tree[parent] += self.tree[j];
+            }
+        }
+    }
+
+    fn remove_index(&mut self, i: usize) {
+        let last = self.points.len() - 1;
+        if last == 0 {
+            // Avoid leaving floating-point residue in an empty tree.
+            self.points.clear();
+            self.rates.clear();
+            self.tree.fill(0.);
+            return;
+        }
+        self.tree_add(i, -self.rates[i]);
+        if i != last {
+            let (p, r) = (self.points[last], self.rates[last]);
+            self.tree_add(last, -r);
+            self.tree_add(i, r);
+            self.points[i] = p;
+            self.rates[i] = r;
+            self.index.insert(p, i);
+        }
+        self.points.pop();
+        self.rates.pop();
+    }
+
+    fn push(&mut self, point: Point, rate: Rate) {
+        if self.points.len() == self.capacity() {
+            self.points.push(point);
+            self.rates.push(rate);
+            self.rebuild(2 * self.capacity());
+        } else {
+            self.points.push(point);
+            self.rates.push(rate);
+            self.tree_add(self.points.len() - 1, rate);
+        }
+        self.index.insert(point, self.points.len() - 1);
+    }
+}
+
+impl RateStore for SparseRateStore {
+    fn choose_point(&self, rng: &mut SimRng) -> Result<(Point, Rate), GrowError> {
+        let mut threshold = self.total_rate() * rng.gen::<f64>();
+
+        if self.points.is_empty() {
+            return Err(GrowError::RateStoreInconsistent(
+                "choosing a point from an empty sparse rate store".to_string(),
+            ));
+        }
+
+        let mut pos = 0;
+        let mut step = self.capacity();
+        while step > 0 {
+            if (pos + step < self.tree.len()) && (self.tree[pos + step] < threshold) {
+                pos += step;
+                threshold -= self.tree[pos];
+            }
+            step /= 2;
+        }
+
+        match self.points.get(pos) {
+            Some(p) => Ok((*p, threshold)),
+            None => Err(GrowError::RateStoreInconsistent(format!(
+                "remaining threshold {threshold:?} past the end of the sparse rate store"
+            ))),
+        }
+    }
+
+    fn rate_at_point(&self, point: PointSafeHere) -> Rate {
+        match self.index.get(&point.0) {
+            Some(i) => self.rates[*i],
+            None => 0.,
+        }
+    }
+
+    fn update_point(&mut self, point: PointSafeHere, new_rate: Rate) {
+        match self.index.get(&point.0) {
+            Some(&i) => {
+                if new_rate == 0. {
+                    self.index.remove(&point.0);
+                    self.remove_index(i);
+                } else {
+                    self.tree_add(i, new_rate - self.rates[i]);
+                    self.rates[i] = new_rate;
+                }
+            }
+            None => {
+                if new_rate != 0. {
+                    self.push(point.0, new_rate);
+                }
+            }
+        }
+    }
+
+    fn update_multiple(&mut self, to_update: &[(PointSafeHere, Rate)]) {
+        for (p, r) in to_update {
+            self.update_point(*p, *r);
+        }
+    }
+
+    fn total_rate(&self) -> Rate {
+        self.tree[self.capacity()]
+    }
+
+    fn resum(&mut self) {
+        self.rebuild(self.capacity());
+    }
+
+    fn check_consistency(&self) -> Result<(), GrowError> {
+        let mut exact = self.clone();
+        exact.resum();
+        let scale = exact.total_rate().abs();
+        for (j, (v, e)) in self.tree.iter().zip(exact.tree.iter()).enumerate() {
+            check_sum(*v, *e, scale, || format!("Fenwick tree node {j}"))?;
+        }
+        Ok(())
+    }
+}
+
+impl CopyNonzeroRates for SparseRateStore {
+    fn copy_nonzero_rates_from<F: FnMut(Point)>(&mut self, source: &Self, f: F) {
+        self.clone_from(source);
+        self.points.iter().copied().for_each(f);
+    }
+}
+
 #[inline(always)]
 fn qt_update_level<R: RateTrait>(rn: &mut Array2<R>, rt: &Array2<R>, np: Point) {
     qt_update_level_val(unsafe { rn.uget_mut(np) }, rt, np);
@@ -269,4 +570,152 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_ratestore_sparse() -> anyhow::Result<()> {
+        let mut rs = SparseRateStore::new_with_size(4096, 4096);
+        let mut rs_tree = QuadTreeRectArray::new_with_size(4096, 4096);
+
+        let mut rng = rand::thread_rng();
+        let mut changes = (0..300usize)
+            .map(|i| {
+                (
+                    PointSafeHere((2000 + i % 17, 1000 + i / 17)),
+                    rng.gen::<f64>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        rs.update_multiple(&changes);
+        rs_tree.update_multiple(&changes);
+        assert_eq!(rs.n_active(), 300);
+
+        // Remove some points, and change others.
+        for (i, (_, r)) in changes.iter_mut().enumerate() {
+            *r = if i % 3 == 0 { 0. } else { *r * 2. };
+        }
+        rs.update_multiple(&changes);
+        rs_tree.update_multiple(&changes);
+        assert_eq!(rs.n_active(), 200);
+
+        assert!((rs.total_rate() - rs_tree.total_rate()).abs() < 1e-9);
+        for (p, _) in changes.iter() {
+            assert_eq!(rs.rate_at_point(*p), rs_tree.rate_at_point(*p));
+        }
+
+        let mut rng = crate::base::new_sim_rng();
+        for _ in 0..1000 {
+            let (p, _) = rs.choose_point(&mut rng)?;
+            assert!(rs.rate_at_point(PointSafeHere(p)) > 0.);
+        }
+
+        let mut rs_copy = SparseRateStore::new_with_size(4096, 4096);
+        let mut n = 0;
+        rs_copy.copy_nonzero_rates_from(&rs, |_| n += 1);
+        assert_eq!(n, 200);
+        assert_eq!(rs_copy.total_rate(), rs.total_rate());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratestore_resum() -> anyhow::Result<()> {
+        let mut rs = QuadTreeSquareArray::<f64>::new_with_size(64, 64);
+        let mut rng = rand::thread_rng();
+        let changes = (0..500usize)
+            .map(|i| (PointSafeHere((i % 61, i / 61)), rng.gen::<f64>()))
+            .collect::<Vec<_>>();
+        rs.update_multiple(&changes);
+        rs.check_consistency()?;
+
+        // Corrupt an intermediate sum, as accumulated drift might.
+        rs.0[2][(1, 1)] += 1.0;
+        assert!(matches!(
+            rs.check_consistency(),
+            Err(GrowError::RateStoreInconsistent(_))
+        ));
+        rs.resum();
+        rs.check_consistency()?;
+
+        let mut sparse = SparseRateStore::new_with_size(64, 64);
+        sparse.update_multiple(&changes);
+        sparse.check_consistency()?;
+        sparse.tree[3] += 1.0;
+        assert!(sparse.check_consistency().is_err());
+        sparse.resum();
+        sparse.check_consistency()?;
+        assert!((sparse.total_rate() - rs.total_rate()).abs() < 1e-9);
+
+        // Removing every point should leave an exactly empty store.
+        sparse.update_multiple(&changes.iter().map(|(p, _)| (*p, 0.)).collect::<Vec<_>>());
+        assert_eq!(sparse.total_rate(), 0.);
+        assert!(sparse
+            .choose_point(&mut crate::base::new_sim_rng())
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratestore_qtra_shape() -> anyhow::Result<()> {
+        let rs = QuadTreeRectArray::<f64>::new_with_size(6, 64);
+        let shapes = rs.0.iter().map(|a| a.dim()).collect::<Vec<_>>();
+        assert_eq!(
+            shapes,
+            vec![(6, 64), (4, 32), (2, 16), (2, 8), (2, 4), (2, 2)]
+        );
+
+        // The rectangular tree stores far fewer rates than the square one for a ribbon.
+        let stored = |levels: &[Array2<f64>]| levels.iter().map(|a| a.len()).sum::<usize>();
+        let square = QuadTreeSquareArray::<f64>::new_with_size(6, 64);
+        assert_eq!(stored(&rs.0), 572);
+        assert!(stored(&square.0) > 4 * stored(&rs.0));
+
+        // For power-of-two squares, the two stores are identical.
+        let square = QuadTreeSquareArray::<f64>::new_with_size(128, 128);
+        let rect = QuadTreeRectArray::<f64>::new_with_size(128, 128);
+        assert_eq!(square.0, rect.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratestore_qtra_choose() -> anyhow::Result<()> {
+        let mut rs = QuadTreeRectArray::new_with_size(6, 100);
+        let mut rs_square = QuadTreeSquareArray::new_with_size(6, 100);
+
+        let rng = rand::thread_rng();
+        let it = rng.sample_iter(rand::distributions::Uniform::new(0.0, 1.0));
+        let allchanges = (0..6usize)
+            .flat_map(|y| {
+                (0..100usize)
+                    .filter(move |x| (x + y) % 3 == 0)
+                    .map(move |x| (y, x))
+            })
+            .zip(it)
+            .map(|(p, r)| (PointSafeHere(p), r))
+            .collect::<Vec<_>>();
+
+        let mut rs_large = rs.clone();
+        let mut rs_all = rs.clone();
+
+        rs.update_multiple(&allchanges);
+        rs_large._update_multiple_large(&allchanges);
+        rs_all._update_multiple_all(&allchanges);
+        for (p, r) in allchanges.iter() {
+            rs_square.update_point(*p, *r);
+        }
+
+        assert_eq!(rs, rs_large);
+        assert_eq!(rs, rs_all);
+        assert!((rs.total_rate() - rs_square.total_rate()).abs() < 1e-9);
+
+        let mut rng = crate::base::new_sim_rng();
+        for _ in 0..1000 {
+            let ((y, x), _) = rs.choose_point(&mut rng)?;
+            assert!((y < 6) & (x < 100));
+            assert!(rs.rate_at_point(PointSafeHere((y, x))) > 0.);
+        }
+
+        Ok(())
+    }
 }
@@ -3,7 +3,10 @@ use crate::canvas::{Canvas, CanvasCreate, CanvasPeriodic, CanvasSquare, CanvasTu
 use crate::{
     canvas::PointSafe2,
     canvas::PointSafeHere,
-    ratestore::{CreateSizedRateStore, QuadTreeSquareArray, RateStore},
+    ratestore::{
+        CopyNonzeroRates, CreateSizedRateStore, QuadTreeRectArray, QuadTreeSquareArray, RateStore,
+        SparseRateStore,
+    },
     system,
 };
 use ndarray::prelude::*;
@@ -11,6 +14,11 @@ use ndarray::prelude::*;
 use enum_dispatch::enum_dispatch;
 use std::fmt::Debug;
 
+#[cfg(feature = "python")]
+use numpy::PyArray1;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
 #[enum_dispatch]
 pub trait State: RateStore + Canvas + StateStatus + Sync + Send {
     fn panicinfo(&self) -> String;
@@ -22,6 +30,12 @@ pub enum StateEnum {
     Square(QuadTreeState<CanvasSquare, NullStateTracker>),
     Periodic(QuadTreeState<CanvasPeriodic, NullStateTracker>),
     Tube(QuadTreeState<CanvasTube, NullStateTracker>),
+    SquareRect(QuadTreeState<CanvasSquare, NullStateTracker, QuadTreeRectArray<Rate>>),
+    PeriodicRect(QuadTreeState<CanvasPeriodic, NullStateTracker, QuadTreeRectArray<Rate>>),
+    TubeRect(QuadTreeState<CanvasTube, NullStateTracker, QuadTreeRectArray<Rate>>),
+    SquareSparse(QuadTreeState<CanvasSquare, NullStateTracker, SparseRateStore>),
+    PeriodicSparse(QuadTreeState<CanvasPeriodic, NullStateTracker, SparseRateStore>),
+    TubeSparse(QuadTreeState<CanvasTube, NullStateTracker, SparseRateStore>),
 }
 
 #[enum_dispatch]
@@ -32,6 +46,152 @@ pub trait StateStatus {
     fn reset_events(&mut self);
     fn add_time(&mut self, time: f64);
     fn time(&self) -> f64;
+    /// Running count of mismatched bonds, if the state is keeping one (see
+    /// [System::track_counts](crate::system::System::track_counts)).
+    fn mismatch_count(&self) -> Option<usize>;
+    fn set_mismatch_count(&mut self, count: Option<usize>);
+    /// Running counts of tiles of each type on the canvas, if the state is keeping them.
+    fn tile_counts(&self) -> Option<ArrayView1<'_, NumTiles>>;
+    /// Starts keeping running tile counts, for at least `ntiles` tile types, counting the tiles
+    /// currently on the canvas; or, with `None`, stops keeping them.
+    fn track_tile_counts(&mut self, ntiles: Option<usize>);
+    /// Counts of events by type and tile, if the state is keeping them (see
+    /// [System::track_event_stats](crate::system::System::track_event_stats)).
+    fn event_stats(&self) -> Option<&EventStats>;
+    fn event_stats_mut(&mut self) -> Option<&mut EventStats>;
+    /// Starts keeping event statistics, from zero, for at least `ntiles` tile types; or, with
+    /// `None`, stops keeping them.
+    fn track_event_stats(&mut self, ntiles: Option<usize>);
+}
+
+/// Counts of events that have taken place in a state, by type and by tile.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "python", pyclass)]
+pub struct EventStats {
+    /// Single-site attachments (including duples, which are counted once, by their main tile).
+    pub attachments: NumEvents,
+    /// Single-site detachments.
+    pub detachments: NumEvents,
+    /// Attachments of more than one tile at once (eg, dimers).
+    pub polymer_attachments: NumEvents,
+    /// Detachments of more than one tile at once, ie, fission events.
+    pub fission_events: NumEvents,
+    /// Tile changes (at single or multiple sites).
+    pub changes: NumEvents,
+    /// Events that were chosen, but turned out to be null: time passed without any change.
+    pub dead_events: NumEvents,
+    /// Attachments of each tile type, counting each tile of polymer attachments.
+    pub tile_attachments: Array1<NumEvents>,
+    /// Detachments of each tile type, counting each tile removed by fission.
+    pub tile_detachments: Array1<NumEvents>,
+}
+
+impl EventStats {
+    pub fn new(ntiles: usize) -> Self {
+        Self {
+            tile_attachments: Array1::zeros(ntiles),
+            tile_detachments: Array1::zeros(ntiles),
+            ..Default::default()
+        }
+    }
+
+    /// Records an event, where `detached` holds the tiles it removes (read before performing
+    /// it).  A null event is recorded as a dead event.
+    pub fn record(&mut self, event: &system::Event, detached: &[Tile]) {
+        use system::Event;
+        match event {
+            Event::None => self.dead_events += 1,
+            Event::MonomerAttachment(_, t) => {
+                self.attachments += 1;
+                self.tile_attachments[*t as usize] += 1;
+            }
+            Event::PolymerAttachment(changelist) => {
+                self.polymer_attachments += 1;
+                for (_, t) in changelist {
+                    self.tile_attachments[*t as usize] += 1;
+                }
+            }
+            Event::MonomerDetachment(_) => self.detachments += 1,
+            Event::PolymerDetachment(_) => self.fission_events += 1,
+            Event::MonomerChange(_, _) | Event::PolymerChange(_) => self.changes += 1,
+        }
+        for t in detached.iter().filter(|t| **t > 0) {
+            self.tile_detachments[*t as usize] += 1;
+        }
+    }
+
+    /// The total number of events recorded, including dead events.
+    pub fn total(&self) -> NumEvents {
+        self.attachments
+            + self.detachments
+            + self.polymer_attachments
+            + self.fission_events
+            + self.changes
+            + self.dead_events
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl EventStats {
+    #[getter]
+    fn attachments(&self) -> NumEvents {
+        self.attachments
+    }
+
+    #[getter]
+    fn detachments(&self) -> NumEvents {
+        self.detachments
+    }
+
+    #[getter]
+    fn polymer_attachments(&self) -> NumEvents {
+        self.polymer_attachments
+    }
+
+    #[getter]
+    fn fission_events(&self) -> NumEvents {
+        self.fission_events
+    }
+
+    #[getter]
+    fn changes(&self) -> NumEvents {
+        self.changes
+    }
+
+    #[getter]
+    fn dead_events(&self) -> NumEvents {
+        self.dead_events
+    }
+
+    /// Attachments of each tile type, indexed by tile number.
+    #[getter]
+    fn tile_attachments<'py>(&self, py: Python<'py>) -> &'py PyArray1<NumEvents> {
+        PyArray1::from_array(py, &self.tile_attachments)
+    }
+
+    /// Detachments of each tile type, indexed by tile number.
+    #[getter]
+    fn tile_detachments<'py>(&self, py: Python<'py>) -> &'py PyArray1<NumEvents> {
+        PyArray1::from_array(py, &self.tile_detachments)
+    }
+
+    #[pyo3(name = "total")]
+    fn py_total(&self) -> NumEvents {
+        self.total()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "EventStats(attachments={}, detachments={}, polymer_attachments={}, fission_events={}, changes={}, dead_events={})",
+            self.attachments,
+            self.detachments,
+            self.polymer_attachments,
+            self.fission_events,
+            self.changes,
+            self.dead_events
+        )
+    }
 }
 
 pub trait StateWithCreate: State + Sized {
@@ -43,22 +203,39 @@ pub trait StateWithCreate: State + Sized {
 }
 
 #[derive(Debug, Clone)]
-pub struct QuadTreeState<C: Canvas, T: StateTracker> {
-    pub rates: QuadTreeSquareArray<Rate>,
+pub struct QuadTreeState<C: Canvas, T: StateTracker, R: RateStore = QuadTreeSquareArray<Rate>> {
+    pub rates: R,
     pub canvas: C,
     ntiles: NumTiles,
     total_events: NumEvents,
     time: f64,
+    tile_counts: Option<Array1<NumTiles>>,
+    mismatch_count: Option<usize>,
+    event_stats: Option<EventStats>,
     pub tracker: T,
 }
 
-impl<C: Canvas, T: StateTracker> QuadTreeState<C, T> {
+impl<C: Canvas, T: StateTracker, R: RateStore> QuadTreeState<C, T, R> {
     pub fn recalc_ntiles(&mut self) {
-        self.ntiles = self.calc_n_tiles();
+        self.ntiles = self.canvas.calc_n_tiles();
+    }
+
+    #[inline(always)]
+    fn count_tile_change(&mut self, old_tile: Tile, new_tile: Tile) {
+        if let Some(counts) = &mut self.tile_counts {
+            if old_tile > 0 {
+                counts[old_tile as usize] -= 1;
+            }
+            if new_tile > 0 {
+                counts[new_tile as usize] += 1;
+            }
+        }
     }
 }
 
-impl<C: Canvas + CanvasCreate, T: StateTracker> State for QuadTreeState<C, T> {
+impl<C: Canvas + CanvasCreate, T: StateTracker, R: RateStore + Debug + Sync + Send> State
+    for QuadTreeState<C, T, R>
+{
     fn panicinfo(&self) -> String {
         format!(
             "{:?} {:?} {}={}",
@@ -70,9 +247,9 @@ impl<C: Canvas + CanvasCreate, T: StateTracker> State for QuadTreeState<C, T> {
     }
 }
 
-impl<C: Canvas, T: StateTracker> RateStore for QuadTreeState<C, T> {
-    fn choose_point(&self) -> (Point, Rate) {
-        self.rates.choose_point()
+impl<C: Canvas, T: StateTracker, R: RateStore> RateStore for QuadTreeState<C, T, R> {
+    fn choose_point(&self, rng: &mut SimRng) -> Result<(Point, Rate), GrowError> {
+        self.rates.choose_point(rng)
     }
 
     fn rate_at_point(&self, point: PointSafeHere) -> Rate {
@@ -91,9 +268,19 @@ impl<C: Canvas, T: StateTracker> RateStore for QuadTreeState<C, T> {
     fn total_rate(&self) -> Rate {
         self.rates.total_rate()
     }
+
+    fn resum(&mut self) {
+        self.rates.resum()
+    }
+
+    fn check_consistency(&self) -> Result<(), GrowError> {
+        self.rates.check_consistency()
+    }
 }
 
-impl<C: Canvas, T: StateTracker> Canvas for QuadTreeState<C, T> {
+impl<C: Canvas, T: StateTracker, R: RateStore + Debug + Sync + Send> Canvas
+    for QuadTreeState<C, T, R>
+{
     unsafe fn uv_pr(&self, p: Point) -> &Tile {
         self.canvas.uv_pr(p)
     }
@@ -155,6 +342,7 @@ impl<C: Canvas, T: StateTracker> Canvas for QuadTreeState<C, T> {
         if (old_tile > 0) & (*t == 0) {
             self.ntiles -= 1
         }
+        self.count_tile_change(old_tile, *t);
     }
 
     fn set_sa_countabletilearray(
@@ -175,6 +363,7 @@ impl<C: Canvas, T: StateTracker> Canvas for QuadTreeState<C, T> {
         if !should_be_counted[old_tile as usize] & should_be_counted[*t as usize] {
             self.ntiles += 1
         }
+        self.count_tile_change(old_tile, *t);
     }
 
     fn draw_size(&self) -> (u32, u32) {
@@ -208,24 +397,27 @@ impl<C: Canvas, T: StateTracker> Canvas for QuadTreeState<C, T> {
     }
 }
 
-impl<C, T> StateWithCreate for QuadTreeState<C, T>
+impl<C, T, R> StateWithCreate for QuadTreeState<C, T, R>
 where
     C: Canvas + CanvasCreate<Params = (usize, usize)>,
     T: StateTracker,
+    R: RateStore + CreateSizedRateStore + CopyNonzeroRates + Debug + Sync + Send,
 {
     type Params = (usize, usize);
 
     fn empty(params: Self::Params) -> Result<Self, GrowError> {
-        let rates: QuadTreeSquareArray<f64> =
-            QuadTreeSquareArray::new_with_size(params.0, params.1);
+        let rates = R::new_with_size(params.0, params.1);
         let canvas = C::new_sized(params)?;
         let tracker = T::default(&canvas);
-        Ok(QuadTreeState::<C, T> {
+        Ok(QuadTreeState {
             rates,
             canvas,
             ntiles: 0,
             total_events: 0,
             time: 0.,
+            tile_counts: None,
+            mismatch_count: None,
+            event_stats: None,
             tracker,
         })
     }
@@ -239,17 +431,23 @@ where
     ///
     /// If on debug, conditions should be checked (TODO)
     fn zeroed_copy_from_state_nonzero_rate(&mut self, source: &Self) -> &mut Self {
-        let max_level = self.rates.0.len() - 1; // FIXME: should not go into RateStore
-
-        self.copy_level_quad(source, max_level, (0, 0));
+        let canvas = &mut self.canvas;
+        self.rates.copy_nonzero_rates_from(&source.rates, |p| {
+            // Tile must have nonzero rate, so we only check points with nonzero rates.
+            let t = unsafe { source.canvas.uv_p(p) };
+            if t > 0 {
+                unsafe { *canvas.uvm_p(p) = t };
+            }
+        });
 
         // General housekeeping
         self.ntiles = source.ntiles;
         self.total_events = source.total_events;
+        self.tile_counts.clone_from(&source.tile_counts);
+        self.mismatch_count = source.mismatch_count;
+        self.event_stats.clone_from(&source.event_stats);
         self.tracker = source.tracker.clone();
 
-        self.rates.1 = source.rates.1;
-
         self
     }
 
@@ -258,9 +456,9 @@ where
     }
 }
 
-unsafe impl<C: Canvas, T: StateTracker> Send for QuadTreeState<C, T> {}
+unsafe impl<C: Canvas, T: StateTracker, R: RateStore + Send> Send for QuadTreeState<C, T, R> {}
 
-impl<C: Canvas, T: StateTracker> StateStatus for QuadTreeState<C, T> {
+impl<C: Canvas, T: StateTracker, R: RateStore> StateStatus for QuadTreeState<C, T, R> {
     #[inline(always)]
     fn n_tiles(&self) -> NumTiles {
         self.ntiles
@@ -286,36 +484,43 @@ impl<C: Canvas, T: StateTracker> StateStatus for QuadTreeState<C, T> {
     fn reset_events(&mut self) {
         self.total_events = 0;
     }
-}
 
-impl<C: Canvas + Canvas, T: StateTracker> QuadTreeState<C, T> {
-    fn copy_level_quad(&mut self, source: &Self, level: usize, point: (usize, usize)) -> &mut Self {
-        // FIXME: should not go into ratestore
-        let (y, x) = point;
-
-        if level > 0 {
-            for (yy, xx) in &[(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)] {
-                let z = source.rates.0[level][(*yy, *xx)];
-                if z > 0. {
-                    self.rates.0[level][(*yy, *xx)] = z;
-                    self.copy_level_quad(source, level - 1, (*yy * 2, *xx * 2));
-                }
-            }
-        } else {
-            for (yy, xx) in &[(y, x), (y, x + 1), (y + 1, x), (y + 1, x + 1)] {
-                let z = source.rates.0[level][(*yy, *xx)];
-                let t = unsafe { source.canvas.uv_p((*yy, *xx)) };
-                if z > 0. {
-                    self.rates.0[level][(*yy, *xx)] = z;
-                    if t > 0 {
-                        // Tile must have nonzero rate, so we only check if the rate is nonzero.
-                        let v = unsafe { self.canvas.uvm_p((*yy, *xx)) };
-                        *v = t;
-                    }
+    fn mismatch_count(&self) -> Option<usize> {
+        self.mismatch_count
+    }
+
+    fn set_mismatch_count(&mut self, count: Option<usize>) {
+        self.mismatch_count = count;
+    }
+
+    fn tile_counts(&self) -> Option<ArrayView1<'_, NumTiles>> {
+        self.tile_counts.as_ref().map(|c| c.view())
+    }
+
+    fn track_tile_counts(&mut self, ntiles: Option<usize>) {
+        self.tile_counts = ntiles.map(|ntiles| {
+            let arr = self.canvas.raw_array();
+            let max_tile = arr.iter().max().map_or(0, |t| *t as usize);
+            let mut counts = Array1::zeros(ntiles.max(max_tile + 1));
+            for t in arr.iter() {
+                if *t > 0 {
+                    counts[*t as usize] += 1;
                 }
             }
-        };
-        self
+            counts
+        });
+    }
+
+    fn event_stats(&self) -> Option<&EventStats> {
+        self.event_stats.as_ref()
+    }
+
+    fn event_stats_mut(&mut self) -> Option<&mut EventStats> {
+        self.event_stats.as_mut()
+    }
+
+    fn track_event_stats(&mut self, ntiles: Option<usize>) {
+        self.event_stats = ntiles.map(EventStats::new);
     }
 }
 
@@ -328,10 +533,11 @@ where
     fn record_event(&mut self, event: &system::Event) -> &mut Self;
 }
 
-impl<C, T> StateTracked<T> for QuadTreeState<C, T>
+impl<C, T, R> StateTracked<T> for QuadTreeState<C, T, R>
 where
     C: Canvas + Canvas,
     T: StateTracker,
+    R: RateStore,
 {
     fn set_tracker(&mut self, tracker: T) -> &mut Self {
         self.tracker = tracker;
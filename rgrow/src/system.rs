@@ -1,6 +1,5 @@
 use enum_dispatch::enum_dispatch;
 use ndarray::prelude::*;
-use rand::thread_rng;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
@@ -10,8 +9,14 @@ use crate::canvas::CanvasPeriodic;
 use crate::canvas::CanvasSquare;
 use crate::canvas::CanvasTube;
 use crate::ffs::BoxedFFSResult;
+use crate::ffs::CommittorAnalysis;
+use crate::ffs::CommittorConfig;
+use crate::ffs::FFSResult;
 use crate::ffs::FFSRun;
 use crate::ffs::FFSRunConfig;
+use crate::ffs::FFSRunData;
+use crate::ffs::StructureAnalysis;
+use crate::ffs::StructureConfig;
 use crate::models::atam::ATAM;
 
 use crate::models::ktam::KTAM;
@@ -21,15 +26,19 @@ use crate::state::QuadTreeState;
 use crate::state::State;
 use crate::state::StateEnum;
 use crate::tileset::CanvasType;
+use crate::umbrella::FreeEnergyProfile;
+use crate::umbrella::UmbrellaConfig;
 
 use crate::{
-    base::GrowError, base::NumEvents, base::NumTiles, canvas::PointSafeHere, state::StateWithCreate,
+    base::new_sim_rng, base::GrowError, base::HashMapType, base::NumEvents, base::NumTiles,
+    base::SimRng, canvas::PointSafeHere, state::StateWithCreate,
 };
 
 use super::base::{Point, Rate, Tile};
 use crate::canvas::PointSafe2;
 
 use std::any::Any;
+use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 
 use std::sync::Arc;
@@ -65,6 +74,17 @@ pub enum StepOutcome {
     ZeroRate,
 }
 
+/// The result of [System::take_leap].
+#[derive(Debug)]
+pub struct LeapOutcome {
+    /// Number of (non-dead) events performed.
+    pub events: NumEvents,
+    /// Simulated time that passed.
+    pub time: f64,
+    /// Whether the leap stopped because it reached its maximum time.
+    pub reached_max_time: bool,
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "python", pyclass)]
 pub enum NeededUpdate {
@@ -77,7 +97,7 @@ thread_local! {
     pub static APP: fltk::app::App = app::App::default()
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
 #[cfg_attr(feature = "python", pyclass)]
 pub struct EvolveBounds {
     /// Stop if this number of events has taken place during this evolve call.
@@ -94,18 +114,32 @@ pub struct EvolveBounds {
     pub size_max: Option<NumTiles>,
     /// Stop after this amount of (real) time has passed.
     pub for_wall_time: Option<Duration>,
+    /// If set, evolve with approximate leaps (see [System::take_leap]) rather than exact
+    /// single steps, allowing stale rates amounting to at most this fraction of the total
+    /// rate within each leap.  Size bounds may be overshot by up to one leap.
+    pub leap_tolerance: Option<f64>,
+    /// If set, evolve the state in parallel domains of about this size on a side (see
+    /// [crate::parallel]).  Event and size bounds may be overshot by up to one cycle.
+    pub parallel_domain_size: Option<usize>,
+    /// For parallel evolution, the average number of events per domain in each phase.  Smaller
+    /// values are more accurate but slower.  Defaults to [crate::parallel::DEFAULT_PHASE_EVENTS].
+    pub parallel_phase_events: Option<f64>,
 }
 
 #[cfg(feature = "python")]
 #[pymethods]
 impl EvolveBounds {
     #[new]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         for_events: Option<NumEvents>,
         for_time: Option<f64>,
         size_min: Option<NumTiles>,
         size_max: Option<NumTiles>,
         for_wall_time: Option<f64>,
+        leap_tolerance: Option<f64>,
+        parallel_domain_size: Option<usize>,
+        parallel_phase_events: Option<f64>,
     ) -> Self {
         Self {
             for_events,
@@ -113,13 +147,16 @@ impl EvolveBounds {
             size_min,
             size_max,
             for_wall_time: for_wall_time.map(Duration::from_secs_f64),
+            leap_tolerance,
+            parallel_domain_size,
+            parallel_phase_events,
             ..Default::default()
         }
     }
 
     pub fn __repr__(&self) -> String {
         format!(
-            "EvolveBounds(events={}, time={}, size_min={}, size_max={}, wall_time={})",
+            "EvolveBounds(events={}, time={}, size_min={}, size_max={}, wall_time={}, leap_tolerance={}, parallel_domain_size={}, parallel_phase_events={})",
             self.for_events
                 .map_or("None".to_string(), |v| format!("{v:?}")),
             self.for_time
@@ -129,6 +166,12 @@ impl EvolveBounds {
             self.size_max
                 .map_or("None".to_string(), |v| format!("{v:?}")),
             self.for_wall_time
+                .map_or("None".to_string(), |v| format!("{v:?}")),
+            self.leap_tolerance
+                .map_or("None".to_string(), |v| format!("{v:?}")),
+            self.parallel_domain_size
+                .map_or("None".to_string(), |v| format!("{v:?}")),
+            self.parallel_phase_events
                 .map_or("None".to_string(), |v| format!("{v:?}"))
         )
     }
@@ -167,6 +210,26 @@ impl EvolveBounds {
         self.for_events = Some(events);
         self
     }
+
+    /// The bounds with `total_events` replaced by the equivalent `for_events`, for a state that
+    /// has already had `events` events.
+    pub fn with_total_events_as_for_events(mut self, events: NumEvents) -> Self {
+        if let Some(total) = self.total_events.take() {
+            let remaining = total.saturating_sub(events);
+            self.for_events = Some(self.for_events.map_or(remaining, |e| e.min(remaining)));
+        }
+        self
+    }
+
+    pub fn leap_tolerance(mut self, tolerance: f64) -> Self {
+        self.leap_tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn parallel_domain_size(mut self, size: usize) -> Self {
+        self.parallel_domain_size = Some(size);
+        self
+    }
 }
 
 #[cfg_attr(feature = "python", pyclass)]
@@ -195,6 +258,40 @@ pub struct DimerInfo {
     pub equilibrium_conc: f64,
 }
 
+/// A small assembly, from [SystemWithDimers::calc_nmers].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct NmerInfo {
+    /// The tiles of the assembly, as (row, column, tile), relative to the top left corner of its
+    /// bounding box.  Duples appear as both of their parts.
+    pub tiles: Vec<(usize, usize, Tile)>,
+    /// The number of tiles, counting each duple once.
+    pub size: NumTiles,
+    /// The total energy of bonds within the assembly, in kT.
+    pub bond_energy: f64,
+    /// The equilibrium rate (in M/s) at which the assembly forms by attachment of a monomer to
+    /// an assembly of one fewer tile, summed over all ways of doing so, with all smaller
+    /// assemblies at equilibrium with free monomers.  For dimers, this counts attachment to
+    /// either tile.
+    pub formation_rate: Rate,
+    /// The equilibrium concentration (in M) of the assembly, with free monomers at their set
+    /// concentrations.
+    pub equilibrium_conc: f64,
+}
+
+impl NmerInfo {
+    /// The assembly as an array of tiles, in its bounding box.
+    pub fn config(&self) -> Array2<Tile> {
+        let rows = self.tiles.iter().map(|t| t.0 + 1).max().unwrap_or(0);
+        let cols = self.tiles.iter().map(|t| t.1 + 1).max().unwrap_or(0);
+        let mut config = Array2::zeros((rows, cols));
+        for &(r, c, t) in &self.tiles {
+            config[(r, c)] = t;
+        }
+        config
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 #[cfg_attr(feature = "python", pyclass)]
 pub enum ChunkHandling {
@@ -243,6 +340,98 @@ impl TryFrom<&str> for ChunkSize {
     }
 }
 
+/// Number of events between exact resummations of the rate store in [System::evolve], bounding
+/// the floating-point drift of its incrementally-updated sums.
+pub const RATE_RESUM_INTERVAL: NumEvents = 1 << 24;
+
+/// Exactly resums the state's rate store.  In debug builds, stored rates are first checked with
+/// [System::validate_rates], and any inconsistency is logged rather than treated as an error, as
+/// drift over a long run can exceed its tolerance.
+fn resum_rates<Sy: System + ?Sized, St: State + ?Sized>(system: &Sy, state: &mut St) {
+    if cfg!(debug_assertions) {
+        if let Err(err) = system.validate_rates(state) {
+            log::warn!("Resumming rates: {err}");
+        }
+    }
+    state.resum();
+}
+
+/// Exact rates calculated during [System::take_leap], held until the rate store is updated at
+/// its end.
+#[derive(Default)]
+struct HeldRates {
+    rates: Vec<(PointSafeHere, Rate)>,
+    index: HashMapType<Point, usize>,
+    /// Summed absolute difference between held and stored rates.
+    error: Rate,
+    /// Summed signed difference between held and stored rates.
+    drift: Rate,
+}
+
+impl HeldRates {
+    fn hold_all<St: State + ?Sized>(
+        &mut self,
+        state: &St,
+        points: &[PointSafeHere],
+        rates: &[Rate],
+    ) {
+        for (&q, &rate) in points.iter().zip(rates) {
+            let stored = state.rate_at_point(q);
+            match self.index.entry(q.0) {
+                Entry::Occupied(e) => {
+                    let old = &mut self.rates[*e.get()].1;
+                    self.error -= (*old - stored).abs();
+                    self.drift -= *old - stored;
+                    *old = rate;
+                }
+                Entry::Vacant(e) => {
+                    e.insert(self.rates.len());
+                    self.rates.push((q, rate));
+                }
+            }
+            self.error += (rate - stored).abs();
+            self.drift += rate - stored;
+        }
+    }
+
+    /// Whether the stored rate at a point differs from its held rate.
+    fn is_stale<St: State + ?Sized>(&self, state: &St, point: Point) -> bool {
+        self.index.get(&point).is_some_and(|&i| {
+            let (q, rate) = self.rates[i];
+            rate != state.rate_at_point(q)
+        })
+    }
+}
+
+/// A flicker being resolved in [System::take_leap]: the point of its first event, the tiles
+/// around it before that event, and the rates around it after, not yet held.
+struct Flicker {
+    point: PointSafe2,
+    tiles_before: [Tile; 13],
+    points: [PointSafeHere; 13],
+    rates: [Rate; 13],
+}
+
+/// The points whose rates may change after an event at `p`, for [System::take_leap]: those
+/// within two sites of it.
+fn leap_diamond<St: State + ?Sized>(state: &St, p: PointSafe2) -> [PointSafeHere; 13] {
+    [
+        PointSafeHere(p.0),
+        state.move_sa_n(p),
+        state.move_sa_e(p),
+        state.move_sa_s(p),
+        state.move_sa_w(p),
+        state.move_sa_nn(p),
+        state.move_sa_ne(p),
+        state.move_sa_ee(p),
+        state.move_sa_se(p),
+        state.move_sa_ss(p),
+        state.move_sa_sw(p),
+        state.move_sa_ww(p),
+        state.move_sa_nw(p),
+    ]
+}
+
 pub trait System: Debug + Sync + Send + TileBondInfo {
     fn new_state<St: StateWithCreate + State>(&self, params: St::Params) -> Result<St, GrowError> {
         let mut new_state = St::empty(params)?;
@@ -260,22 +449,197 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
         &self,
         state: &mut St,
         max_time_step: f64,
-    ) -> StepOutcome {
-        let time_step = -f64::ln(thread_rng().gen()) / state.total_rate();
+        rng: &mut SimRng,
+    ) -> Result<StepOutcome, GrowError> {
+        let time_step = -f64::ln(rng.gen()) / state.total_rate();
         if time_step > max_time_step {
             state.add_time(max_time_step);
-            return StepOutcome::NoEventIn(max_time_step);
+            return Ok(StepOutcome::NoEventIn(max_time_step));
         }
-        let (point, remainder) = state.choose_point(); // todo: resultify
+        let (point, remainder) = match state.choose_point(rng) {
+            Ok(x) => x,
+            Err(_) => {
+                // Accumulated floating-point error can leave the rate store's sums
+                // inconsistent; resum exactly and try once more before giving up.
+                state.resum();
+                state.choose_point(rng)?
+            }
+        };
         let event = self.choose_event_at_point(state, PointSafe2(point), remainder); // FIXME
+        self.record_event_stats(state, &event);
         if let Event::None = event {
-            return StepOutcome::DeadEventAt(time_step);
+            state.add_time(time_step);
+            return Ok(StepOutcome::DeadEventAt(time_step));
         }
 
-        self.perform_event(state, &event);
+        self.perform_event_with_counts(state, &event);
         self.update_after_event(state, &event);
         state.add_time(time_step);
-        StepOutcome::HadEventAt(time_step)
+        Ok(StepOutcome::HadEventAt(time_step))
+    }
+
+    /// Takes an approximate leap: a batch of events drawn from the rates stored at the start of
+    /// the leap, followed by a single update of all rates the batch changed.
+    ///
+    /// After each event, the exact rates at points within two sites of it are calculated
+    /// (event rates are assumed to depend only on tiles within two sites, as in all models
+    /// here) and held until the end of the leap.  Error is controlled by `tolerance` in two
+    /// ways:
+    ///
+    /// - The leap ends once the summed difference between stored and held rates exceeds
+    ///   `tolerance` times the total rate at the start of the leap, so events are drawn from a
+    ///   distribution within about `tolerance` of the exact one in total variation, with time
+    ///   steps at a total rate within the same relative error.  A draw at a point whose held
+    ///   rate differs from its stored one also ends the leap, and is discarded along with its
+    ///   time step.
+    /// - Equilibrium flickers are resolved in place: if the rate at the point of a monomer
+    ///   event is at least 1/`tolerance` times the summed rates of the other points around it,
+    ///   the next event among those points is performed immediately, chosen from their exact
+    ///   rates.  This is usually the reverse of the first event, but can be, for example, an
+    ///   attachment that locks a weakly-bound tile in place.  This takes no time: events
+    ///   further away continue concurrently, and can only interact with the flicker
+    ///   indirectly, through rates around it.  When a weakly-bound tile
+    ///   attaches and then detaches, nothing has changed, so no rates need to be held and the
+    ///   leap continues.
+    ///
+    /// The leap also ends after `max_events` events or at `max_time`.  With a `tolerance` of
+    /// zero, each leap is a single exact step, apart from flickers at points with no other
+    /// events around them.
+    ///
+    /// For large assemblies near equilibrium, most events are such flickers, and resolving
+    /// them without touching the rate store, along with updating it once per leap, is where
+    /// the speedup comes from.
+    fn take_leap<St: State + ?Sized>(
+        &self,
+        state: &mut St,
+        max_time: f64,
+        max_events: NumEvents,
+        tolerance: f64,
+        rng: &mut SimRng,
+    ) -> Result<LeapOutcome, GrowError> {
+        let start_rate = state.total_rate();
+        let max_error = tolerance * start_rate;
+        let mut outcome = LeapOutcome {
+            events: 0,
+            time: 0.,
+            reached_max_time: false,
+        };
+        let mut held = HeldRates::default();
+
+        'leap: while outcome.events < max_events {
+            let time_step = -f64::ln(rng.gen()) / start_rate;
+            if outcome.time + time_step > max_time {
+                outcome.reached_max_time = true;
+                break;
+            }
+            let (point, remainder) = match state.choose_point(rng) {
+                Ok(x) => x,
+                Err(e) if held.rates.is_empty() => {
+                    // As in take_single_step: resum and retry once.
+                    state.resum();
+                    match state.choose_point(rng) {
+                        Ok(x) => x,
+                        Err(_) => return Err(e),
+                    }
+                }
+                Err(_) => break,
+            };
+            if held.is_stale(state, point) {
+                break;
+            }
+            outcome.time += time_step;
+            state.add_time(time_step);
+            let mut event = self.choose_event_at_point(state, PointSafe2(point), remainder);
+
+            let mut flicker: Option<Flicker> = None;
+            loop {
+                self.record_event_stats(state, &event);
+                if let Event::None = event {
+                    if let Some(f) = flicker {
+                        held.hold_all(state, &f.points, &f.rates);
+                    }
+                    continue 'leap;
+                }
+                let single = match &event {
+                    Event::MonomerAttachment(p, _)
+                    | Event::MonomerDetachment(p)
+                    | Event::MonomerChange(p, _) => Some(*p),
+                    _ => None,
+                };
+                let tiles_before = single.map(|p| leap_diamond(state, p).map(|q| state.v_sh(q)));
+                self.perform_event_with_counts(state, &event);
+                outcome.events += 1;
+
+                if let Some(f) = flicker.take() {
+                    if single == Some(f.point)
+                        && leap_diamond(state, f.point).map(|q| state.v_sh(q)) == f.tiles_before
+                    {
+                        // The flicker left the tiles around it as they were, so the held rates
+                        // are still correct.
+                        continue 'leap;
+                    }
+                    held.hold_all(state, &f.points, &f.rates);
+                }
+
+                let Some(p) = single else {
+                    let changed = match &event {
+                        Event::PolymerAttachment(changelist) | Event::PolymerChange(changelist) => {
+                            changelist.iter().map(|(p, _)| *p).collect()
+                        }
+                        Event::PolymerDetachment(changelist) => changelist.clone(),
+                        _ => unreachable!(),
+                    };
+                    for p in changed {
+                        let points = leap_diamond(state, p);
+                        let rates = points.map(|q| self.event_rate_at_point(state, q));
+                        held.hold_all(state, &points, &rates);
+                    }
+                    break;
+                };
+
+                let points = leap_diamond(state, p);
+                let rates = points.map(|q| self.event_rate_at_point(state, q));
+                let local_rate = rates.iter().sum::<Rate>();
+                if rates[0] == 0.
+                    || rates[0] * tolerance < local_rate - rates[0]
+                    || outcome.events >= max_events
+                {
+                    held.hold_all(state, &points, &rates);
+                    break;
+                }
+                // The next event around the flicker, chosen from the exact local rates.
+                let mut acc = rng.gen::<f64>() * local_rate;
+                let mut chosen = 0;
+                for (i, &rate) in rates.iter().enumerate() {
+                    if rate > 0. {
+                        chosen = i;
+                        if acc < rate {
+                            break;
+                        }
+                        acc -= rate;
+                    }
+                }
+                let acc = acc.min(rates[chosen] * (1. - f64::EPSILON));
+                flicker = Some(Flicker {
+                    point: p,
+                    tiles_before: tiles_before.unwrap(),
+                    points,
+                    rates,
+                });
+                event = self.choose_event_at_point(state, PointSafe2(points[chosen].0), acc);
+            }
+
+            if held.error > max_error {
+                break;
+            }
+        }
+
+        if outcome.reached_max_time {
+            state.add_time(max_time - outcome.time);
+            outcome.time = max_time;
+        }
+        state.update_multiple(&held.rates);
+        Ok(outcome)
     }
 
     fn evolve<St: State + ?Sized>(
@@ -283,14 +647,31 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
         state: &mut St,
         bounds: EvolveBounds,
     ) -> Result<EvolveOutcome, GrowError> {
-        let mut events = 0;
+        self.evolve_with_rng(state, bounds, &mut new_sim_rng())
+    }
 
-        if bounds.total_events.is_some() {
-            return Err(GrowError::NotImplemented(
-                "Total events bound is not implemented".to_string(),
-            ));
+    /// As [System::evolve], but drawing random numbers from `rng`, so that a seeded generator
+    /// gives a reproducible trajectory.
+    fn evolve_with_rng<St: State + ?Sized>(
+        &self,
+        state: &mut St,
+        bounds: EvolveBounds,
+        rng: &mut SimRng,
+    ) -> Result<EvolveOutcome, GrowError> {
+        let bounds = bounds.with_total_events_as_for_events(state.total_events());
+
+        if let Some(size) = bounds.parallel_domain_size {
+            #[cfg(feature = "use_rayon")]
+            return crate::parallel::evolve_in_domains(self, state, bounds, size, rng);
+            #[cfg(not(feature = "use_rayon"))]
+            return Err(GrowError::NotImplemented(format!(
+                "Parallel evolution (domain size {size}) needs the use_rayon feature"
+            )));
         }
 
+        let mut events = 0;
+        let mut events_since_resum = 0;
+
         let mut rtime = match bounds.for_time {
             Some(t) => t,
             None => f64::INFINITY,
@@ -303,6 +684,10 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
         let start_time = bounds.for_wall_time.map(|_| std::time::Instant::now());
 
         loop {
+            if events_since_resum >= RATE_RESUM_INTERVAL {
+                resum_rates(self, state);
+                events_since_resum = 0;
+            }
             if bounds.size_min.is_some_and(|ms| state.n_tiles() <= ms) {
                 return Ok(EvolveOutcome::ReachedSizeMin);
             } else if bounds.size_max.is_some_and(|ms| state.n_tiles() >= ms) {
@@ -319,10 +704,22 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
             } else if state.total_rate() == 0. {
                 return Ok(EvolveOutcome::ReachedZeroRate);
             }
-            let out = self.take_single_step(state, rtime);
+            if let Some(tolerance) = bounds.leap_tolerance {
+                let max_events = bounds.for_events.map_or(NumEvents::MAX, |e| e - events);
+                let out = self.take_leap(state, rtime, max_events, tolerance, rng)?;
+                events += out.events;
+                events_since_resum += out.events;
+                rtime -= out.time;
+                if out.reached_max_time {
+                    return Ok(EvolveOutcome::ReachedTimeMax);
+                }
+                continue;
+            }
+            let out = self.take_single_step(state, rtime, rng)?;
             match out {
                 StepOutcome::HadEventAt(t) => {
                     events += 1;
+                    events_since_resum += 1;
                     rtime -= t;
                 }
                 StepOutcome::NoEventIn(_) => return Ok(EvolveOutcome::ReachedTimeMax),
@@ -369,7 +766,7 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
     ) -> &Self {
         let event = Event::MonomerChange(point, tile);
 
-        self.perform_event(state, &event)
+        self.perform_event_with_counts(state, &event)
             .update_after_event(state, &event);
 
         self
@@ -389,7 +786,7 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
                 .map(|(p, t)| (PointSafe2(*p), *t))
                 .collect(),
         );
-        self.perform_event(state, &event)
+        self.perform_event_with_counts(state, &event)
             .update_after_event(state, &event);
         self
     }
@@ -401,6 +798,106 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
         Ok(())
     }
 
+    /// Starts keeping running counts of mismatches and of tiles of each type on the state, which
+    /// are then updated as events are performed, and available through
+    /// [StateStatus](crate::state::StateStatus).
+    fn track_counts<St: State + ?Sized>(&self, state: &mut St) {
+        state.track_tile_counts(Some(self.tile_names().len()));
+        state.set_mismatch_count(Some(self.calc_mismatches(state)));
+    }
+
+    /// Starts keeping statistics of events, by type and tile, on the state, from zero.  Events
+    /// taken by [System::evolve] are then recorded, and available through
+    /// [StateStatus::event_stats](crate::state::StateStatus::event_stats).
+    fn track_event_stats<St: State + ?Sized>(&self, state: &mut St) {
+        state.track_event_stats(Some(self.tile_names().len()));
+    }
+
+    /// Records an event that is about to be performed (or a null event) in the state's event
+    /// statistics, if it is keeping them.
+    fn record_event_stats<St: State + ?Sized>(&self, state: &mut St, event: &Event) {
+        if state.event_stats().is_none() {
+            return;
+        }
+        let detached = match event {
+            Event::MonomerDetachment(p) => vec![state.tile_at_point(*p)],
+            Event::PolymerDetachment(changelist) => {
+                changelist.iter().map(|p| state.tile_at_point(*p)).collect()
+            }
+            _ => Vec::new(),
+        };
+        if let Some(stats) = state.event_stats_mut() {
+            stats.record(event, &detached);
+        }
+    }
+
+    /// Performs an event, updating the state's running mismatch count if it is keeping one.
+    /// Mismatches are counted (as in [System::calc_mismatches]) at the points within two steps of
+    /// each point of the event, before and after performing it.
+    fn perform_event_with_counts<St: State + ?Sized>(
+        &self,
+        state: &mut St,
+        event: &Event,
+    ) -> &Self {
+        let Some(count) = state.mismatch_count() else {
+            return self.perform_event(state, event);
+        };
+
+        let mut points = Vec::new();
+        let mut add_around = |p: &PointSafe2| {
+            for q in [
+                PointSafeHere(p.0),
+                state.move_sa_n(*p),
+                state.move_sa_e(*p),
+                state.move_sa_s(*p),
+                state.move_sa_w(*p),
+                state.move_sa_nn(*p),
+                state.move_sa_ne(*p),
+                state.move_sa_ee(*p),
+                state.move_sa_se(*p),
+                state.move_sa_nw(*p),
+            ] {
+                if state.inbounds(q.0) {
+                    points.push(PointSafe2(q.0));
+                }
+            }
+        };
+        match event {
+            Event::None => (),
+            Event::MonomerAttachment(p, _)
+            | Event::MonomerDetachment(p)
+            | Event::MonomerChange(p, _) => add_around(p),
+            Event::PolymerAttachment(changelist) | Event::PolymerChange(changelist) => {
+                changelist.iter().for_each(|(p, _)| add_around(p))
+            }
+            Event::PolymerDetachment(changelist) => changelist.iter().for_each(add_around),
+        }
+        points.sort_unstable_by_key(|p| p.0);
+        points.dedup_by_key(|p| p.0);
+
+        // Only south and west mismatches are counted at each point, so each bond is counted once.
+        let counted = |state: &St| -> usize {
+            points
+                .iter()
+                .map(|p| {
+                    let code = self.mismatch_code_at_point(state, *p);
+                    ((code & 0b10) >> 1) + (code & 0b01)
+                })
+                .sum()
+        };
+
+        let before = counted(state);
+        self.perform_event(state, event);
+        let after = counted(state);
+        let updated = (count + after) as isize - before as isize;
+        debug_assert!(
+            updated >= 0,
+            "tracked mismatch count went negative; was it recomputed after a parameter change?"
+        );
+        state.set_mismatch_count(Some(updated.max(0) as usize));
+        self
+    }
+
     fn perform_event<St: State + ?Sized>(&self, state: &mut St, event: &Event) -> &Self {
         //state.record_event(&event);
         match event {
@@ -446,12 +943,36 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
     /// Returns an array of mismatch locations.  At each point, mismatches are designated by 8*N+4*E+2*S+1*W.
     fn calc_mismatch_locations<St: State + ?Sized>(&self, state: &St) -> Array2<usize>;
 
+    /// Returns the mismatches of the tile at a single point, designated as in [System::calc_mismatch_locations].
+    fn mismatch_code_at_point<St: State + ?Sized>(&self, state: &St, p: PointSafe2) -> usize;
+
     fn calc_mismatches<St: State + ?Sized>(&self, state: &St) -> usize {
         let mut arr = self.calc_mismatch_locations(state);
         arr.map_inplace(|x| *x = (*x & 0b01) + ((*x & 0b10) / 2));
         arr.sum()
     }
 
+    /// Checks that the state's rate store is internally consistent, and that the rate stored at
+    /// each point agrees with [System::event_rate_at_point].  This is slow, and intended for
+    /// debugging and validation.
+    fn validate_rates<St: State + ?Sized>(&self, state: &St) -> Result<(), GrowError> {
+        state.check_consistency()?;
+        for r in 0..state.nrows() {
+            for c in 0..state.ncols() {
+                let p = PointSafeHere((r, c));
+                let stored = state.rate_at_point(p);
+                let exact = self.event_rate_at_point(state, p);
+                if (stored - exact).abs() > 1e-9 * stored.abs().max(exact.abs()) {
+                    return Err(GrowError::RateStoreInconsistent(format!(
+                        "rate at point {:?} is {stored:?}, but the system calculates {exact:?}",
+                        (r, c)
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn update_points<St: State + ?Sized>(&self, state: &mut St, points: &[PointSafeHere]) {
         let p = points
             .iter()
@@ -477,6 +998,12 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
         };
 
         self.update_points(state, &all_points);
+
+        // Parameter changes can change which bonds are mismatches.
+        if state.mismatch_count().is_some() {
+            let count = self.calc_mismatches(state);
+            state.set_mismatch_count(Some(count));
+        }
     }
 
     fn set_param(&mut self, _name: &str, _value: Box<dyn Any>) -> Result<NeededUpdate, GrowError> {
@@ -593,7 +1120,9 @@ pub trait System: Debug + Sync + Send + TileBondInfo {
                 state.time(),
                 state.total_events(),
                 state.n_tiles(),
-                self.calc_mismatches(state) // FIXME: should not recalculate
+                state
+                    .mismatch_count()
+                    .unwrap_or_else(|| self.calc_mismatches(state))
             ));
 
             app::flush();
@@ -643,6 +1172,12 @@ pub trait DynSystem: Sync + Send + TileBondInfo {
     fn calc_mismatches(&self, state: &StateEnum) -> usize;
     fn calc_mismatch_locations(&self, state: &StateEnum) -> Array2<usize>;
 
+    fn validate_rates(&self, state: &StateEnum) -> Result<(), GrowError>;
+
+    fn track_counts(&self, state: &mut StateEnum);
+
+    fn track_event_stats(&self, state: &mut StateEnum);
+
     fn set_param(&mut self, name: &str, value: Box<dyn Any>) -> Result<NeededUpdate, GrowError>;
     fn get_param(&self, name: &str) -> Result<Box<dyn Any>, GrowError>;
 
@@ -655,6 +1190,49 @@ pub trait DynSystem: Sync + Send + TileBondInfo {
         config: &FFSRunConfig,
         canvas_type: Option<CanvasType>,
     ) -> Result<BoxedFFSResult, GrowError>;
+
+    /// Runs FFS from an ensemble of starting configurations (see [FFSRun::create_from_states]).
+    fn run_ffs_from_configs(
+        &mut self,
+        configs: &[Array2<Tile>],
+        config: &FFSRunConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<BoxedFFSResult, GrowError>;
+
+    /// Resumes or extends a saved FFS run made with this system (see [FFSRun::resume]).
+    fn resume_ffs(
+        &mut self,
+        data: &FFSRunData,
+        config: &FFSRunConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<BoxedFFSResult, GrowError>;
+
+    /// Estimates committors for the configurations of an FFS run made with this system (see
+    /// [CommittorAnalysis::calculate]).
+    fn ffs_committor(
+        &self,
+        result: &dyn FFSResult,
+        config: &CommittorConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<CommittorAnalysis, GrowError>;
+
+    /// Classifies the nucleated assemblies of an FFS run made with this system into target
+    /// structures, with the nucleation rate of each (see [StructureAnalysis::calculate]).
+    fn ffs_structures(
+        &self,
+        result: &dyn FFSResult,
+        config: &StructureConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<StructureAnalysis, GrowError>;
+
+    /// Samples the free energy landscape of an assembly by umbrella sampling, starting from
+    /// `start` if given (see [FreeEnergyProfile::calculate]).
+    fn free_energy_profile(
+        &self,
+        config: &UmbrellaConfig,
+        start: Option<&Array2<Tile>>,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<FreeEnergyProfile, GrowError>;
 }
 
 impl<S: System + SystemWithDimers> DynSystem for S {
@@ -700,6 +1278,18 @@ impl<S: System + SystemWithDimers> DynSystem for S {
         self.calc_mismatch_locations(state)
     }
 
+    fn validate_rates(&self, state: &StateEnum) -> Result<(), GrowError> {
+        self.validate_rates(state)
+    }
+
+    fn track_counts(&self, state: &mut StateEnum) {
+        self.track_counts(state)
+    }
+
+    fn track_event_stats(&self, state: &mut StateEnum) {
+        self.track_event_stats(state)
+    }
+
     fn set_param(&mut self, name: &str, value: Box<dyn Any>) -> Result<NeededUpdate, GrowError> {
         self.set_param(name, value)
     }
@@ -737,6 +1327,131 @@ impl<S: System + SystemWithDimers> DynSystem for S {
         }
     }
 
+    fn run_ffs_from_configs(
+        &mut self,
+        configs: &[Array2<Tile>],
+        config: &FFSRunConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<BoxedFFSResult, GrowError> {
+        match canvas_type.unwrap_or(CanvasType::Periodic) {
+            CanvasType::Square => {
+                let run =
+                    FFSRun::<QuadTreeState<CanvasSquare, NullStateTracker>>::create_from_configs(
+                        self, configs, config,
+                    )?;
+                Ok(BoxedFFSResult(Arc::new(Box::new(run))))
+            }
+            CanvasType::Periodic => {
+                let run =
+                    FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::create_from_configs(
+                        self, configs, config,
+                    )?;
+                Ok(BoxedFFSResult(Arc::new(Box::new(run))))
+            }
+            CanvasType::Tube => {
+                let run =
+                    FFSRun::<QuadTreeState<CanvasTube, NullStateTracker>>::create_from_configs(
+                        self, configs, config,
+                    )?;
+                Ok(BoxedFFSResult(Arc::new(Box::new(run))))
+            }
+        }
+    }
+
+    fn resume_ffs(
+        &mut self,
+        data: &FFSRunData,
+        config: &FFSRunConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<BoxedFFSResult, GrowError> {
+        match canvas_type.unwrap_or(CanvasType::Periodic) {
+            CanvasType::Square => {
+                let run = FFSRun::<QuadTreeState<CanvasSquare, NullStateTracker>>::resume(
+                    self, data, config,
+                )?;
+                Ok(BoxedFFSResult(Arc::new(Box::new(run))))
+            }
+            CanvasType::Periodic => {
+                let run = FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::resume(
+                    self, data, config,
+                )?;
+                Ok(BoxedFFSResult(Arc::new(Box::new(run))))
+            }
+            CanvasType::Tube => {
+                let run = FFSRun::<QuadTreeState<CanvasTube, NullStateTracker>>::resume(
+                    self, data, config,
+                )?;
+                Ok(BoxedFFSResult(Arc::new(Box::new(run))))
+            }
+        }
+    }
+
+    fn ffs_committor(
+        &self,
+        result: &dyn FFSResult,
+        config: &CommittorConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<CommittorAnalysis, GrowError> {
+        match canvas_type.unwrap_or(CanvasType::Periodic) {
+            CanvasType::Square => CommittorAnalysis::calculate::<
+                QuadTreeState<CanvasSquare, NullStateTracker>,
+                _,
+            >(self, result, config),
+            CanvasType::Periodic => CommittorAnalysis::calculate::<
+                QuadTreeState<CanvasPeriodic, NullStateTracker>,
+                _,
+            >(self, result, config),
+            CanvasType::Tube => CommittorAnalysis::calculate::<
+                QuadTreeState<CanvasTube, NullStateTracker>,
+                _,
+            >(self, result, config),
+        }
+    }
+
+    fn ffs_structures(
+        &self,
+        result: &dyn FFSResult,
+        config: &StructureConfig,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<StructureAnalysis, GrowError> {
+        match canvas_type.unwrap_or(CanvasType::Periodic) {
+            CanvasType::Square => StructureAnalysis::calculate::<
+                QuadTreeState<CanvasSquare, NullStateTracker>,
+                _,
+            >(self, result, config),
+            CanvasType::Periodic => StructureAnalysis::calculate::<
+                QuadTreeState<CanvasPeriodic, NullStateTracker>,
+                _,
+            >(self, result, config),
+            CanvasType::Tube => StructureAnalysis::calculate::<
+                QuadTreeState<CanvasTube, NullStateTracker>,
+                _,
+            >(self, result, config),
+        }
+    }
+
+    fn free_energy_profile(
+        &self,
+        config: &UmbrellaConfig,
+        start: Option<&Array2<Tile>>,
+        canvas_type: Option<CanvasType>,
+    ) -> Result<FreeEnergyProfile, GrowError> {
+        match canvas_type.unwrap_or(CanvasType::Periodic) {
+            CanvasType::Square => FreeEnergyProfile::from_config::<
+                QuadTreeState<CanvasSquare, NullStateTracker>,
+                _,
+            >(self, start, config),
+            CanvasType::Periodic => FreeEnergyProfile::from_config::<
+                QuadTreeState<CanvasPeriodic, NullStateTracker>,
+                _,
+            >(self, start, config),
+            CanvasType::Tube => FreeEnergyProfile::from_config::<
+                QuadTreeState<CanvasTube, NullStateTracker>,
+                _,
+            >(self, start, config),
+        }
+    }
+
     fn system_info(&self) -> String {
         self.system_info()
     }
@@ -754,6 +1469,20 @@ pub enum SystemEnum {
 pub trait SystemWithDimers {
     /// Returns information on dimers that the system can form, similarly useful for starting out a state.
     fn calc_dimers(&self) -> Vec<DimerInfo>;
+
+    /// Enumerates every assembly of 2 to `max_size` tiles held together by bonds, up to
+    /// translation, with its bond energy, formation rate and equilibrium concentration.  The
+    /// number of assemblies grows quickly with `max_size`, so this is meant for small sizes.
+    /// Systems without concentrations (the aTAM) return no assemblies.
+    fn calc_nmers(&self, max_size: NumTiles) -> Vec<NmerInfo>;
+
+    /// Energy of the bond between a tile and the tile to its south, in units of kT (for aTAM,
+    /// glue strength), with larger values binding more strongly.
+    fn bond_energy_ns(&self, tn: Tile, ts: Tile) -> f64;
+
+    /// Energy of the bond between a tile and the tile to its east, as for
+    /// [SystemWithDimers::bond_energy_ns].
+    fn bond_energy_we(&self, tw: Tile, te: Tile) -> f64;
 }
 
 #[enum_dispatch]
@@ -1,10 +1,12 @@
-use crate::base::{GlueIdent, RgrowError, StringConvError, TileIdent};
+use crate::base::{GlueIdent, Ident, RgrowError, StringConvError, TileIdent};
 use crate::canvas::{CanvasPeriodic, CanvasSquare, CanvasTube};
 use crate::colors::get_color_or_random;
 
 use crate::models::atam::ATAM;
 use crate::models::ktam::KTAM;
 use crate::models::oldktam::OldKTAM;
+use crate::parser_xgrow::IgnoredOption;
+use crate::ratestore::{QuadTreeRectArray, QuadTreeSquareArray, RateStore, SparseRateStore};
 use crate::state::{NullStateTracker, QuadTreeState, StateWithCreate};
 use crate::system::{DynSystem, EvolveBounds};
 
@@ -15,7 +17,7 @@ use super::base::{CanvasLength, Glue};
 use super::system::FissionHandling;
 use super::*;
 use anyhow::Context;
-use base::{NumEvents, NumTiles};
+use base::{NumEvents, NumTiles, Rate};
 use bimap::BiMap;
 use core::fmt;
 use ndarray::prelude::*;
@@ -25,7 +27,7 @@ use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::io::{self, Read};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use system::{ChunkHandling, ChunkSize};
 
 #[cfg(feature = "python")]
@@ -39,6 +41,7 @@ pub const MODEL_DEFAULT: Model = Model::KTAM;
 pub const GMC_DEFAULT: f64 = 16.0;
 pub const GSE_DEFAULT: f64 = 8.1;
 pub const CANVAS_TYPE_DEFAULT: CanvasType = CanvasType::Square;
+pub const RATE_STORE_DEFAULT: RateStoreType = RateStoreType::QuadTree;
 pub const SIZE_DEFAULT: Size = Size::Single(64);
 
 #[derive(thiserror::Error, Debug)]
@@ -69,6 +72,14 @@ pub enum ParserError {
         num: usize,
         shape: TileShape,
     },
+    #[error("Tile template {template} uses undefined parameter {param}.")]
+    UndefinedTemplateParameter { template: String, param: String },
+    #[error("Tile template {template} has an unclosed {{ in \"{text}\".")]
+    MalformedTemplate { template: String, text: String },
+    #[error("{path} includes itself.")]
+    IncludeCycle { path: String },
+    #[error("Tile set has unresolved includes; read it with TileSet::from_file or call resolve_includes first.")]
+    UnresolvedInclude,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -257,6 +268,178 @@ impl CoverStrand {
     }
 }
 
+/// A value of a tile template parameter.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TemplateValue {
+    Int(i64),
+    Str(String),
+}
+
+impl Display for TemplateValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Str(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// The values taken by a tile template parameter: either a list of values,
+/// or a range of integers, `{range: [start, end]}`, excluding the end.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum TemplateValues {
+    Range { range: (i64, i64) },
+    List(Vec<TemplateValue>),
+}
+
+impl TemplateValues {
+    fn values(&self) -> Vec<TemplateValue> {
+        match self {
+            Self::Range { range: (a, b) } => (*a..*b).map(TemplateValue::Int).collect(),
+            Self::List(v) => v.clone(),
+        }
+    }
+}
+
+/// A family of tiles, expanded into tiles when the tile set is parsed.
+///
+/// `{param}` in the name, glue names and color is replaced by the parameter's value.  Tiles are
+/// produced for each row of `table` (or once, if there is no table), combined with every
+/// combination of the values in `params`.  For example, a template with
+/// `params: {a: {range: [0, 2]}, b: [x, y]}` produces four tiles.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TileTemplate {
+    pub name: Option<String>,
+    pub edges: Vec<GlueIdent>,
+    pub stoic: Option<f64>,
+    pub color: Option<String>,
+    pub shape: Option<TileShape>,
+    #[serde(default)]
+    pub params: BTreeMap<String, TemplateValues>,
+    #[serde(default)]
+    pub table: Vec<BTreeMap<String, TemplateValue>>,
+}
+
+fn substitute_template(
+    text: &str,
+    vars: &BTreeMap<String, TemplateValue>,
+    template: &str,
+) -> Result<String, ParserError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| ParserError::MalformedTemplate {
+                template: template.to_string(),
+                text: text.to_string(),
+            })?
+            + start;
+        let param = &rest[start + 1..end];
+        match vars.get(param) {
+            Some(v) => out.push_str(&v.to_string()),
+            None => {
+                return Err(ParserError::UndefinedTemplateParameter {
+                    template: template.to_string(),
+                    param: param.to_string(),
+                })
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+impl TileTemplate {
+    /// Expands the template into tiles.
+    pub fn expand(&self) -> Result<Vec<Tile>, ParserError> {
+        let template = self
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{:?}", self.edges));
+
+        let mut assignments = if self.table.is_empty() {
+            vec![BTreeMap::new()]
+        } else {
+            self.table.clone()
+        };
+        for (param, values) in &self.params {
+            let values = values.values();
+            assignments = assignments
+                .into_iter()
+                .flat_map(|a| {
+                    values.iter().map(move |v| {
+                        let mut a = a.clone();
+                        a.insert(param.clone(), v.clone());
+                        a
+                    })
+                })
+                .collect();
+        }
+
+        assignments
+            .iter()
+            .map(|vars| {
+                let edges = self
+                    .edges
+                    .iter()
+                    .map(|e| match e {
+                        GlueIdent::Name(n) => {
+                            let n = substitute_template(n, vars, &template)?;
+                            Ok(match n.parse::<Glue>() {
+                                Ok(i) => GlueIdent::Num(i),
+                                Err(_) => GlueIdent::Name(n),
+                            })
+                        }
+                        GlueIdent::Num(i) => Ok(GlueIdent::Num(*i)),
+                    })
+                    .collect::<Result<Vec<_>, ParserError>>()?;
+                Ok(Tile {
+                    name: self
+                        .name
+                        .as_ref()
+                        .map(|n| substitute_template(n, vars, &template))
+                        .transpose()?,
+                    edges,
+                    stoic: self.stoic,
+                    color: self
+                        .color
+                        .as_ref()
+                        .map(|c| substitute_template(c, vars, &template))
+                        .transpose()?,
+                    shape: self.shape.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Another tile set file whose tiles, bonds and glue links are added to this one.  Tile and glue
+/// names from the file can be prefixed, so that the same file can be included more than once.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Include {
+    File(String),
+    WithOptions {
+        file: String,
+        /// Prefix for both tile and glue names.
+        prefix: Option<String>,
+        tile_prefix: Option<String>,
+        glue_prefix: Option<String>,
+    },
+}
+
+fn prefix_ident(ident: &Ident, prefix: &str) -> Ident {
+    match ident {
+        Ident::Name(n) => Ident::Name(format!("{prefix}{n}")),
+        Ident::Num(i) => Ident::Num(*i),
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "python", derive(FromPyObject))]
 pub struct Bond {
@@ -286,6 +469,9 @@ struct SerdeTileSet {
     pub(self) size: Option<Size>,
     pub(self) tau: Option<f64>,
     pub(self) smax: Option<NumTiles>,
+    pub(self) smin: Option<NumTiles>,
+    pub(self) tmax: Option<f64>,
+    pub(self) emax: Option<NumEvents>,
     pub(self) update_rate: Option<NumEvents>,
     #[serde(alias = "k_f")]
     pub(self) kf: Option<f64>,
@@ -294,17 +480,21 @@ struct SerdeTileSet {
     pub(self) chunk_handling: Option<ChunkHandling>,
     pub(self) chunk_size: Option<ChunkSize>,
     pub(self) canvas_type: Option<CanvasType>,
+    pub(self) rate_store: Option<RateStoreType>,
     #[serde(alias = "doubletiles")]
     pub(self) hdoubletiles: Option<Vec<(TileIdent, TileIdent)>>,
     pub(self) vdoubletiles: Option<Vec<(TileIdent, TileIdent)>>,
     pub(self) model: Option<Model>,
+    pub(self) include: Option<Vec<Include>>,
+    #[serde(alias = "templates")]
+    pub(self) tile_templates: Option<Vec<TileTemplate>>,
     #[serde(alias = "xgrowargs", alias = "params")]
     pub(self) options: Option<Box<SerdeTileSet>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[cfg_attr(feature = "python", pyclass)]
-#[serde(from = "SerdeTileSet")]
+#[serde(try_from = "SerdeTileSet")]
 pub struct TileSet {
     #[serde(default = "Vec::new")]
     pub tiles: Vec<Tile>,
@@ -321,6 +511,9 @@ pub struct TileSet {
     pub size: Option<Size>,
     pub tau: Option<f64>,
     pub smax: Option<NumTiles>,
+    pub smin: Option<NumTiles>,
+    pub tmax: Option<f64>,
+    pub emax: Option<NumEvents>,
     pub update_rate: Option<NumEvents>,
     pub kf: Option<f64>,
     pub fission: Option<FissionHandling>,
@@ -328,13 +521,19 @@ pub struct TileSet {
     pub chunk_handling: Option<ChunkHandling>,
     pub chunk_size: Option<ChunkSize>,
     pub canvas_type: Option<CanvasType>,
+    pub rate_store: Option<RateStoreType>,
     pub hdoubletiles: Option<Vec<(TileIdent, TileIdent)>>,
     pub vdoubletiles: Option<Vec<(TileIdent, TileIdent)>>,
     pub model: Option<Model>,
+    /// Files to include.  These are resolved, relative to the including file, by
+    /// [TileSet::from_file] or [TileSet::resolve_includes].
+    pub include: Option<Vec<Include>>,
 }
 
-impl From<SerdeTileSet> for TileSet {
-    fn from(serde_tile_set: SerdeTileSet) -> Self {
+impl TryFrom<SerdeTileSet> for TileSet {
+    type Error = ParserError;
+
+    fn try_from(serde_tile_set: SerdeTileSet) -> Result<Self, Self::Error> {
         let SerdeTileSet {
             tiles,
             bonds,
@@ -348,6 +547,9 @@ impl From<SerdeTileSet> for TileSet {
             size,
             tau,
             smax,
+            smin,
+            tmax,
+            emax,
             update_rate,
             kf,
             fission,
@@ -355,9 +557,12 @@ impl From<SerdeTileSet> for TileSet {
             chunk_handling,
             chunk_size,
             canvas_type,
+            rate_store,
             hdoubletiles,
             vdoubletiles,
             model,
+            include,
+            tile_templates,
             options,
         } = serde_tile_set;
 
@@ -374,6 +579,9 @@ impl From<SerdeTileSet> for TileSet {
             size,
             tau,
             smax,
+            smin,
+            tmax,
+            emax,
             update_rate,
             kf,
             fission,
@@ -381,12 +589,21 @@ impl From<SerdeTileSet> for TileSet {
             chunk_handling,
             chunk_size,
             canvas_type,
+            rate_store,
             hdoubletiles,
             vdoubletiles,
             model,
+            include,
         };
 
+        for template in tile_templates.iter().flatten() {
+            tile_set.tiles.extend(template.expand()?);
+        }
+
         if let Some(options) = options {
+            for template in options.tile_templates.iter().flatten() {
+                tile_set.tiles.extend(template.expand()?);
+            }
             tile_set.tiles.extend(options.tiles);
             tile_set.bonds.extend(options.bonds);
             tile_set.glues.extend(options.glues);
@@ -399,6 +616,9 @@ impl From<SerdeTileSet> for TileSet {
             tile_set.size = options.size.or(tile_set.size);
             tile_set.tau = options.tau.or(tile_set.tau);
             tile_set.smax = options.smax.or(tile_set.smax);
+            tile_set.smin = options.smin.or(tile_set.smin);
+            tile_set.tmax = options.tmax.or(tile_set.tmax);
+            tile_set.emax = options.emax.or(tile_set.emax);
             tile_set.update_rate = options.update_rate.or(tile_set.update_rate);
             tile_set.kf = options.kf.or(tile_set.kf);
             tile_set.fission = options.fission.or(tile_set.fission);
@@ -406,12 +626,19 @@ impl From<SerdeTileSet> for TileSet {
             tile_set.chunk_handling = options.chunk_handling.or(tile_set.chunk_handling);
             tile_set.chunk_size = options.chunk_size.or(tile_set.chunk_size);
             tile_set.canvas_type = options.canvas_type.or(tile_set.canvas_type);
+            tile_set.rate_store = options.rate_store.or(tile_set.rate_store);
             tile_set.hdoubletiles = options.hdoubletiles.or(tile_set.hdoubletiles);
             tile_set.vdoubletiles = options.vdoubletiles.or(tile_set.vdoubletiles);
             tile_set.model = options.model.or(tile_set.model);
+            if let Some(include) = options.include {
+                tile_set
+                    .include
+                    .get_or_insert_with(Vec::new)
+                    .extend(include);
+            }
         }
 
-        tile_set
+        Ok(tile_set)
     }
 }
 
@@ -455,6 +682,9 @@ impl Display for TileSet {
         }
         writeln!(f, "        tau: {:?}", self.tau)?;
         writeln!(f, "        smax: {:?}", self.smax)?;
+        writeln!(f, "        smin: {:?}", self.smin)?;
+        writeln!(f, "        tmax: {:?}", self.tmax)?;
+        writeln!(f, "        emax: {:?}", self.emax)?;
         if let Some(x) = self.update_rate {
             writeln!(f, "        update_rate: {}", x)?;
         }
@@ -464,6 +694,7 @@ impl Display for TileSet {
         writeln!(f, "        chunk_handling: {:?}", self.chunk_handling)?;
         writeln!(f, "        chunk_size: {:?}", self.chunk_size)?;
         writeln!(f, "        canvas_type: {:?}", self.canvas_type)?;
+        writeln!(f, "        rate_store: {:?}", self.rate_store)?;
         writeln!(f, "        hdoubletiles: {:?}", self.hdoubletiles)?;
         writeln!(f, "        vdoubletiles: {:?}", self.vdoubletiles)?;
         writeln!(f, "        model: {:?}", self.model)?;
@@ -516,6 +747,33 @@ impl TryFrom<&str> for CanvasType {
     }
 }
 
+/// The RateStore used by states.  `QuadTree` uses a square quadtree for power-of-two square
+/// canvases, and a rectangular one otherwise.  `Sparse` only stores points with nonzero rates,
+/// and is suited to large canvases with small assemblies.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy)]
+#[cfg_attr(feature = "python", pyclass)]
+pub enum RateStoreType {
+    #[serde(alias = "quadtree")]
+    QuadTree,
+    #[serde(alias = "sparse")]
+    Sparse,
+}
+
+impl TryFrom<&str> for RateStoreType {
+    type Error = StringConvError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "quadtree" => Ok(RateStoreType::QuadTree),
+            "sparse" => Ok(RateStoreType::Sparse),
+            _ => Err(StringConvError(format!(
+                "Unknown rate store {}.  Valid options are \"quadtree\" and \"sparse\".",
+                value
+            ))),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum Model {
     #[serde(alias = "kTAM", alias = "ktam")]
@@ -558,15 +816,50 @@ pub trait FromTileSet: Sized {
 }
 
 impl TileSet {
+    /// Parses a tile set from JSON.  Included files can only be resolved relative to a file,
+    /// so a tile set with `include` is an error here; use [TileSet::from_file] instead.
     pub fn from_json(data: &str) -> serde_json::Result<Self> {
-        serde_json::from_str(data)
+        let ts: Self = serde_json::from_str(data)?;
+        if ts.include.is_some() {
+            return Err(serde::de::Error::custom(ParserError::UnresolvedInclude));
+        }
+        Ok(ts)
     }
 
+    /// Parses a tile set from YAML.  As with [TileSet::from_json], `include` is an error.
     pub fn from_yaml(data: &str) -> Result<Self, serde_yaml::Error> {
-        serde_yaml::from_str(data)
+        let ts: Self = serde_yaml::from_str(data)?;
+        if ts.include.is_some() {
+            return Err(serde::de::Error::custom(ParserError::UnresolvedInclude));
+        }
+        Ok(ts)
     }
 
+    /// Reads a tile set from a YAML or Xgrow file, resolving any included files.  Options
+    /// in Xgrow files that were ignored are logged as warnings.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, anyhow::Error> {
+        let (ts, ignored) = Self::from_file_with_ignored(path)?;
+        for i in ignored {
+            log::warn!("{i}; ignoring it.");
+        }
+        Ok(ts)
+    }
+
+    /// Reads a tile set like [TileSet::from_file], also returning the options in Xgrow files
+    /// (including any included files) that were ignored.
+    pub fn from_file_with_ignored<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<(Self, Vec<IgnoredOption>), anyhow::Error> {
+        let mut ignored = Vec::new();
+        let ts = Self::from_file_including(path.as_ref(), &mut Vec::new(), &mut ignored)?;
+        Ok((ts, ignored))
+    }
+
+    fn from_file_including(
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        ignored: &mut Vec<IgnoredOption>,
+    ) -> Result<Self, anyhow::Error> {
         let mut file = std::fs::File::open(path)?;
 
         let mut s = String::new();
@@ -574,22 +867,117 @@ impl TileSet {
 
         let res: Result<TileSet, _> = serde_yaml::from_str(&s);
 
-        if let Ok(ts) = res {
-            return Ok(ts);
-        }
+        let mut ts = match res {
+            Ok(ts) => ts,
+            Err(e) => match parser_xgrow::parse_xgrow_string_with_ignored(&s) {
+                Ok((ts, ig)) => {
+                    ignored.extend(ig);
+                    ts
+                }
+                // We've failed on both.  Do we look like an xgrow file?
+                Err(e2) => {
+                    return if s.contains("tile edges={") {
+                        Err(e2).context("Failed to parse xgrow file")
+                    } else {
+                        Err(e).context("Failed to parse yaml file")
+                    }
+                }
+            },
+        };
 
-        let res2 = parser_xgrow::parse_xgrow_string(&s);
+        let canonical = path.canonicalize()?;
+        stack.push(canonical);
+        ts.resolve_includes_inner(path.parent().unwrap_or(Path::new("")), stack, ignored)?;
+        stack.pop();
+
+        Ok(ts)
+    }
 
-        if let Ok(ts) = res2 {
-            return Ok(ts);
+    /// Adds the tiles, bonds, glue links and double tiles of included files to the tile set,
+    /// resolving paths relative to `base_dir`.  Other options in included files are ignored.
+    pub fn resolve_includes<P: AsRef<Path>>(&mut self, base_dir: P) -> Result<(), anyhow::Error> {
+        let mut ignored = Vec::new();
+        self.resolve_includes_inner(base_dir.as_ref(), &mut Vec::new(), &mut ignored)?;
+        for i in ignored {
+            log::warn!("{i}; ignoring it.");
         }
+        Ok(())
+    }
 
-        // We've failed on both.  Do we look like an xgrow file?
-        if s.contains("tile edges={") {
-            res2.context("Failed to parse xgrow file")
-        } else {
-            res.context("Failed to parse yaml file")
+    fn resolve_includes_inner(
+        &mut self,
+        base_dir: &Path,
+        stack: &mut Vec<PathBuf>,
+        ignored: &mut Vec<IgnoredOption>,
+    ) -> Result<(), anyhow::Error> {
+        let Some(includes) = self.include.take() else {
+            return Ok(());
+        };
+
+        for include in includes {
+            let (file, tile_prefix, glue_prefix) = match include {
+                Include::File(file) => (file, String::new(), String::new()),
+                Include::WithOptions {
+                    file,
+                    prefix,
+                    tile_prefix,
+                    glue_prefix,
+                } => {
+                    let prefix = prefix.unwrap_or_default();
+                    (
+                        file,
+                        tile_prefix.unwrap_or_else(|| prefix.clone()),
+                        glue_prefix.unwrap_or(prefix),
+                    )
+                }
+            };
+
+            let path = base_dir.join(&file);
+            let canonical = path
+                .canonicalize()
+                .with_context(|| format!("Failed to include {file}"))?;
+            if stack.contains(&canonical) {
+                return Err(ParserError::IncludeCycle { path: file }.into());
+            }
+            let other = Self::from_file_including(&path, stack, ignored)
+                .with_context(|| format!("Failed to include {file}"))?;
+
+            self.tiles.extend(other.tiles.into_iter().map(|t| {
+                Tile {
+                    name: t.name.map(|n| format!("{tile_prefix}{n}")),
+                    edges: t
+                        .edges
+                        .iter()
+                        .map(|e| prefix_ident(e, &glue_prefix))
+                        .collect(),
+                    ..t
+                }
+            }));
+            self.bonds.extend(other.bonds.into_iter().map(|b| Bond {
+                name: prefix_ident(&b.name, &glue_prefix),
+                strength: b.strength,
+            }));
+            self.glues.extend(other.glues.iter().map(|(g1, g2, s)| {
+                (
+                    prefix_ident(g1, &glue_prefix),
+                    prefix_ident(g2, &glue_prefix),
+                    *s,
+                )
+            }));
+            for (mine, theirs) in [
+                (&mut self.hdoubletiles, other.hdoubletiles),
+                (&mut self.vdoubletiles, other.vdoubletiles),
+            ] {
+                if let Some(theirs) = theirs {
+                    mine.get_or_insert_with(Vec::new)
+                        .extend(theirs.iter().map(|(a, b)| {
+                            (prefix_ident(a, &tile_prefix), prefix_ident(b, &tile_prefix))
+                        }));
+                }
+            }
         }
+
+        Ok(())
     }
 
     pub fn create_dynsystem(&self) -> Result<SystemEnum, RgrowError> {
@@ -607,15 +995,40 @@ impl TileSet {
             Size::Pair(i) => i,
         };
 
+        // Canvases that aren't power-of-two squares use a quadtree shaped to the canvas.
+        let square = (shape.0 == shape.1) && shape.0.is_power_of_two();
+
+        match (self.rate_store.unwrap_or(RATE_STORE_DEFAULT), square) {
+            (RateStoreType::QuadTree, true) => {
+                self.create_state_empty_with::<QuadTreeSquareArray<Rate>>(shape)
+            }
+            (RateStoreType::QuadTree, false) => {
+                self.create_state_empty_with::<QuadTreeRectArray<Rate>>(shape)
+            }
+            (RateStoreType::Sparse, _) => self.create_state_empty_with::<SparseRateStore>(shape),
+        }
+    }
+
+    fn create_state_empty_with<R>(&self, shape: (usize, usize)) -> Result<StateEnum, RgrowError>
+    where
+        QuadTreeState<CanvasSquare, NullStateTracker, R>: StateWithCreate<Params = (usize, usize)>,
+        QuadTreeState<CanvasPeriodic, NullStateTracker, R>:
+            StateWithCreate<Params = (usize, usize)>,
+        QuadTreeState<CanvasTube, NullStateTracker, R>: StateWithCreate<Params = (usize, usize)>,
+        StateEnum: From<QuadTreeState<CanvasSquare, NullStateTracker, R>>
+            + From<QuadTreeState<CanvasPeriodic, NullStateTracker, R>>
+            + From<QuadTreeState<CanvasTube, NullStateTracker, R>>,
+        R: RateStore,
+    {
         match self.canvas_type.unwrap_or(CANVAS_TYPE_DEFAULT) {
             CanvasType::Square => {
-                Ok(QuadTreeState::<CanvasSquare, NullStateTracker>::empty(shape)?.into())
+                Ok(QuadTreeState::<CanvasSquare, NullStateTracker, R>::empty(shape)?.into())
             }
             CanvasType::Periodic => {
-                Ok(QuadTreeState::<CanvasPeriodic, NullStateTracker>::empty(shape)?.into())
+                Ok(QuadTreeState::<CanvasPeriodic, NullStateTracker, R>::empty(shape)?.into())
             }
             CanvasType::Tube => {
-                Ok(QuadTreeState::<CanvasTube, NullStateTracker>::empty(shape)?.into())
+                Ok(QuadTreeState::<CanvasTube, NullStateTracker, R>::empty(shape)?.into())
             }
         }
     }
@@ -641,9 +1054,13 @@ impl TileSet {
         Ok(state)
     }
 
-    fn get_bounds(&self) -> EvolveBounds {
+    /// Bounds for a simulation set by the tile set (smax, smin, tmax and emax, as in Xgrow).
+    pub fn get_bounds(&self) -> EvolveBounds {
         EvolveBounds {
             size_max: self.smax,
+            size_min: self.smin,
+            total_time: self.tmax,
+            total_events: self.emax,
             ..Default::default()
         }
     }
@@ -668,11 +1085,15 @@ pub(crate) struct ProcessedTileSet {
 
     pub(crate) gluelinks: Vec<(Glue, Glue, f64)>,
 
-    glue_map: GlueNameMap,
+    pub(crate) glue_map: GlueNameMap,
 }
 
 impl ProcessedTileSet {
     pub fn from_tileset(tileset: &TileSet) -> Result<Self, ParserError> {
+        if tileset.include.is_some() {
+            return Err(ParserError::UnresolvedInclude);
+        }
+
         // Process  glues.
         let mut glue_map = BiMap::new();
         let mut gluestrengthmap = BTreeMap::<Glue, f64>::new();
@@ -0,0 +1,545 @@
+//! Free energy landscapes of assemblies by umbrella sampling.
+//!
+//! The equilibrium distribution of a single assembly's order parameter (by default, its number of
+//! tiles) is sampled in a series of overlapping windows.  In each window, moves are short runs of
+//! unbiased dynamics ([System::take_single_step]), of a random number of events, accepted or
+//! rejected by the Metropolis criterion on a bias: infinite walls at the edges of the window, and
+//! (if `adaptive`) the previous window's estimate of ln P(n), to flatten the window's histogram.
+//! As kTAM dynamics satisfy detailed balance, the sequence of states visited samples the biased
+//! equilibrium distribution weighted by each state's total rate, so weighting samples by
+//! the inverse of the total rate gives it exactly.  Histograms from the windows are unbiased and
+//! stitched together in their overlaps to give ΔG(n) = -ln P(n).
+//!
+//! The first event of each move gives unbiased rates of transitions between values, and so
+//! equilibrium fluxes between them and a Becker–Döring estimate of the nucleation rate that can
+//! be compared to FFS ([FreeEnergyProfile::nucleation_rate]).
+//!
+//! Fission handling other than `NoFission` makes dynamics irreversible, so results with it are
+//! approximate.  Assemblies that only rarely lose tiles (for example, those held together by
+//! strong bonds) make windows slow to sample, so results should be checked with more samples.
+
+use std::collections::BTreeMap;
+
+use ndarray::Array2;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::base::{new_sim_rng, GrowError, NumTiles, SimRng, Tile};
+use crate::canvas::PointSafe2;
+use crate::ffs::{order_value, FFSOrderParameter};
+use crate::state::{State, StateWithCreate};
+use crate::system::{Orientation, StepOutcome, System, SystemWithDimers};
+
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyTypeError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+
+/// Configuration options for umbrella sampling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+#[cfg_attr(feature = "python", pyclass(get_all, set_all))]
+pub struct UmbrellaConfig {
+    /// Order parameter to sample.
+    pub order_parameter: FFSOrderParameter,
+    /// Smallest value of the order parameter sampled.
+    pub min_size: NumTiles,
+    /// Largest value of the order parameter sampled.
+    pub max_size: NumTiles,
+    /// Number of values in each window.
+    pub window_width: NumTiles,
+    /// Number of values shared by consecutive windows.
+    pub window_overlap: NumTiles,
+    /// Bias each window by the estimate of ln P(n) from the previous one, extrapolated to new
+    /// values, to flatten its histogram.  Otherwise, windows are flat.
+    pub adaptive: bool,
+    /// Largest number of events (including events that change nothing) in each move.  Each
+    /// move's number is chosen uniformly from 1 to this.
+    pub move_events: usize,
+    /// Moves sampled in each window.
+    pub samples: usize,
+    /// Moves made in each window before sampling.
+    pub equilibration: usize,
+    /// Canvas size, if a starting state is not given.
+    pub canvas_size: (usize, usize),
+    /// If a starting state is not given, start from the system's seed rather than from a dimer.
+    pub from_seed: bool,
+    /// Seed for reproducible results.
+    pub seed: Option<u64>,
+}
+
+impl UmbrellaConfig {
+    fn validate(&self) -> Result<(), GrowError> {
+        let invalid = |s: &str| Err(GrowError::UmbrellaInvalidConfig(s.to_string()));
+        if self.min_size == 0 {
+            return invalid("min_size must be at least 1");
+        }
+        if self.max_size < self.min_size {
+            return invalid("max_size must be at least min_size");
+        }
+        if self.window_overlap >= self.window_width {
+            return invalid("window_overlap must be less than window_width");
+        }
+        Ok(())
+    }
+}
+
+impl Default for UmbrellaConfig {
+    fn default() -> Self {
+        Self {
+            order_parameter: FFSOrderParameter::TileCount,
+            min_size: 2,
+            max_size: 30,
+            window_width: 3,
+            window_overlap: 2,
+            adaptive: true,
+            move_events: 4,
+            samples: 2000,
+            equilibration: 200,
+            canvas_size: (32, 32),
+            from_seed: false,
+            seed: None,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl UmbrellaConfig {
+    pub fn _py_set(&mut self, k: &str, v: &PyAny, _py: Python) -> PyResult<()> {
+        match k {
+            "order_parameter" => self.order_parameter = v.extract()?,
+            "min_size" => self.min_size = v.extract()?,
+            "max_size" => self.max_size = v.extract()?,
+            "window_width" => self.window_width = v.extract()?,
+            "window_overlap" => self.window_overlap = v.extract()?,
+            "adaptive" => self.adaptive = v.extract()?,
+            "move_events" => self.move_events = v.extract()?,
+            "samples" => self.samples = v.extract()?,
+            "equilibration" => self.equilibration = v.extract()?,
+            "canvas_size" => self.canvas_size = v.extract()?,
+            "from_seed" => self.from_seed = v.extract()?,
+            "seed" => self.seed = v.extract()?,
+            _ => {
+                return Err(PyTypeError::new_err(format!(
+                    "Unknown UmbrellaConfig setting: {k}"
+                )))
+            }
+        };
+        Ok(())
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl UmbrellaConfig {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    fn new(kwargs: Option<&PyDict>, py: Python) -> PyResult<Self> {
+        let mut rc = Self::default();
+        if let Some(dict) = kwargs {
+            for (k, v) in dict.iter() {
+                rc._py_set(&k.extract::<String>()?, v, py)?;
+            }
+        }
+        Ok(rc)
+    }
+}
+
+/// A free energy landscape from umbrella sampling.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "python", pyclass(get_all))]
+pub struct FreeEnergyProfile {
+    /// The values of the order parameter sampled, from `min_size` to `max_size`.
+    pub sizes: Vec<NumTiles>,
+    /// ΔG at each value, in kT, relative to the smallest value sampled (NaN where no samples
+    /// were taken).
+    pub free_energy: Vec<f64>,
+    /// The number of samples at each value.
+    pub counts: Vec<usize>,
+    /// The smallest and largest values of each window.
+    pub windows: Vec<(NumTiles, NumTiles)>,
+    /// The fraction of moves accepted in each window.
+    pub acceptance: Vec<f64>,
+    /// For each value, the equilibrium flux between it and larger values and smaller ones, per
+    /// unit of probability at the smallest value sampled.
+    pub flux: Vec<f64>,
+    /// The value with the largest ΔG, if it is below `max_size`.
+    pub critical_size: Option<NumTiles>,
+    /// ΔG at the critical size, relative to the smallest value sampled.
+    pub barrier: Option<f64>,
+}
+
+impl FreeEnergyProfile {
+    /// The Becker–Döring nucleation rate, treating the order parameter as a reaction
+    /// coordinate, with fluxes calibrated by `dimerization_rate` (as in
+    /// [crate::ffs::FFSResult::dimerization_rate]), so that it can be compared to the FFS
+    /// nucleation rate.  Each dimer can form from either of its tiles on the lattice, so the
+    /// flux between monomers and dimers there is twice the dimerization rate.  This needs the
+    /// tile count order parameter for an unseeded system, with `min_size` 2, and is only
+    /// meaningful if the profile extends well past the critical size.  As assemblies of the
+    /// same size can differ in how likely they are to grow, it is usually larger than the FFS
+    /// rate.
+    pub fn nucleation_rate(&self, dimerization_rate: f64) -> Option<f64> {
+        if self.sizes.first() != Some(&2) {
+            return None;
+        }
+        // Fluxes per unit of probability at size 2, times this, are rates.
+        let scale = 2. * dimerization_rate / self.flux.first()?;
+        let resistance: f64 =
+            1. / dimerization_rate + self.flux[1..].iter().map(|f| 1. / (scale * f)).sum::<f64>();
+        resistance.is_finite().then(|| 1. / resistance)
+    }
+
+    /// Samples the free energy landscape of `system`, starting from `start`.  If `start` is
+    /// None, the system's most stable dimer is used, or its seeded state if
+    /// `config.from_seed` is set.  The starting state's order parameter must be in the first
+    /// window.
+    pub fn calculate<
+        St: State + StateWithCreate<Params = (usize, usize)> + Clone,
+        Sy: SystemWithDimers + System,
+    >(
+        system: &Sy,
+        start: Option<St>,
+        config: &UmbrellaConfig,
+    ) -> Result<Self, GrowError> {
+        config.validate()?;
+        let order = &config.order_parameter;
+        let mut rng = match config.seed {
+            Some(seed) => SimRng::seed_from_u64(seed),
+            None => new_sim_rng(),
+        };
+
+        let windows = windows(config);
+        let mut state = match start {
+            Some(state) => state,
+            None => default_start(system, config)?,
+        };
+        let mut value = order_value(system, &state, order);
+        if (value < windows[0].0) || (value > windows[0].1) {
+            return Err(GrowError::UmbrellaStartOutsideWindow(
+                value,
+                windows[0].0,
+                windows[0].1,
+            ));
+        }
+
+        let nsizes = (config.max_size - config.min_size + 1) as usize;
+        let idx = |v: NumTiles| (v - config.min_size) as usize;
+        let mut stats = TransitionStats {
+            time: vec![0.; nsizes],
+            transitions: BTreeMap::new(),
+        };
+        let mut histograms = Vec::with_capacity(windows.len());
+        let mut acceptance = Vec::with_capacity(windows.len());
+
+        // The bias on each value, set for each window from the previous one.
+        let mut bias = vec![0.; nsizes];
+        let mut trial = state.clone();
+        for (k, &(low, high)) in windows.iter().enumerate() {
+            let next_low = windows.get(k + 1).map(|w| w.0);
+            let mut next_start: Option<(St, NumTiles)> = None;
+
+            let mut hist = vec![0usize; nsizes];
+            let mut weights = vec![0.; nsizes];
+            let mut accepted = 0;
+            for i in 0..(config.equilibration + config.samples) {
+                let sampling = i >= config.equilibration;
+                let rate = state.total_rate();
+
+                trial.clone_from(&state);
+                let events = rng.gen_range(1..=config.move_events.max(1));
+                let (new_value, first_value) =
+                    run_move(system, &mut trial, order, value, events, &mut rng)?;
+
+                // The first step, from the current state, gives unbiased rates.
+                if sampling {
+                    stats.time[idx(value)] += 1. / rate;
+                    if first_value != value {
+                        *stats.transitions.entry((value, first_value)).or_insert(0) += 1;
+                    }
+                }
+
+                if (low..=high).contains(&new_value)
+                    && (trial.total_rate() > 0.)
+                    && rng.gen::<f64>() < f64::exp(bias[idx(value)] - bias[idx(new_value)])
+                {
+                    std::mem::swap(&mut state, &mut trial);
+                    value = new_value;
+                    accepted += 1;
+                }
+
+                if sampling {
+                    hist[idx(value)] += 1;
+                    weights[idx(value)] += 1. / state.total_rate();
+                    if next_low.is_some_and(|n| value >= n) {
+                        match &mut next_start {
+                            Some((s, v)) => {
+                                s.clone_from(&state);
+                                *v = value;
+                            }
+                            None => next_start = Some((state.clone(), value)),
+                        }
+                    }
+                }
+            }
+            acceptance.push(accepted as f64 / (config.equilibration + config.samples) as f64);
+
+            // ln P(n), up to a constant, in this window.
+            let ln_p: Vec<Option<f64>> = (0..nsizes)
+                .map(|i| (hist[i] > 0).then(|| weights[i].ln() + bias[i]))
+                .collect();
+            if config.adaptive {
+                adapt_bias(&mut bias, &ln_p, idx(low), idx(high));
+            }
+            histograms.push((hist, ln_p));
+
+            if let Some(n) = next_low {
+                match next_start {
+                    Some((s, v)) => {
+                        state = s;
+                        value = v;
+                    }
+                    None => return Err(GrowError::UmbrellaNoOverlap(low, n)),
+                }
+            }
+        }
+
+        // Each window's offset matches it to the previous one where both have samples.
+        let mut offsets = vec![0.; windows.len()];
+        for k in 1..windows.len() {
+            let (prev_hist, prev_ln_p) = &histograms[k - 1];
+            let (hist, ln_p) = &histograms[k];
+            let (mut sum, mut total_weight) = (0., 0.);
+            for i in 0..nsizes {
+                if let (Some(a), Some(b)) = (prev_ln_p[i], ln_p[i]) {
+                    let (h0, h1) = (prev_hist[i] as f64, hist[i] as f64);
+                    let weight = h0 * h1 / (h0 + h1);
+                    sum += weight * (a + offsets[k - 1] - b);
+                    total_weight += weight;
+                }
+            }
+            if total_weight == 0. {
+                return Err(GrowError::UmbrellaNoOverlap(windows[k - 1].0, windows[k].0));
+            }
+            offsets[k] = sum / total_weight;
+        }
+
+        let mut counts = vec![0; nsizes];
+        let mut ln_p = vec![f64::NAN; nsizes];
+        for i in 0..nsizes {
+            let (mut sum, mut n) = (0., 0);
+            for ((hist, window_ln_p), offset) in histograms.iter().zip(&offsets) {
+                if let Some(l) = window_ln_p[i] {
+                    sum += hist[i] as f64 * (l + offset);
+                    n += hist[i];
+                }
+            }
+            counts[i] = n;
+            if n > 0 {
+                ln_p[i] = sum / n as f64;
+            }
+        }
+        let reference = ln_p.iter().copied().find(|l| !l.is_nan()).unwrap_or(0.);
+        let free_energy: Vec<f64> = ln_p.iter().map(|l| reference - l).collect();
+
+        // The flux across each boundary, from its upward and downward transitions, which agree
+        // at equilibrium.  Below `min_size`, only downward transitions are sampled.
+        let flux = (0..nsizes)
+            .map(|n| {
+                let boundary = n as NumTiles + config.min_size - 1;
+                let (mut up, mut down) = (0., 0.);
+                for (&(from, to), &count) in &stats.transitions {
+                    let i = idx(from);
+                    let f = f64::exp(-free_energy[i]) * count as f64 / stats.time[i];
+                    if f.is_nan() {
+                        continue;
+                    }
+                    if (from <= boundary) && (to > boundary) {
+                        up += f;
+                    } else if (from > boundary) && (to <= boundary) {
+                        down += f;
+                    }
+                }
+                if n == 0 {
+                    down
+                } else {
+                    (up + down) / 2.
+                }
+            })
+            .collect();
+
+        let (critical, barrier) = free_energy
+            .iter()
+            .enumerate()
+            .filter(|(_, g)| !g.is_nan())
+            .fold(
+                (0, f64::NEG_INFINITY),
+                |acc, (i, &g)| {
+                    if g > acc.1 {
+                        (i, g)
+                    } else {
+                        acc
+                    }
+                },
+            );
+        let (critical_size, barrier) = if (critical + 1 < nsizes) && barrier.is_finite() {
+            (Some(critical as NumTiles + config.min_size), Some(barrier))
+        } else {
+            (None, None)
+        };
+
+        Ok(Self {
+            sizes: (config.min_size..=config.max_size).collect(),
+            free_energy,
+            counts,
+            windows,
+            acceptance,
+            flux,
+            critical_size,
+            barrier,
+        })
+    }
+
+    /// Samples the free energy landscape of `system`, starting from a configuration, as for
+    /// [FreeEnergyProfile::calculate].
+    pub fn from_config<
+        St: State + StateWithCreate<Params = (usize, usize)> + Clone,
+        Sy: SystemWithDimers + System,
+    >(
+        system: &Sy,
+        start: Option<&Array2<Tile>>,
+        config: &UmbrellaConfig,
+    ) -> Result<Self, GrowError> {
+        let start = match start {
+            Some(c) => {
+                let mut state = St::empty(c.dim())?;
+                let tiles: Vec<_> = c
+                    .indexed_iter()
+                    .filter(|(_, &t)| t != 0)
+                    .map(|(p, &t)| (p, t))
+                    .collect();
+                system.set_points(&mut state, &tiles);
+                Some(state)
+            }
+            None => None,
+        };
+        Self::calculate::<St, Sy>(system, start, config)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl FreeEnergyProfile {
+    #[pyo3(name = "nucleation_rate")]
+    fn py_nucleation_rate(&self, dimerization_rate: f64) -> Option<f64> {
+        self.nucleation_rate(dimerization_rate)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FreeEnergyProfile(sizes={}..{}, critical_size={:?}, barrier={:?})",
+            self.sizes.first().unwrap_or(&0),
+            self.sizes.last().unwrap_or(&0),
+            self.critical_size,
+            self.barrier
+        )
+    }
+}
+
+/// Expected time spent at each value of the order parameter before the first event of moves from
+/// it, and the number of those events that changed it, for each pair of values.
+struct TransitionStats {
+    time: Vec<f64>,
+    transitions: BTreeMap<(NumTiles, NumTiles), usize>,
+}
+
+/// Sets `bias` to the estimate of ln P(n) from a window covering `low` to `high` (as indices),
+/// wherever it has samples, and extrapolates it linearly to larger values.
+fn adapt_bias(bias: &mut [f64], ln_p: &[Option<f64>], low: usize, high: usize) {
+    for i in low..=high {
+        if let Some(l) = ln_p[i] {
+            bias[i] = l;
+        }
+    }
+    let slope = if high > low {
+        match (ln_p[high - 1], ln_p[high]) {
+            (Some(a), Some(b)) => b - a,
+            _ => 0.,
+        }
+    } else {
+        0.
+    };
+    for i in (high + 1)..bias.len() {
+        bias[i] = bias[i - 1] + slope;
+    }
+}
+
+/// The windows covering `min_size` to `max_size`.
+fn windows(config: &UmbrellaConfig) -> Vec<(NumTiles, NumTiles)> {
+    let step = config.window_width - config.window_overlap;
+    let mut windows = Vec::new();
+    let mut low = config.min_size;
+    loop {
+        let high = (low + config.window_width - 1).min(config.max_size);
+        windows.push((low, high));
+        if high >= config.max_size {
+            return windows;
+        }
+        low += step;
+    }
+}
+
+/// The seeded state, or the most stable dimer in the middle of the canvas.
+fn default_start<Sy: SystemWithDimers + System, St: StateWithCreate<Params = (usize, usize)>>(
+    system: &Sy,
+    config: &UmbrellaConfig,
+) -> Result<St, GrowError> {
+    let canvas_size = config.canvas_size;
+    let mut state = St::empty(canvas_size)?;
+    if config.from_seed {
+        if system.seed_locs().is_empty() {
+            return Err(GrowError::FFSNoSeed);
+        }
+        system.configure_empty_state(&mut state)?;
+        return Ok(state);
+    }
+    let dimers = system.calc_dimers();
+    let dimer = dimers
+        .iter()
+        .max_by(|a, b| a.equilibrium_conc.total_cmp(&b.equilibrium_conc))
+        .ok_or(GrowError::FFSNoStartStates)?;
+    let mid = PointSafe2((canvas_size.0 / 2, canvas_size.1 / 2));
+    let other = match dimer.orientation {
+        Orientation::NS => state.move_sa_s(mid).0,
+        Orientation::WE => state.move_sa_e(mid).0,
+    };
+    system.set_points(&mut state, &[(mid.0, dimer.t1), (other, dimer.t2)]);
+    Ok(state)
+}
+
+/// Runs `events` steps of unbiased dynamics from `state`, whose order parameter is `value`,
+/// returning the order parameter at the end and after the first step.
+fn run_move<Sy: SystemWithDimers + System, St: State>(
+    system: &Sy,
+    state: &mut St,
+    order: &FFSOrderParameter,
+    mut value: NumTiles,
+    events: usize,
+    rng: &mut SimRng,
+) -> Result<(NumTiles, NumTiles), GrowError> {
+    let mut first = value;
+    for i in 0..events {
+        if state.total_rate() <= 0. {
+            break;
+        }
+        if let StepOutcome::HadEventAt(_) = system.take_single_step(state, f64::INFINITY, rng)? {
+            value = order_value(system, state, order);
+        }
+        if i == 0 {
+            first = value;
+        }
+    }
+    Ok((value, first))
+}
@@ -5,10 +5,12 @@ use anyhow::{Context, Result};
 use std::fs::File;
 
 use rgrow::{
+    base::{NumTiles, TileIdent},
     canvas::{Canvas, CanvasPeriodic, PointSafe2},
+    parser_xgrow::IgnoredOption,
     state::{NullStateTracker, QuadTreeState, StateStatus},
-    system::{DynSystem, EvolveBounds, FissionHandling, System},
-    tileset::{FromTileSet, Seed, Size, TileSet},
+    system::{DynSystem, Event, EvolveBounds, FissionHandling, System, TileBondInfo},
+    tileset::{FromTileSet, RateStoreType, Seed, Size, TileSet},
 };
 
 fn test_sim(ts: &TileSet) -> Result<()> {
@@ -20,6 +22,7 @@ fn test_sim(ts: &TileSet) -> Result<()> {
             ..Default::default()
         },
     )?;
+    sys.validate_rates(&state)?;
     Ok(())
 }
 
@@ -55,6 +58,26 @@ fn parser_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn xgrow_emax_test() -> Result<()> {
+    let s = std::fs::read_to_string("./examples/xgrow-format/sierpinski.tiles")?;
+    let ts = rgrow::parser_xgrow::parse_xgrow_string(&format!("{s}\nemax=300\n"))?;
+    assert_eq!(ts.emax, Some(300));
+
+    let (sys, mut state) = ts.create_system_and_state()?;
+    sys.evolve(&mut state, ts.get_bounds())?;
+    assert_eq!(state.total_events(), 300);
+
+    // The bound is on the state's total, so evolving again does nothing.
+    let outcome = sys.evolve(&mut state, ts.get_bounds())?;
+    assert!(matches!(
+        outcome,
+        rgrow::system::EvolveOutcome::ReachedEventsMax
+    ));
+    assert_eq!(state.total_events(), 300);
+    Ok(())
+}
+
 #[test]
 fn atam_test() -> Result<()> {
     let mut ts = get_sierpinski()?;
@@ -83,6 +106,241 @@ fn atam_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn sparse_ratestore_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::ATAM);
+    ts.size = Some(Size::Single(100));
+    ts.seed = Some(Seed::Single(96, 96, 1.into()));
+
+    // Grown to completion, the aTAM Sierpinski assembly is the same regardless of the rate store.
+    let mut canvases = Vec::new();
+    for rate_store in [RateStoreType::QuadTree, RateStoreType::Sparse] {
+        ts.rate_store = Some(rate_store);
+        let (sys, mut state) = ts.create_system_and_state()?;
+        sys.evolve(
+            &mut state,
+            EvolveBounds {
+                for_events: Some(20000),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(state.n_tiles(), 95 * 95);
+        canvases.push(state.raw_array().to_owned());
+    }
+    assert_eq!(canvases[0], canvases[1]);
+
+    Ok(())
+}
+
+#[test]
+fn leap_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::ATAM);
+    ts.size = Some(Size::Single(100));
+    ts.seed = Some(Seed::Single(96, 96, 1.into()));
+
+    // Leaping shouldn't change the (deterministic) complete aTAM assembly, and must leave the
+    // rate store consistent.
+    let (sys, mut state) = ts.create_system_and_state()?;
+    sys.evolve(
+        &mut state,
+        EvolveBounds::default()
+            .for_events(20000)
+            .leap_tolerance(0.2),
+    )?;
+    sys.validate_rates(&state)?;
+    assert_eq!(state.n_tiles(), 95 * 95);
+
+    let (sys, mut exact) = ts.create_system_and_state()?;
+    sys.evolve(&mut exact, EvolveBounds::default().for_events(20000))?;
+    assert_eq!(state.raw_array(), exact.raw_array());
+
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.size = Some(Size::Single(64));
+    ts.seed = Some(Seed::Single(60, 60, 1.into()));
+    let (sys, mut state) = ts.create_system_and_state()?;
+    let (time, events) = (state.time(), state.total_events());
+    sys.evolve(
+        &mut state,
+        EvolveBounds::default().for_events(5000).leap_tolerance(0.1),
+    )?;
+    sys.validate_rates(&state)?;
+    assert_eq!(state.total_events() - events, 5000);
+    assert!(state.time() > time);
+
+    Ok(())
+}
+
+#[test]
+fn parallel_domains_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::ATAM);
+    ts.size = Some(Size::Single(100));
+    ts.seed = Some(Seed::Single(96, 96, 1.into()));
+
+    // Growth has to cross many domain boundaries, but the complete assembly should be the same.
+    let (sys, mut state) = ts.create_system_and_state()?;
+    sys.evolve(
+        &mut state,
+        EvolveBounds::default()
+            .for_events(100000)
+            .parallel_domain_size(32),
+    )?;
+    sys.validate_rates(&state)?;
+    assert_eq!(state.n_tiles(), 95 * 95);
+
+    let (sys, mut exact) = ts.create_system_and_state()?;
+    sys.evolve(&mut exact, EvolveBounds::default().for_events(20000))?;
+    assert_eq!(state.raw_array(), exact.raw_array());
+
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.size = Some(Size::Single(128));
+    ts.seed = Some(Seed::Single(64, 64, 1.into()));
+    let (sys, mut state) = ts.create_system_and_state()?;
+    let events = state.total_events();
+    sys.evolve(
+        &mut state,
+        EvolveBounds::default()
+            .for_time(2000.)
+            .parallel_domain_size(32),
+    )?;
+    sys.validate_rates(&state)?;
+    assert!(state.total_events() > events);
+    assert!((state.time() - 2000.).abs() < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn parallel_domains_statistics_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.size = Some(Size::Single(64));
+    ts.seed = Some(Seed::Single(48, 48, 1.into()));
+
+    const NRUNS: usize = 20;
+
+    // Mean and standard error of the assembly size after a fixed time.
+    let size_stats = |bounds: EvolveBounds| -> Result<(f64, f64)> {
+        let sizes = (0..NRUNS)
+            .map(|_| {
+                let (sys, mut state) = ts.create_system_and_state()?;
+                sys.evolve(&mut state, bounds)?;
+                Ok(state.n_tiles() as f64)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mean = sizes.iter().sum::<f64>() / NRUNS as f64;
+        let var = sizes.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (NRUNS - 1) as f64;
+        Ok((mean, (var / NRUNS as f64).sqrt()))
+    };
+
+    // Growth crosses several domain boundaries in this time.
+    let bounds = EvolveBounds::default().for_time(1000.);
+    let (serial, serial_se) = size_stats(bounds)?;
+    let (parallel, parallel_se) = size_stats(bounds.parallel_domain_size(16))?;
+
+    assert!(
+        (serial - parallel).abs() < 4. * serial_se.hypot(parallel_se),
+        "serial mean size {serial} ± {serial_se}, parallel {parallel} ± {parallel_se}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn template_include_test() -> Result<()> {
+    let mut ts = TileSet::from_file("examples/sierpinski-template.yaml")?;
+
+    assert_eq!(ts.tiles.len(), 7);
+
+    ts.model = Some(rgrow::tileset::Model::ATAM);
+    ts.size = Some(Size::Single(64));
+    let p = PointSafe2((60, 60));
+    ts.seed = Some(Seed::Single(
+        p.0 .0,
+        p.0 .1,
+        TileIdent::Name("corner".to_string()),
+    ));
+
+    let (sys, mut state) = ts.create_system_and_state()?;
+
+    sys.evolve(
+        &mut state,
+        EvolveBounds {
+            size_max: Some(500),
+            for_events: Some(2000),
+            ..Default::default()
+        },
+    )?;
+
+    let name_at = |y, x| {
+        sys.tile_name(state.tile_at_point(PointSafe2((y, x))))
+            .to_string()
+    };
+    assert_eq!(name_at(p.0 .0 - 7, p.0 .1 - 7), "rule_00");
+    assert_eq!(name_at(p.0 .0 - 8, p.0 .1 - 8), "rule_11");
+
+    Ok(())
+}
+
+#[test]
+fn missing_include_test() -> Result<()> {
+    let path = std::env::temp_dir().join("rgrow-missing-include.yaml");
+    std::fs::write(&path, "include: [no-such-file.yaml]\ntiles: []\n")?;
+    let err = TileSet::from_file(&path).unwrap_err();
+    std::fs::remove_file(&path)?;
+    assert!(format!("{err:#}").contains("Failed to include no-such-file.yaml"));
+    Ok(())
+}
+
+#[test]
+fn unresolved_include_test() -> Result<()> {
+    let s = "include: [sierpinski.yaml]\ntiles: []\n";
+    assert!(TileSet::from_yaml(s).is_err());
+    assert!(TileSet::from_json(r#"{"include": ["sierpinski.yaml"], "tiles": []}"#).is_err());
+
+    let mut ts: TileSet = serde_yaml::from_str(s)?;
+    assert!(ts.create_dynsystem().is_err());
+    ts.resolve_includes("examples")?;
+    assert!(!ts.tiles.is_empty());
+    ts.create_dynsystem()?;
+    Ok(())
+}
+
+#[test]
+fn included_xgrow_ignored_options_test() -> Result<()> {
+    let dir = std::env::temp_dir();
+    let xgrow = dir.join(format!("rgrow-ignored-{}.tiles", std::process::id()));
+    let yaml = dir.join(format!("rgrow-ignored-{}.yaml", std::process::id()));
+    std::fs::write(
+        &xgrow,
+        "num tile types=1\nnum binding types=1\ntile edges={\n{1 0 0 1}\n}\nanneal=10,1000\n",
+    )?;
+    std::fs::write(
+        &yaml,
+        format!(
+            "include: [{}]\ntiles: []\n",
+            xgrow.file_name().unwrap().to_str().unwrap()
+        ),
+    )?;
+    let res = TileSet::from_file_with_ignored(&yaml);
+    std::fs::remove_file(&xgrow)?;
+    std::fs::remove_file(&yaml)?;
+    let (ts, ignored) = res?;
+
+    assert_eq!(ts.tiles.len(), 1);
+    assert_eq!(
+        ignored,
+        vec![IgnoredOption::Unsupported("anneal=10,1000".to_string())]
+    );
+    Ok(())
+}
+
 #[test]
 fn ktam_test() -> Result<()> {
     let mut ts = get_sierpinski()?;
@@ -130,6 +388,127 @@ fn ktam_test() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn tracked_counts_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.size = Some(Size::Single(64));
+    ts.seed = Some(Seed::Single(60, 60, 1.into()));
+
+    let sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+    let mut st = sys.new_state::<QuadTreeState<CanvasPeriodic, NullStateTracker>>((64, 64))?;
+    System::track_counts(&sys, &mut st);
+
+    for _ in 0..10 {
+        System::evolve(
+            &sys,
+            &mut st,
+            EvolveBounds {
+                for_events: Some(2000),
+                ..Default::default()
+            },
+        )?;
+
+        assert_eq!(
+            st.mismatch_count(),
+            Some(System::calc_mismatches(&sys, &st))
+        );
+
+        let counts = st.tile_counts().unwrap();
+        let mut expected = vec![0; counts.len()];
+        for t in st.raw_array().iter().filter(|t| **t > 0) {
+            expected[*t as usize] += 1;
+        }
+        assert_eq!(counts.to_vec(), expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn oldktam_periodic_mismatch_count_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::OldKTAM);
+    ts.size = Some(Size::Single(32));
+    ts.seed = Some(Seed::Single(16, 16, 1.into()));
+
+    let sys = rgrow::models::oldktam::OldKTAM::from_tileset(&ts)?;
+    let mut st = sys.new_state::<QuadTreeState<CanvasPeriodic, NullStateTracker>>((32, 32))?;
+    System::track_counts(&sys, &mut st);
+
+    // The south side of tile 5 (v1) faces the north side of tile 4 (v0) across the periodic
+    // boundary between the last and first rows.
+    for (p, t) in [((31, 5), 5), ((0, 5), 4)] {
+        System::perform_event_with_counts(
+            &sys,
+            &mut st,
+            &Event::MonomerAttachment(PointSafe2(p), t),
+        );
+    }
+
+    assert_eq!(System::calc_mismatches(&sys, &st), 1);
+    assert_eq!(st.mismatch_count(), Some(1));
+
+    System::evolve(
+        &sys,
+        &mut st,
+        EvolveBounds {
+            for_events: Some(5000),
+            ..Default::default()
+        },
+    )?;
+
+    assert_eq!(
+        st.mismatch_count(),
+        Some(System::calc_mismatches(&sys, &st))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn event_stats_test() -> Result<()> {
+    let mut ts = get_sierpinski()?;
+
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.size = Some(Size::Single(64));
+    ts.seed = Some(Seed::Single(60, 60, 1.into()));
+    ts.fission = Some(FissionHandling::KeepLargest);
+
+    let sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+    let mut st = sys.new_state::<QuadTreeState<CanvasPeriodic, NullStateTracker>>((64, 64))?;
+    System::track_event_stats(&sys, &mut st);
+
+    for bounds in [
+        EvolveBounds::default().for_events(5000),
+        EvolveBounds::default().for_events(5000).leap_tolerance(0.1),
+    ] {
+        let (events, ntiles) = (st.total_events(), st.n_tiles());
+        let before = st.event_stats().unwrap().clone();
+        System::evolve(&sys, &mut st, bounds)?;
+        let stats = st.event_stats().unwrap();
+
+        assert_eq!(
+            (stats.total() - stats.dead_events) - (before.total() - before.dead_events),
+            st.total_events() - events
+        );
+        assert!(stats.attachments > before.attachments);
+        assert!(stats.detachments > before.detachments);
+
+        // Sierpinski has no duples or tile changes, so per-tile counts account for every tile.
+        let attached = stats.tile_attachments.sum() - before.tile_attachments.sum();
+        let detached = stats.tile_detachments.sum() - before.tile_detachments.sum();
+        assert_eq!(
+            st.n_tiles() as i64 - ntiles as i64,
+            attached as i64 - detached as i64
+        );
+    }
+
+    Ok(())
+}
+
 #[test]
 fn ktam_barish_test() -> Result<()> {
     let mut ts = TileSet::from_file("examples/barish-perfect.yaml")?;
@@ -232,14 +611,7 @@ fn simple_fission_test() -> Result<()> {
 
 #[test]
 fn nucrate_test() -> Result<()> {
-    let mut ts: TileSet = serde_yaml::from_reader(File::open("examples/barish-perfect.yaml")?)?;
-
-    ts.alpha = Some(-7.1);
-    ts.gse = Some(5.7);
-    ts.gmc = Some(9.7);
-    ts.model = Some(rgrow::tileset::Model::KTAM);
-    ts.canvas_type = Some(rgrow::tileset::CanvasType::Periodic);
-    ts.fission = Some(rgrow::system::FissionHandling::KeepLargest);
+    let ts = get_barish_ffs()?;
 
     let conf = rgrow::ffs::FFSRunConfig {
         max_configs: 100,
@@ -251,7 +623,774 @@ fn nucrate_test() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "use_rayon")]
+#[test]
+fn ffs_seed_test() -> Result<()> {
+    let ts = get_barish_ffs()?;
+
+    let conf = rgrow::ffs::FFSRunConfig {
+        min_configs: 50,
+        max_configs: 100,
+        target_size: 20,
+        seed: Some(42),
+        ..Default::default()
+    };
+
+    // With a seed, results shouldn't depend on how many threads trials run on.
+    let runs = [1, 4]
+        .into_iter()
+        .map(|n| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(n).build()?;
+            Ok(pool.install(|| ts.run_ffs(&conf))?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    assert_eq!(runs[0].forward_vec(), runs[1].forward_vec());
+    for (a, b) in runs[0].surfaces().iter().zip(runs[1].surfaces()) {
+        assert_eq!(a.num_trials(), b.num_trials());
+        assert_eq!(a.previous_list(), b.previous_list());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ffs_confidence_interval_test() -> Result<()> {
+    let ts = get_barish_ffs()?;
+
+    let conf = rgrow::ffs::FFSRunConfig {
+        min_configs: 100,
+        max_configs: 200,
+        target_size: 20,
+        seed: Some(7),
+        ..Default::default()
+    };
+    let res = ts.run_ffs(&conf)?;
+    let surfaces = res.surfaces();
+
+    // Every trial after the first surface starts from a configuration of the previous one.
+    for (prev, surface) in surfaces[1..].iter().zip(&surfaces[2..]) {
+        let parent_trials = surface.parent_trials();
+        assert_eq!(parent_trials.len(), prev.num_states());
+        assert_eq!(parent_trials.iter().sum::<usize>(), surface.num_trials());
+    }
+
+    let intervals = res.forward_prob_intervals(0.95);
+    assert_eq!(intervals.len(), res.forward_vec().len());
+    for (interval, &p) in intervals.iter().zip(res.forward_vec()) {
+        assert!(interval.contains(p));
+        assert!((interval.lower >= 0.) & (interval.upper <= 1.));
+    }
+
+    let rate = res.nucleation_rate();
+    let interval = res.nucleation_rate_interval(0.95);
+    assert!(interval.contains(rate) & (interval.lower < rate));
+
+    // One standard deviation, on a log scale.
+    let sigma = res.nucleation_rate_relative_variance().ln_1p().sqrt();
+    let one_sigma = res.nucleation_rate_interval(0.682_689_492);
+    assert!(((one_sigma.upper / rate).ln() - sigma).abs() < 1e-6 * sigma);
+
+    let bootstrap = res.nucleation_rate_bootstrap(0.95, 500, Some(1));
+    assert_eq!(bootstrap, res.nucleation_rate_bootstrap(0.95, 500, Some(1)));
+    assert!(bootstrap.contains(rate));
+    assert!(bootstrap.upper / bootstrap.lower < 100. * interval.upper / interval.lower);
+
+    Ok(())
+}
+
+#[test]
+fn ffs_save_resume_test() -> Result<()> {
+    use rgrow::ffs::{FFSDataError, FFSResult, FFSRunConfig, FFSRunData};
+
+    let ts = get_barish_ffs()?;
+
+    let path = std::env::temp_dir().join(format!("rgrow-ffs-test-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let conf = FFSRunConfig {
+        min_configs: 50,
+        max_configs: 100,
+        target_size: 20,
+        early_cutoff: false,
+        seed: Some(3),
+        ..Default::default()
+    };
+    let full = ts.run_ffs(&conf)?;
+
+    // A run stopped partway, as if interrupted, and checkpointed.
+    let partial = ts.run_ffs(&FFSRunConfig {
+        target_size: 10,
+        checkpoint: Some(path.to_string()),
+        ..conf.clone()
+    })?;
+    let data = FFSRunData::load(path)?;
+    std::fs::remove_file(path)?;
+
+    assert_eq!(data.forward_vec(), partial.forward_vec());
+    assert_eq!(data.nucleation_rate(), partial.nucleation_rate());
+    for (a, b) in data.surfaces().iter().zip(partial.surfaces()) {
+        assert_eq!(a.num_configs(), b.num_configs());
+        assert_eq!(a.configs(), b.configs());
+        assert_eq!(a.previous_list(), b.previous_list());
+        assert_eq!(a.parent_trials(), b.parent_trials());
+    }
+
+    // Corrupt data is rejected when loaded, rather than panicking when resumed.
+    let mut corrupt = data.clone();
+    corrupt.forward_prob.pop();
+    corrupt.save(path)?;
+    let loaded = FFSRunData::load(path);
+    std::fs::remove_file(path)?;
+    assert!(matches!(loaded, Err(FFSDataError::Inconsistent(_))));
+    assert!(ts.resume_ffs(&corrupt, &conf).is_err());
+
+    let resumed = ts.resume_ffs(&data, &conf)?;
+    assert_eq!(resumed.forward_vec(), full.forward_vec());
+    assert_eq!(resumed.config().target_size, 20);
+    for (a, b) in resumed.surfaces().iter().zip(full.surfaces()) {
+        assert_eq!(a.num_trials(), b.num_trials());
+        assert_eq!(a.previous_list(), b.previous_list());
+    }
+
+    // Resuming runs that have already stopped, by early cutoff or by a minimum nucleation rate,
+    // adds no surfaces.
+    for stopping in [
+        FFSRunConfig {
+            early_cutoff: true,
+            target_size: 60,
+            min_cutoff_size: 15,
+            ..conf.clone()
+        },
+        FFSRunConfig {
+            min_nuc_rate: Some(1.0),
+            ..conf.clone()
+        },
+    ] {
+        let stopping = FFSRunConfig {
+            checkpoint: Some(path.to_string()),
+            ..stopping
+        };
+        let stopped = ts.run_ffs(&stopping)?;
+        let data = FFSRunData::load(path)?;
+        std::fs::remove_file(path)?;
+        assert!(stopped.surfaces().last().unwrap().target_size() < stopping.target_size);
+
+        let resumed = ts.resume_ffs(&data, &stopping)?;
+        assert!(!std::path::Path::new(path).exists());
+        assert_eq!(resumed.surfaces().len(), stopped.surfaces().len());
+        for (a, b) in resumed.surfaces().iter().zip(stopped.surfaces()) {
+            assert_eq!(a.num_trials(), b.num_trials());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ffs_seeded_test() -> Result<()> {
+    use rgrow::ffs::{FFSResult, FFSRun, FFSRunConfig};
+    use rgrow::models::ktam::KTAM;
+
+    let mut ts = TileSet::from_file("examples/sierpinski.yaml")?;
+    ts.size = Some(Size::Single(32));
+    ts.seed = Some(Seed::Single(28, 28, 1.into()));
+    ts.gmc = Some(15.8);
+
+    let mut sys = KTAM::from_tileset(&ts)?;
+    let conf = FFSRunConfig {
+        from_seed: true,
+        start_size: 3,
+        target_size: 12,
+        min_configs: 30,
+        max_configs: 60,
+        early_cutoff: false,
+        keep_configs: true,
+        canvas_size: (32, 32),
+        seed: Some(5),
+        ..Default::default()
+    };
+
+    type St = QuadTreeState<rgrow::canvas::CanvasSquare, NullStateTracker>;
+    let run = FFSRun::<St>::create(&mut sys, &conf)?;
+
+    assert_eq!(run.basin(), Some(0));
+    assert_eq!(run.level_list[0].state_list.len(), 1);
+    assert!(run.dimerization_rate > 0.);
+    assert!(run.nucleation_rate() > 0.);
+    let last = run.level_list.last().unwrap();
+    assert_eq!(last.target_size, 12);
+    for state in &last.state_list {
+        assert_eq!(state.tile_at_point(PointSafe2((28, 28))), 1);
+        assert!(state.n_tiles() >= 12);
+    }
+
+    // Runs from an ensemble of the states that reached the first surface.
+    let start: Vec<St> = run.level_list[1].state_list.clone();
+    let run2 = FFSRun::<St>::create_from_states(
+        &mut sys,
+        start,
+        &FFSRunConfig {
+            start_size: 5,
+            ..conf.clone()
+        },
+    )?;
+    assert_eq!(run2.basin(), Some(3));
+
+    assert!(FFSRun::<St>::create(
+        &mut sys,
+        &FFSRunConfig {
+            start_size: 0,
+            ..conf
+        }
+    )
+    .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn ffs_committor_test() -> Result<()> {
+    use rgrow::ffs::{CommittorAnalysis, CommittorConfig, FFSRun, FFSRunConfig};
+    use rgrow::models::ktam::KTAM;
+
+    let mut ts = TileSet::from_file("examples/sierpinski.yaml")?;
+    ts.size = Some(Size::Single(32));
+    ts.seed = Some(Seed::Single(28, 28, 1.into()));
+    ts.gmc = Some(15.8);
+
+    let mut sys = KTAM::from_tileset(&ts)?;
+    let conf = FFSRunConfig {
+        from_seed: true,
+        start_size: 2,
+        target_size: 10,
+        min_configs: 20,
+        max_configs: 40,
+        early_cutoff: false,
+        keep_configs: true,
+        canvas_size: (32, 32),
+        seed: Some(3),
+        ..Default::default()
+    };
+
+    type St = QuadTreeState<rgrow::canvas::CanvasSquare, NullStateTracker>;
+    let run = FFSRun::<St>::create(&mut sys, &conf)?;
+
+    let analysis = CommittorAnalysis::calculate::<St, _>(
+        &sys,
+        &run,
+        &CommittorConfig {
+            trials: 20,
+            max_configs: 5,
+            window: 0.5,
+            seed: Some(3),
+            ..Default::default()
+        },
+    )?;
+
+    assert_eq!(analysis.target, 10);
+    // The starting state and every surface below the target.
+    assert_eq!(analysis.surfaces.len(), 9);
+    for surface in &analysis.surfaces {
+        assert!(surface.configs.len() <= 5);
+        assert_eq!(surface.configs.len(), surface.committors.len());
+        assert!(surface.committors.iter().all(|p| (0. ..=1.).contains(p)));
+    }
+    assert!(analysis.surfaces[0].mean() < analysis.surfaces.last().unwrap().mean());
+
+    // With a window of 1/2, every analyzed configuration is counted.
+    let nuclei = &analysis.critical_nuclei;
+    assert_eq!(
+        nuclei.count,
+        analysis
+            .surfaces
+            .iter()
+            .map(|s| s.configs.len())
+            .sum::<usize>()
+    );
+    assert_eq!(nuclei.count, analysis.critical_configs.len());
+    assert_eq!(nuclei.tile_counts.len(), sys.tile_names().len());
+    // Every configuration contains the seed tile.
+    assert_eq!(nuclei.tile_frequency[1], 1.);
+    assert!(nuclei.mean_tiles >= 1.);
+    assert!(nuclei.mean_height >= 1. && nuclei.mean_width >= 1.);
+
+    Ok(())
+}
+
+#[test]
+fn ffs_progress_test() -> Result<()> {
+    use rgrow::ffs::{FFSProgress, FFSProgressCallback, FFSResult, FFSRun, FFSRunConfig};
+    use std::sync::{Arc, Mutex};
+
+    let ts = get_barish_ffs()?;
+    let mut sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+
+    let reports: Arc<Mutex<Vec<FFSProgress>>> = Default::default();
+    let r = reports.clone();
+    let conf = FFSRunConfig {
+        target_size: 20,
+        min_configs: 50,
+        max_configs: 100,
+        early_cutoff: false,
+        seed: Some(11),
+        // Stops the run at the third surface.
+        progress: Some(FFSProgressCallback::new(move |p| {
+            r.lock().unwrap().push(p.clone());
+            p.surface < 3
+        })),
+        ..Default::default()
+    };
+
+    let run = FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::create(&mut sys, &conf)?;
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 3);
+    assert_eq!(run.surfaces().len(), 4);
+    for (report, surface) in reports.iter().zip(&run.surfaces()[1..]) {
+        assert_eq!(report.target_size, surface.target_size());
+        assert_eq!(report.num_trials, surface.num_trials());
+        assert_eq!(report.num_successes, surface.num_states());
+    }
+    let last = reports.last().unwrap();
+    assert_eq!(last.forward_prob, *run.forward_vec().last().unwrap());
+    assert_eq!(last.nucleation_rate, run.nucleation_rate());
+
+    // A failing callback fails the run.
+    let failing = FFSRunConfig {
+        progress: Some(FFSProgressCallback::try_new(|p| {
+            if p.surface < 2 {
+                Ok(true)
+            } else {
+                Err(rgrow::base::GrowError::NotImplemented("stop".to_string()))
+            }
+        })),
+        ..conf.clone()
+    };
+    match FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::create(&mut sys, &failing) {
+        Err(err) => assert_eq!(err.to_string(), "stop"),
+        Ok(_) => panic!("run should have failed"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ffs_order_parameter_test() -> Result<()> {
+    use rgrow::ffs::{FFSOrderParameter, FFSResult, FFSRun, FFSRunConfig, OrderParameter};
+
+    let ts = get_barish_ffs()?;
+
+    let mut sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+    let order = FFSOrderParameter::StrongBonds(5.);
+    let conf = FFSRunConfig {
+        min_configs: 30,
+        max_configs: 60,
+        start_size: 2,
+        target_size: 16,
+        keep_configs: true,
+        early_cutoff: false,
+        order_parameter: order.clone(),
+        seed: Some(1),
+        ..Default::default()
+    };
+
+    let run = FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::create(&mut sys, &conf)?;
+    assert!(run.level_list.last().unwrap().target_size >= 16);
+    for level in &run.level_list[1..] {
+        for state in &level.state_list {
+            assert!(order.calc(&sys, state) >= level.target_size);
+            // Every tile type counted gives the tile count.
+            let all = FFSOrderParameter::TileSubset((1..sys.tile_names().len() as u32).collect());
+            assert_eq!(all.calc(&sys, state), state.calc_n_tiles());
+        }
+    }
+    assert!(run.nucleation_rate() > 0.);
+
+    // Non-tile-count order parameters are tracked with exact single steps.
+    let leaping = FFSRunConfig {
+        subseq_bound: EvolveBounds {
+            leap_tolerance: Some(0.01),
+            ..conf.subseq_bound
+        },
+        ..conf.clone()
+    };
+    assert!(
+        FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::create(&mut sys, &leaping)
+            .is_err()
+    );
+
+    Ok(())
+}
+
+/// Tiles in even rows, as an order parameter that isn't built in.
+#[derive(Debug)]
+struct EvenRowTiles;
+
+impl rgrow::ffs::OrderParameter for EvenRowTiles {
+    fn at_point(
+        &self,
+        _system: &dyn rgrow::system::SystemWithDimers,
+        state: &dyn rgrow::state::State,
+        p: PointSafe2,
+    ) -> NumTiles {
+        ((p.0 .0 & 1 == 0) && (state.tile_at_point(p) != 0)) as NumTiles
+    }
+}
+
+#[test]
+fn ffs_custom_order_parameter_test() -> Result<()> {
+    use rgrow::ffs::{FFSOrderParameter, FFSResult, FFSRun, FFSRunConfig, OrderParameter};
+    use std::sync::Arc;
+
+    let ts = get_barish_ffs()?;
+
+    let mut sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+    let conf = FFSRunConfig {
+        min_configs: 30,
+        max_configs: 60,
+        start_size: 2,
+        target_size: 10,
+        keep_configs: true,
+        early_cutoff: false,
+        order_parameter: FFSOrderParameter::Custom(Arc::new(EvenRowTiles)),
+        seed: Some(1),
+        ..Default::default()
+    };
+
+    let run = FFSRun::<QuadTreeState<CanvasPeriodic, NullStateTracker>>::create(&mut sys, &conf)?;
+    assert!(run.level_list.last().unwrap().target_size >= 10);
+    for level in &run.level_list[1..] {
+        for state in &level.state_list {
+            assert!(EvenRowTiles.calc(&sys, state) >= level.target_size);
+        }
+    }
+    assert!(run.nucleation_rate() > 0.);
+
+    Ok(())
+}
+
+#[test]
+fn umbrella_test() -> Result<()> {
+    use rgrow::ffs::{FFSResult, FFSRun, FFSRunConfig};
+    use rgrow::umbrella::{FreeEnergyProfile, UmbrellaConfig};
+
+    // A 2×2 periodic crystal with strength-1 glues, which only nucleates past a barrier.
+    let mut ts: TileSet = serde_yaml::from_str(
+        "tiles:
+- {name: A, edges: [n1, e1, s1, w1]}
+- {name: B, edges: [n2, w1, s2, e1]}
+- {name: C, edges: [s1, e2, n1, w2]}
+- {name: D, edges: [s2, w2, n2, e2]}
+",
+    )?;
+    ts.alpha = Some(0.);
+    ts.gse = Some(5.0);
+    ts.gmc = Some(8.0);
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.fission = Some(FissionHandling::NoFission);
+    let mut sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+
+    type St = QuadTreeState<CanvasPeriodic, NullStateTracker>;
+    let config = UmbrellaConfig {
+        max_size: 16,
+        seed: Some(3),
+        ..Default::default()
+    };
+    let profile = FreeEnergyProfile::calculate::<St, _>(&sys, None, &config)?;
+
+    assert_eq!(profile.sizes, (2..=16).collect::<Vec<_>>());
+    assert_eq!(profile.windows.first(), Some(&(2, 4)));
+    assert_eq!(profile.windows.last(), Some(&(14, 16)));
+    assert_eq!(profile.free_energy[0], 0.);
+    assert!(profile.free_energy.iter().all(|g| g.is_finite()));
+    assert!(profile.counts.iter().all(|&c| c > 0));
+
+    let critical = profile.critical_size.unwrap();
+    let barrier = profile.barrier.unwrap();
+    assert!((3..=12).contains(&critical), "{critical}");
+    assert!(barrier > 0.);
+    assert!(*profile.free_energy.last().unwrap() < 0.);
+
+    // Becker–Döring along tile count should overestimate the FFS rate, but not by much.
+    let ffs = FFSRun::<St>::create(
+        &mut sys,
+        &FFSRunConfig {
+            target_size: 20,
+            min_configs: 200,
+            seed: Some(3),
+            ..Default::default()
+        },
+    )?;
+    let ratio = profile.nucleation_rate(ffs.dimerization_rate()).unwrap() / ffs.nucleation_rate();
+    assert!((0.3..100.).contains(&ratio), "{ratio}");
+
+    for bad in [
+        UmbrellaConfig {
+            min_size: 0,
+            ..config.clone()
+        },
+        UmbrellaConfig {
+            min_size: 17,
+            ..config.clone()
+        },
+        UmbrellaConfig {
+            window_overlap: 3,
+            ..config.clone()
+        },
+    ] {
+        assert!(FreeEnergyProfile::calculate::<St, _>(&sys, None, &bad).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn nmers_test() -> Result<()> {
+    use rgrow::system::SystemWithDimers;
+
+    // A 2×2 periodic crystal with unique glues.
+    let mut ts: TileSet = serde_yaml::from_str(
+        "tiles:
+- {name: A, edges: [n1, e1, s1, w1]}
+- {name: B, edges: [n2, w1, s2, e1]}
+- {name: C, edges: [s1, e2, n1, w2]}
+- {name: D, edges: [s2, w2, n2, e2]}
+",
+    )?;
+    ts.alpha = Some(-2.);
+    ts.gse = Some(5.0);
+    ts.gmc = Some(8.0);
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    let ktam = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+    let nmers = ktam.calc_nmers(4);
+
+    let of_size = |n| nmers.iter().filter(|x| x.size == n).collect::<Vec<_>>();
+    assert_eq!(of_size(2).len(), ktam.calc_dimers().len());
+    assert_eq!(of_size(2).len(), 8);
+    // Four straight trimers in each direction, and four of each L shape.
+    assert_eq!(of_size(3).len(), 24);
+    let squares: Vec<_> = of_size(4)
+        .into_iter()
+        .filter(|x| x.config().dim() == (2, 2))
+        .collect();
+    assert_eq!(squares.len(), 4);
+
+    let monomer = f64::exp(-8.);
+    for dimer in of_size(2) {
+        assert_eq!(dimer.bond_energy, 5.0);
+        assert!((dimer.equilibrium_conc / (monomer * monomer * f64::exp(5.)) - 1.).abs() < 1e-9);
+    }
+    for square in &squares {
+        assert_eq!(square.bond_energy, 20.0);
+        // Detailed balance: each corner detaches with two bonds.
+        let detachment = ktam.kf * 1e9 * f64::exp(-10. + ktam.alpha);
+        let expected = 4. * square.equilibrium_conc * detachment;
+        assert!((square.formation_rate / expected - 1.).abs() < 1e-9);
+    }
+
+    // The old kTAM implementation agrees.
+    ts.model = Some(rgrow::tileset::Model::OldKTAM);
+    let oldktam = rgrow::models::oldktam::OldKTAM::from_tileset(&ts)?;
+    let old_nmers = oldktam.calc_nmers(4);
+    assert_eq!(old_nmers.len(), nmers.len());
+    for (a, b) in nmers.iter().zip(&old_nmers) {
+        assert_eq!(a.tiles, b.tiles);
+        assert!((a.equilibrium_conc / b.equilibrium_conc - 1.).abs() < 1e-9);
+        assert!((a.formation_rate / b.formation_rate - 1.).abs() < 1e-9);
+    }
+
+    // The aTAM has no concentrations, and so no n-mers.
+    ts.model = Some(rgrow::tileset::Model::ATAM);
+    let atam = rgrow::models::atam::ATAM::from_tileset(&ts)?;
+    assert!(atam.calc_nmers(4).is_empty());
+
+    // A horizontal duple H, binding S on its east, which binds T.
+    let mut ts: TileSet = serde_yaml::from_str(
+        "tiles:
+- {name: H, edges: [0, 0, x, 0, 0, 0], shape: horizontal}
+- {name: S, edges: [0, y, 0, x]}
+- {name: T, edges: [0, 0, 0, y]}
+",
+    )?;
+    ts.gse = Some(5.0);
+    ts.gmc = Some(8.0);
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    let ktam = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+    let nmers = ktam.calc_nmers(4);
+
+    assert_eq!(nmers.len(), 3);
+    let hst = nmers.iter().find(|x| x.size == 3).unwrap();
+    assert_eq!(hst.config().dim(), (1, 4));
+    assert_eq!(hst.bond_energy, 10.0);
+    assert!((hst.equilibrium_conc / (monomer.powi(3) * f64::exp(10.)) - 1.).abs() < 1e-9);
+    // It forms by H attaching to ST, or T attaching to HS, but not by S attaching.
+    let dimer_conc = monomer * monomer * f64::exp(5.);
+    let attachment = ktam.kf * ktam.tile_concs[1];
+    assert!((hst.formation_rate / (2. * dimer_conc * attachment) - 1.).abs() < 1e-9);
+
+    Ok(())
+}
+
+#[test]
+fn ffs_structures_test() -> Result<()> {
+    use ndarray::array;
+    use rgrow::ffs::{
+        FFSResult, FFSRun, FFSRunConfig, StructureAnalysis, StructureConfig, StructurePattern,
+        TargetStructure,
+    };
+
+    // Two unrelated 2×2 periodic crystals, which nucleate at the same rate.
+    let mut ts: TileSet = serde_yaml::from_str(
+        "tiles:
+- {name: A, edges: [n1, e1, s1, w1]}
+- {name: B, edges: [n2, w1, s2, e1]}
+- {name: C, edges: [s1, e2, n1, w2]}
+- {name: D, edges: [s2, w2, n2, e2]}
+- {name: E, edges: [m1, f1, t1, x1]}
+- {name: F, edges: [m2, x1, t2, f1]}
+- {name: G, edges: [t1, f2, m1, x2]}
+- {name: H, edges: [t2, x2, m2, f2]}
+",
+    )?;
+    ts.alpha = Some(0.);
+    ts.gse = Some(5.0);
+    ts.gmc = Some(8.0);
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.fission = Some(FissionHandling::NoFission);
+    let mut sys = rgrow::models::ktam::KTAM::from_tileset(&ts)?;
+
+    type St = QuadTreeState<CanvasPeriodic, NullStateTracker>;
+    let run = FFSRun::<St>::create(
+        &mut sys,
+        &FFSRunConfig {
+            target_size: 16,
+            min_configs: 200,
+            early_cutoff: false,
+            keep_configs: true,
+            canvas_size: (32, 32),
+            seed: Some(5),
+            ..Default::default()
+        },
+    )?;
+
+    let structures = vec![
+        TargetStructure {
+            name: "first".to_string(),
+            pattern: StructurePattern::TileSubset(vec![1, 2, 3, 4], 0.9),
+        },
+        TargetStructure {
+            name: "second".to_string(),
+            pattern: StructurePattern::Template(array![[5, 6], [7, 8]]),
+        },
+    ];
+    let analysis = StructureAnalysis::calculate::<St, _>(
+        &sys,
+        &run,
+        &StructureConfig {
+            structures: structures.clone(),
+            ..Default::default()
+        },
+    )?;
+
+    assert_eq!(analysis.names, vec!["first", "second"]);
+    assert_eq!(analysis.surfaces.len(), run.surfaces().len());
+    for (surface, run_surface) in analysis.surfaces.iter().zip(run.surfaces()) {
+        assert_eq!(
+            surface.counts.iter().sum::<usize>() + surface.unmatched,
+            run_surface.num_configs()
+        );
+    }
+    // Dimers are too small to contain the template.
+    assert_eq!(analysis.surfaces[0].counts[1], 0);
+
+    let last = run.surfaces().last().unwrap().num_configs();
+    assert_eq!(analysis.outcomes.len(), last);
+    assert_eq!(analysis.dissolved, 0);
+    let total: f64 = analysis.nucleation_rates.iter().sum::<f64>() + analysis.unmatched_rate;
+    assert!((total / run.nucleation_rate() - 1.).abs() < 1e-9);
+    for (rate, interval) in analysis
+        .nucleation_rates
+        .iter()
+        .zip(&analysis.nucleation_rate_intervals)
+    {
+        assert!(
+            *rate > 0.2 * run.nucleation_rate(),
+            "{:?}",
+            analysis.nucleation_rates
+        );
+        assert!(interval.contains(*rate));
+    }
+
+    // Growing the assemblies further before classifying them.
+    let grown = StructureAnalysis::calculate::<St, _>(
+        &sys,
+        &run,
+        &StructureConfig {
+            structures: structures.clone(),
+            grow_to: Some(30),
+            seed: Some(5),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(grown.outcomes.len(), last);
+    let classified = grown.outcomes.iter().flatten().count();
+    assert!(classified + grown.dissolved <= last);
+    let total: f64 = grown.nucleation_rates.iter().sum::<f64>() + grown.unmatched_rate;
+    assert!((total / run.nucleation_rate() - 1.).abs() < 1e-9);
+    assert!(grown.dissolved < last / 2);
+
+    // Without configurations at the last surface, there is nothing to classify.
+    let mut data = run.to_data();
+    data.levels.last_mut().unwrap().configs.clear();
+    let empty = StructureAnalysis::calculate::<St, _>(
+        &sys,
+        &data,
+        &StructureConfig {
+            structures: structures.clone(),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        empty,
+        Err(rgrow::base::GrowError::FFSNoAssembliesToClassify)
+    ));
+
+    // Nor without any surfaces.
+    data.levels.clear();
+    let empty = StructureAnalysis::calculate::<St, _>(
+        &sys,
+        &data,
+        &StructureConfig {
+            structures,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        empty,
+        Err(rgrow::base::GrowError::FFSNoAssembliesToClassify)
+    ));
+
+    Ok(())
+}
+
 fn get_sierpinski() -> Result<TileSet> {
     serde_yaml::from_reader(File::open("examples/sierpinski.yaml")?)
         .context("Failure opening sierpinski example.")
 }
+
+/// The barish-perfect tileset, with kTAM parameters under which FFS surfaces are quick to
+/// generate.
+fn get_barish_ffs() -> Result<TileSet> {
+    let mut ts: TileSet = serde_yaml::from_reader(File::open("examples/barish-perfect.yaml")?)
+        .context("Failure opening barish-perfect example.")?;
+
+    ts.alpha = Some(-7.1);
+    ts.gse = Some(5.7);
+    ts.gmc = Some(9.7);
+    ts.model = Some(rgrow::tileset::Model::KTAM);
+    ts.canvas_type = Some(rgrow::tileset::CanvasType::Periodic);
+    ts.fission = Some(FissionHandling::KeepLargest);
+
+    Ok(ts)
+}